@@ -0,0 +1,674 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+/// The cases checked against a signer, grouped by protocol method.
+
+use std::{convert::TryFrom, fmt::Display, future::Future, time::Duration};
+
+use sp_core::{
+	crypto::{CryptoTypeId, CryptoTypePublicPair, KeyTypeId, Pair as _},
+	ecdsa, ed25519, sr25519,
+};
+use sp_keystore::vrf::{make_transcript, VRFSignature, VRFTranscriptData, VRFTranscriptValue};
+use se_remote_signer::{
+	Capabilities, HexBytes, METHODS, PublicPair, RemoteSignerApiClient as Client,
+	TransferableVRFTranscriptData,
+};
+
+/// Key type of the keys generated for the checks.
+const KEY_TYPE: KeyTypeId = KeyTypeId(*b"tsct");
+/// Key type no key is ever generated for.
+const UNKNOWN_KEY_TYPE: KeyTypeId = KeyTypeId(*b"tscu");
+/// Crypto scheme no signer knows.
+const WRONG_CRYPTO_ID: CryptoTypeId = CryptoTypeId(*b"nope");
+/// Seed of the keys generated for the checks, derived locally for comparison.
+const SEED: &str = "//tssrs-conformance";
+/// Seed of the key inserted by `signer_insert_unknown`.
+const INSERTED: &str = "//tssrs-conformance//inserted";
+/// Seed of a key the signer never sees.
+const UNKNOWN: &str = "//tssrs-conformance//unknown";
+/// Size of the large message to sign.
+const LARGE_MESSAGE: usize = 1024 * 1024;
+/// Engine id BABE labels its VRF transcripts with.
+const BABE_ENGINE_ID: &[u8] = b"BABE";
+const MESSAGE: &[u8] = b"tssrs conformance";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Scheme {
+	Sr25519,
+	Ed25519,
+	Ecdsa,
+}
+
+const SCHEMES: [Scheme; 3] = [Scheme::Sr25519, Scheme::Ed25519, Scheme::Ecdsa];
+
+impl Scheme {
+	fn id(self) -> CryptoTypeId {
+		match self {
+			Scheme::Sr25519 => sr25519::CRYPTO_ID,
+			Scheme::Ed25519 => ed25519::CRYPTO_ID,
+			Scheme::Ecdsa => ecdsa::CRYPTO_ID,
+		}
+	}
+
+	fn public_keys_method(self) -> &'static str {
+		match self {
+			Scheme::Sr25519 => "signer_sr25519_public_keys",
+			Scheme::Ed25519 => "signer_ed25519_public_keys",
+			Scheme::Ecdsa => "signer_ecdsa_public_keys",
+		}
+	}
+
+	fn generate_method(self) -> &'static str {
+		match self {
+			Scheme::Sr25519 => "signer_sr25519_generate_new",
+			Scheme::Ed25519 => "signer_ed25519_generate_new",
+			Scheme::Ecdsa => "signer_ecdsa_generate_new",
+		}
+	}
+
+	/// Public key of `suri`, as the signer should derive it.
+	fn derive(self, suri: &str) -> Vec<u8> {
+		match self {
+			Scheme::Sr25519 => sr25519::Pair::from_string(suri, None)
+				.expect("Seeds are valid; qed").public().0.to_vec(),
+			Scheme::Ed25519 => ed25519::Pair::from_string(suri, None)
+				.expect("Seeds are valid; qed").public().0.to_vec(),
+			Scheme::Ecdsa => ecdsa::Pair::from_string(suri, None)
+				.expect("Seeds are valid; qed").public().0.to_vec(),
+		}
+	}
+
+	fn verify(self, signature: &[u8], msg: &[u8], public: &[u8]) -> bool {
+		match self {
+			Scheme::Sr25519 => sr25519::Pair::verify_weak(signature, msg, public),
+			Scheme::Ed25519 => ed25519::Pair::verify_weak(signature, msg, public),
+			// `verify_weak` compares against the uncompressed public key.
+			Scheme::Ecdsa => match (ecdsa::Public::try_from(public), ecdsa::Signature::try_from(signature)) {
+				(Ok(public), Ok(signature)) => ecdsa::Pair::verify(&signature, msg, &public),
+				_ => false,
+			},
+		}
+	}
+}
+
+/// A key the signer holds, of [`KEY_TYPE`].
+#[derive(Clone)]
+struct Key {
+	scheme: Scheme,
+	public: Vec<u8>,
+}
+
+impl Key {
+	fn pair(&self) -> PublicPair {
+		PublicPair(CryptoTypePublicPair(self.scheme.id(), self.public.clone()))
+	}
+}
+
+fn ensure(condition: bool, failure: impl FnOnce() -> String) -> Result<(), String> {
+	if condition { Ok(()) } else { Err(failure()) }
+}
+
+/// A call that must be rejected.
+fn rejected<T>(result: Result<T, String>) -> Result<(), String> {
+	ensure(result.is_err(), || "Succeeded, but must fail".into())
+}
+
+enum Outcome {
+	Pass,
+	Fail(String),
+	Skip(String),
+}
+
+struct Case {
+	method: &'static str,
+	name: String,
+	outcome: Outcome,
+}
+
+/// Outcome of every case checked.
+#[derive(Default)]
+pub struct Report {
+	cases: Vec<Case>,
+}
+
+impl Report {
+	fn check(&mut self, method: &'static str, name: impl Into<String>, result: Result<(), String>) {
+		let outcome = match result {
+			Ok(()) => Outcome::Pass,
+			Err(e) => Outcome::Fail(e),
+		};
+		self.cases.push(Case { method, name: name.into(), outcome });
+	}
+
+	fn skip(&mut self, method: &'static str, name: impl Into<String>, reason: impl Into<String>) {
+		self.cases.push(Case { method, name: name.into(), outcome: Outcome::Skip(reason.into()) });
+	}
+
+	/// Whether no case failed.
+	pub fn passed(&self) -> bool {
+		!self.cases.iter().any(|c| matches!(c.outcome, Outcome::Fail(_)))
+	}
+
+	/// Print one line per method and the cases that did not pass.
+	pub fn print(&self, verbose: bool) {
+		for method in METHODS {
+			let cases = self.cases.iter().filter(|c| c.method == *method).collect::<Vec<_>>();
+			let passed = cases.iter().filter(|c| matches!(c.outcome, Outcome::Pass)).count();
+			let failed = cases.iter().filter(|c| matches!(c.outcome, Outcome::Fail(_))).count();
+			let status = if failed > 0 {
+				"FAIL"
+			} else if passed == 0 {
+				"SKIP"
+			} else {
+				"PASS"
+			};
+			println!("{:<32} {}  {}/{} cases passed", method, status, passed, cases.len());
+			for case in cases {
+				match &case.outcome {
+					Outcome::Fail(e) => println!("    FAIL {}: {}", case.name, e),
+					Outcome::Skip(reason) => println!("    SKIP {}: {}", case.name, reason),
+					Outcome::Pass if verbose => println!("    PASS {}", case.name),
+					Outcome::Pass => {},
+				}
+			}
+		}
+		let failed = self.cases.iter().filter(|c| matches!(c.outcome, Outcome::Fail(_))).count();
+		println!("\n{} cases, {} failed", self.cases.len(), failed);
+	}
+}
+
+struct Checks<'a> {
+	client: &'a Client,
+	timeout: Duration,
+	report: Report,
+}
+
+/// Check every method of the signer behind `client`, waiting `timeout` for
+/// each answer. Only rotates keys of the signer if `destructive`.
+pub async fn run(client: &Client, timeout: Duration, destructive: bool) -> Report {
+	let mut checks = Checks { client, timeout, report: Report::default() };
+
+	let capabilities = checks.capabilities().await;
+	let keys = checks.generate().await;
+	checks.insert_unknown().await;
+	checks.keys(&keys).await;
+	checks.supported_keys(&keys).await;
+	checks.has_keys(&keys).await;
+	checks.sign_with(&keys).await;
+	checks.sign_with_any(&keys).await;
+	checks.sign_with_all(&keys).await;
+	checks.vrf_sign(&keys).await;
+	let key_management = capabilities.map(|c| c.key_management);
+	checks.generate_session_keys(key_management).await;
+	checks.key_management(key_management, destructive).await;
+
+	checks.report
+}
+
+impl<'a> Checks<'a> {
+	async fn call<T, E: Display>(&self, request: impl Future<Output = Result<T, E>>) -> Result<T, String> {
+		match tokio::time::timeout(self.timeout, request).await {
+			Ok(result) => result.map_err(|e| e.to_string()),
+			Err(_) => Err(format!("No answer within {:?}", self.timeout)),
+		}
+	}
+
+	async fn generate_new(&self, scheme: Scheme, id: KeyTypeId, seed: Option<&str>) -> Result<Vec<u8>, String> {
+		let seed = seed.map(str::to_owned);
+		match scheme {
+			Scheme::Sr25519 => self.call(self.client.sr25519_generate_new(id, seed)).await
+				.map(|k| k.0.to_vec()),
+			Scheme::Ed25519 => self.call(self.client.ed25519_generate_new(id, seed)).await
+				.map(|k| k.0.to_vec()),
+			Scheme::Ecdsa => self.call(self.client.ecdsa_generate_new(id, seed)).await
+				.map(|k| k.0.to_vec()),
+		}
+	}
+
+	async fn public_keys(&self, scheme: Scheme, id: KeyTypeId) -> Result<Vec<Vec<u8>>, String> {
+		match scheme {
+			Scheme::Sr25519 => self.call(self.client.sr25519_public_keys(id)).await
+				.map(|keys| keys.iter().map(|k| k.0.to_vec()).collect()),
+			Scheme::Ed25519 => self.call(self.client.ed25519_public_keys(id)).await
+				.map(|keys| keys.iter().map(|k| k.0.to_vec()).collect()),
+			Scheme::Ecdsa => self.call(self.client.ecdsa_public_keys(id)).await
+				.map(|keys| keys.iter().map(|k| k.0.to_vec()).collect()),
+		}
+	}
+
+	async fn has(&self, keys: Vec<(Vec<u8>, KeyTypeId)>) -> Result<bool, String> {
+		let keys = keys.into_iter().map(|(public, id)| (HexBytes(public), id)).collect();
+		self.call(self.client.has_keys(keys)).await
+	}
+
+	async fn capabilities(&mut self) -> Option<Capabilities> {
+		const METHOD: &str = "signer_capabilities";
+		let capabilities = match self.call(self.client.capabilities()).await {
+			Ok(capabilities) => capabilities,
+			Err(e) => {
+				self.report.check(METHOD, "answers", Err(e));
+				return None
+			},
+		};
+		println!(
+			"{} ({})\n",
+			capabilities.implementation,
+			if capabilities.key_management { "key management" } else { "no key management" },
+		);
+		self.report.check(METHOD, "names the implementation", ensure(
+			!capabilities.implementation.is_empty(),
+			|| "Implementation is empty".into(),
+		));
+		let missing = METHODS.iter()
+			.filter(|m| !capabilities.methods.iter().any(|c| c == *m))
+			.collect::<Vec<_>>();
+		self.report.check(METHOD, "lists every method", ensure(
+			missing.is_empty(),
+			|| format!("Missing {:?}", missing),
+		));
+		Some(capabilities)
+	}
+
+	/// Generate random and seeded keys of each scheme, returning the seeded
+	/// ones or else the random ones.
+	async fn generate(&mut self) -> Vec<Key> {
+		let mut keys = vec![];
+		for scheme in SCHEMES.iter().copied() {
+			let method = scheme.generate_method();
+			let expected = scheme.derive(SEED);
+
+			let random = self.generate_new(scheme, KEY_TYPE, None).await;
+			self.report.check(method, "generates a random key", random.as_ref()
+				.map_err(Clone::clone)
+				.and_then(|public| ensure(
+					public.len() == expected.len() && public != &expected,
+					|| format!("Got {} bytes, expected {} fresh ones", public.len(), expected.len()),
+				)));
+
+			let seeded = self.generate_new(scheme, KEY_TYPE, Some(SEED)).await;
+			self.report.check(method, "generates the key of a seed", seeded.as_ref()
+				.map_err(Clone::clone)
+				.and_then(|public| ensure(
+					public == &expected,
+					|| format!("Got 0x{}, expected 0x{}", hex(public), hex(&expected)),
+				)));
+
+			let generated = random.iter().chain(seeded.iter()).cloned().collect::<Vec<_>>();
+
+			let method = scheme.public_keys_method();
+			let listed = self.public_keys(scheme, KEY_TYPE).await;
+			self.report.check(method, "lists the generated keys", listed.and_then(|listed| {
+				let missing = generated.iter().filter(|k| !listed.contains(k)).count();
+				ensure(missing == 0, || format!("{} of {} generated keys missing", missing, generated.len()))
+			}));
+			let unknown = self.public_keys(scheme, UNKNOWN_KEY_TYPE).await;
+			self.report.check(method, "lists nothing for an unknown key type", unknown.and_then(|listed| ensure(
+				listed.is_empty(),
+				|| format!("Listed {} keys", listed.len()),
+			)));
+
+			if let Some(public) = seeded.ok().or(random.ok()) {
+				keys.push(Key { scheme, public });
+			}
+		}
+		keys
+	}
+
+	async fn insert_unknown(&mut self) {
+		const METHOD: &str = "signer_insert_unknown";
+		let public = Scheme::Sr25519.derive(INSERTED);
+		let inserted = self.call(self.client.insert_unknown(KEY_TYPE, INSERTED.into(), public.clone().into())).await;
+		self.report.check(METHOD, "inserts a key", inserted);
+		let has = self.has(vec![(public, KEY_TYPE)]).await;
+		self.report.check(METHOD, "holds the inserted key", has.and_then(|has| ensure(
+			has,
+			|| "Inserted key is unknown".into(),
+		)));
+	}
+
+	async fn keys(&mut self, keys: &[Key]) {
+		const METHOD: &str = "signer_keys";
+		let listed = self.call(self.client.keys(KEY_TYPE)).await;
+		self.report.check(METHOD, "lists the generated keys", listed.and_then(|listed| {
+			let missing = keys.iter().filter(|k| !listed.contains(&k.pair())).count();
+			ensure(missing == 0, || format!("{} of {} keys missing", missing, keys.len()))
+		}));
+		let unknown = self.call(self.client.keys(UNKNOWN_KEY_TYPE)).await;
+		self.report.check(METHOD, "lists nothing for an unknown key type", unknown.and_then(|listed| ensure(
+			listed.is_empty(),
+			|| format!("Listed {} keys", listed.len()),
+		)));
+	}
+
+	async fn supported_keys(&mut self, keys: &[Key]) {
+		const METHOD: &str = "signer_supported_keys";
+		let unsupported = unsupported(keys);
+		let offered = keys.iter().map(Key::pair).chain(unsupported.iter().cloned()).collect::<Vec<_>>();
+
+		let supported = self.call(self.client.supported_keys(KEY_TYPE, offered.clone())).await;
+		self.report.check(METHOD, "keeps the known keys", supported.as_ref()
+			.map_err(Clone::clone)
+			.and_then(|supported| {
+				let missing = keys.iter().filter(|k| !supported.contains(&k.pair())).count();
+				ensure(missing == 0, || format!("Dropped {} of {} known keys", missing, keys.len()))
+			}));
+		self.report.check(METHOD, "drops unknown keys and wrong crypto ids", supported
+			.and_then(|supported| {
+				let kept = unsupported.iter().filter(|k| supported.contains(k)).count();
+				ensure(kept == 0, || format!("Kept {} of {} unsupported keys", kept, unsupported.len()))
+			}));
+
+		let unknown = self.call(self.client.supported_keys(UNKNOWN_KEY_TYPE, offered)).await;
+		self.report.check(METHOD, "supports nothing of an unknown key type", unknown.and_then(|supported| ensure(
+			supported.is_empty(),
+			|| format!("Kept {} keys", supported.len()),
+		)));
+		let empty = self.call(self.client.supported_keys(KEY_TYPE, vec![])).await;
+		self.report.check(METHOD, "supports nothing of an empty list", empty.and_then(|supported| ensure(
+			supported.is_empty(),
+			|| format!("Kept {} keys", supported.len()),
+		)));
+	}
+
+	async fn has_keys(&mut self, keys: &[Key]) {
+		const METHOD: &str = "signer_has_keys";
+		let known = keys.iter().map(|k| (k.public.clone(), KEY_TYPE)).collect::<Vec<_>>();
+
+		let has = self.has(known.clone()).await;
+		self.report.check(METHOD, "has the generated keys", has.and_then(|has| ensure(
+			has,
+			|| "Generated keys are unknown".into(),
+		)));
+
+		let mut with_unknown = known.clone();
+		with_unknown.push((Scheme::Sr25519.derive(UNKNOWN), KEY_TYPE));
+		let has = self.has(with_unknown).await;
+		self.report.check(METHOD, "lacks an unknown key", has.and_then(|has| ensure(
+			!has,
+			|| "Claims to have a key it never saw".into(),
+		)));
+
+		let other_type = known.into_iter().map(|(public, _)| (public, UNKNOWN_KEY_TYPE)).collect();
+		let has = self.has(other_type).await;
+		self.report.check(METHOD, "lacks keys of an unknown key type", has.and_then(|has| ensure(
+			!has || keys.is_empty(),
+			|| "Claims to have keys of a key type it never generated".into(),
+		)));
+	}
+
+	async fn sign(&self, id: KeyTypeId, key: PublicPair, msg: &[u8]) -> Result<Vec<u8>, String> {
+		self.call(self.client.sign_with(id, key, msg.into())).await.map(|s| s.0)
+	}
+
+	async fn sign_with(&mut self, keys: &[Key]) {
+		const METHOD: &str = "signer_sign_with";
+		let large = vec![0x5a; LARGE_MESSAGE];
+		for key in keys {
+			for (what, msg) in &[("a message", MESSAGE), ("an empty message", &[][..]), ("a 1 MiB message", &large[..])] {
+				let signature = self.sign(KEY_TYPE, key.pair(), msg).await;
+				self.report.check(METHOD, format!("signs {} with {:?}", what, key.scheme), signature
+					.and_then(|signature| ensure(
+						key.scheme.verify(&signature, msg, &key.public),
+						|| format!("Signature 0x{} does not verify", hex(&signature)),
+					)));
+			}
+
+			let wrong_id = PublicPair(CryptoTypePublicPair(WRONG_CRYPTO_ID, key.public.clone()));
+			let signature = self.sign(KEY_TYPE, wrong_id, MESSAGE).await;
+			self.report.check(METHOD, format!("rejects a wrong crypto id for {:?}", key.scheme), rejected(signature));
+
+			let signature = self.sign(UNKNOWN_KEY_TYPE, key.pair(), MESSAGE).await;
+			self.report.check(METHOD, format!("rejects an unknown key type for {:?}", key.scheme), rejected(signature));
+		}
+
+		let unknown = PublicPair(CryptoTypePublicPair(sr25519::CRYPTO_ID, Scheme::Sr25519.derive(UNKNOWN)));
+		let signature = self.sign(KEY_TYPE, unknown, MESSAGE).await;
+		self.report.check(METHOD, "rejects an unknown key", rejected(signature));
+	}
+
+	async fn sign_with_any(&mut self, keys: &[Key]) {
+		const METHOD: &str = "signer_sign_with_any";
+		let mut offered = unsupported(keys);
+		offered.extend(keys.iter().map(Key::pair));
+
+		let signed = self.call(self.client.sign_with_any(KEY_TYPE, offered, MESSAGE.into())).await;
+		self.report.check(METHOD, "signs with a known key", signed.and_then(|(pair, signature)| {
+			let key = keys.iter().find(|k| k.pair() == pair)
+				.ok_or_else(|| format!("Signed with {:?}, which was not offered", pair.0))?;
+			ensure(
+				key.scheme.verify(&signature, MESSAGE, &key.public),
+				|| format!("Signature 0x{} does not verify", hex(&signature)),
+			)
+		}));
+
+		let signed = self.call(self.client.sign_with_any(KEY_TYPE, unsupported(keys), MESSAGE.into())).await;
+		self.report.check(METHOD, "rejects unknown keys only", rejected(signed));
+		let signed = self.call(self.client.sign_with_any(KEY_TYPE, vec![], MESSAGE.into())).await;
+		self.report.check(METHOD, "rejects an empty list", rejected(signed));
+	}
+
+	async fn sign_with_all(&mut self, keys: &[Key]) {
+		const METHOD: &str = "signer_sign_with_all";
+		let signed = self.call(self.client.sign_with_all(KEY_TYPE, vec![], MESSAGE.into())).await;
+		self.report.check(METHOD, "signs nothing for an empty list", signed.and_then(|signed| ensure(
+			signed.is_empty(),
+			|| format!("Answered {} results", signed.len()),
+		)));
+
+		let unsupported = unsupported(keys);
+		let offered = keys.iter().map(Key::pair).chain(unsupported.iter().cloned()).collect::<Vec<_>>();
+		let signed = self.call(self.client.sign_with_all(KEY_TYPE, offered.clone(), MESSAGE.into())).await;
+		self.report.check(METHOD, "answers every key in order", signed.and_then(|signed| {
+			ensure(
+				signed.len() == offered.len(),
+				|| format!("Answered {} results for {} keys", signed.len(), offered.len()),
+			)?;
+			for (key, result) in keys.iter().zip(&signed) {
+				let signature = result.as_ref()
+					.map_err(|e| format!("{:?} key failed: {}", key.scheme, e))?;
+				ensure(
+					key.scheme.verify(signature, MESSAGE, &key.public),
+					|| format!("{:?} signature 0x{} does not verify", key.scheme, hex(signature)),
+				)?;
+			}
+			ensure(
+				signed[keys.len()..].iter().all(Result::is_err),
+				|| "Signed with an unknown key or wrong crypto id".into(),
+			)
+		}));
+	}
+
+	async fn vrf(
+		&self,
+		public: &[u8],
+		label: &[u8],
+		items: Vec<VRFTranscriptValue>,
+	) -> Result<VRFSignature, String> {
+		let public = <sr25519::Public as sp_core::Public>::from_slice(public);
+		let transcript = TransferableVRFTranscriptData { label: label.into(), items };
+		self.call(self.client.sr25519_vrf_sign(KEY_TYPE, public, transcript)).await.map(Into::into)
+	}
+
+	async fn vrf_sign(&mut self, keys: &[Key]) {
+		const METHOD: &str = "signer_sr25519_vrf_sign";
+		let key = match keys.iter().find(|k| k.scheme == Scheme::Sr25519) {
+			Some(key) => key.public.clone(),
+			None => return self.report.skip(METHOD, "all", "No sr25519 key was generated"),
+		};
+
+		let signature = self.vrf(&key, BABE_ENGINE_ID, babe_items()).await;
+		self.report.check(METHOD, "signs a BABE transcript", signature.and_then(|signature| {
+			let transcript = make_transcript(VRFTranscriptData {
+				label: BABE_ENGINE_ID,
+				items: vec!["slot number", "current epoch", "chain randomness"]
+					.into_iter()
+					.zip(babe_items())
+					.collect(),
+			});
+			schnorrkel::PublicKey::from_bytes(&key)
+				.and_then(|public| public.vrf_verify(transcript, &signature.output, &signature.proof))
+				.map(|_| ())
+				.map_err(|e| format!("VRF proof does not verify: {}", e))
+		}));
+
+		let signature = self.vrf(&key, b"NOPE", babe_items()).await;
+		self.report.check(METHOD, "rejects an unknown transcript label", rejected(signature));
+		let mut items = babe_items();
+		items.pop();
+		let signature = self.vrf(&key, BABE_ENGINE_ID, items).await;
+		self.report.check(METHOD, "rejects missing transcript items", rejected(signature));
+		let mut items = babe_items();
+		items.push(VRFTranscriptValue::U64(0));
+		let signature = self.vrf(&key, BABE_ENGINE_ID, items).await;
+		self.report.check(METHOD, "rejects extra transcript items", rejected(signature));
+		let signature = self.vrf(&Scheme::Sr25519.derive(UNKNOWN), BABE_ENGINE_ID, babe_items()).await;
+		self.report.check(METHOD, "rejects an unknown key", rejected(signature));
+	}
+
+	/// Without key management a signer can't roll back, so it must refuse
+	/// generating more than one key.
+	async fn generate_session_keys(&mut self, key_management: Option<bool>) {
+		const METHOD: &str = "signer_generate_session_keys";
+		let keys = vec![(KEY_TYPE, sr25519::CRYPTO_ID), (KEY_TYPE, ed25519::CRYPTO_ID)];
+		match key_management {
+			None => self.report.skip(METHOD, "generates session keys", "Capabilities unknown"),
+			Some(false) => {
+				let session_keys = self.call(self.client.generate_session_keys(keys)).await;
+				self.report.check(METHOD, "rejected without key management", rejected(session_keys));
+			},
+			Some(true) => self.generates_session_keys(keys).await,
+		}
+
+		let keys = vec![(KEY_TYPE, sr25519::CRYPTO_ID), (KEY_TYPE, WRONG_CRYPTO_ID)];
+		let session_keys = self.call(self.client.generate_session_keys(keys)).await;
+		self.report.check(METHOD, "rejects a wrong crypto id", rejected(session_keys));
+	}
+
+	async fn generates_session_keys(&mut self, keys: Vec<(KeyTypeId, CryptoTypeId)>) {
+		const METHOD: &str = "signer_generate_session_keys";
+		let session_keys = self.call(self.client.generate_session_keys(keys)).await;
+		let session_keys = match session_keys.and_then(|k| {
+			ensure(k.len() == 64, || format!("Got {} bytes, expected 64", k.len()))?;
+			Ok(k.0)
+		}) {
+			Ok(session_keys) => session_keys,
+			Err(e) => return self.report.check(METHOD, "generates session keys", Err(e)),
+		};
+		let has = self.has(vec![
+			(session_keys[..32].to_vec(), KEY_TYPE),
+			(session_keys[32..].to_vec(), KEY_TYPE),
+		]).await;
+		self.report.check(METHOD, "generates session keys", has.and_then(|has| ensure(
+			has,
+			|| "Generated session keys are unknown".into(),
+		)));
+	}
+
+	/// `signer_remove_key` and `signer_rotate_keys` must work as the signer
+	/// claims in its capabilities.
+	async fn key_management(&mut self, key_management: Option<bool>, destructive: bool) {
+		const REMOVE: &str = "signer_remove_key";
+		const ROTATE: &str = "signer_rotate_keys";
+		let inserted = Scheme::Sr25519.derive(INSERTED);
+
+		match key_management {
+			None => {
+				self.report.skip(REMOVE, "all", "Capabilities unknown");
+				self.report.skip(ROTATE, "all", "Capabilities unknown");
+			},
+			Some(false) => {
+				let removed = self.call(self.client.remove_key(KEY_TYPE, inserted.into())).await;
+				self.report.check(REMOVE, "rejected without key management", rejected(removed));
+				let rotated = self.call(self.client.rotate_keys(vec![KeyTypeId(*b"imon")])).await;
+				self.report.check(ROTATE, "rejected without key management", rejected(rotated));
+			},
+			Some(true) => {
+				let removed = self.call(self.client.remove_key(KEY_TYPE, inserted.clone().into())).await;
+				self.report.check(REMOVE, "removes a key", removed);
+				let has = self.has(vec![(inserted, KEY_TYPE)]).await;
+				self.report.check(REMOVE, "no longer has the removed key", has.and_then(|has| ensure(
+					!has,
+					|| "Still has the removed key".into(),
+				)));
+
+				let rotated = self.call(self.client.rotate_keys(vec![KEY_TYPE])).await;
+				self.report.check(ROTATE, "rejects a key type without session key scheme", rejected(rotated));
+				if destructive {
+					self.rotate_keys().await;
+				} else {
+					self.report.skip(ROTATE, "rotates keys", "Retires the signer's keys, pass --destructive");
+				}
+			},
+		}
+	}
+
+	async fn rotate_keys(&mut self) {
+		const METHOD: &str = "signer_rotate_keys";
+		let id = KeyTypeId(*b"imon");
+		let before = self.public_keys(Scheme::Sr25519, id).await.unwrap_or_default();
+		let rotated = self.call(self.client.rotate_keys(vec![id])).await;
+		let rotated = match rotated.and_then(|k| {
+			ensure(k.len() == 32, || format!("Got {} bytes, expected 32", k.len()))?;
+			Ok(k.0)
+		}) {
+			Ok(rotated) => rotated,
+			Err(e) => return self.report.check(METHOD, "rotates keys", Err(e)),
+		};
+		let after = self.public_keys(Scheme::Sr25519, id).await;
+		self.report.check(METHOD, "rotates keys", after.and_then(|after| ensure(
+			after == vec![rotated.clone()],
+			|| format!("Holds {} imon keys, expected only the new one", after.len()),
+		)));
+		let mut kept = 0;
+		for public in &before {
+			match self.has(vec![(public.clone(), id)]).await {
+				Ok(false) => {},
+				Ok(true) => kept += 1,
+				Err(e) => return self.report.check(METHOD, "retires the previous keys", Err(e)),
+			}
+		}
+		self.report.check(METHOD, "retires the previous keys", ensure(
+			kept == 0,
+			|| format!("Still has {} of {} previous keys", kept, before.len()),
+		));
+	}
+}
+
+/// Keys of [`KEY_TYPE`] the signer must not sign with: one it never saw and
+/// the known ones with a wrong crypto id.
+fn unsupported(keys: &[Key]) -> Vec<PublicPair> {
+	let unknown = PublicPair(CryptoTypePublicPair(sr25519::CRYPTO_ID, Scheme::Sr25519.derive(UNKNOWN)));
+	keys.iter()
+		.map(|k| PublicPair(CryptoTypePublicPair(WRONG_CRYPTO_ID, k.public.clone())))
+		.chain(std::iter::once(unknown))
+		.collect()
+}
+
+/// Items of a BABE slot claim transcript.
+fn babe_items() -> Vec<VRFTranscriptValue> {
+	vec![
+		VRFTranscriptValue::U64(42),
+		VRFTranscriptValue::U64(1),
+		VRFTranscriptValue::Bytes(vec![7; 32]),
+	]
+}
+
+fn hex(data: &[u8]) -> String {
+	data.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,129 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+/// Conformance checks of a Too Simple Substrate Remote Signer.
+///
+/// Connects to the signer over JSON-RPC, calls every method of the protocol
+/// with valid and invalid input and reports pass/fail per method. The checks
+/// generate keys of the `tsct` key type, so point this at a scratch keystore.
+
+mod checks;
+
+use std::{fs, path::{Path, PathBuf}, time::Duration};
+use structopt::StructOpt;
+
+use se_remote_signer::client::{connect_json, RemoteSignerConfig, TlsConfig};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "tssrs-conformance")]
+struct Opt {
+	/// The signer to check, `http(s)://` or `ws(s)://`, optionally prefixed with `tssrs+`
+	#[structopt(name = "URL")]
+	url: String,
+
+	/// File holding the token sent as `Authorization: Bearer` header
+	#[structopt(long, parse(from_os_str))]
+	auth_token_file: Option<PathBuf>,
+
+	/// PEM certificate of a CA to trust for `https` and `wss`
+	#[structopt(long, parse(from_os_str))]
+	ca_cert: Option<PathBuf>,
+
+	/// PKCS#12 client identity to authenticate with over TLS
+	#[structopt(long, parse(from_os_str))]
+	client_identity: Option<PathBuf>,
+
+	/// File holding the password of the client identity
+	#[structopt(long, parse(from_os_str), requires = "client-identity")]
+	client_identity_password_file: Option<PathBuf>,
+
+	/// Milliseconds to wait for each answer
+	#[structopt(long, default_value = "10000")]
+	timeout: u64,
+
+	/// Also rotate the `imon` keys of the signer, retiring the current ones
+	#[structopt(long)]
+	destructive: bool,
+
+	/// Also list the cases that passed
+	#[structopt(short, long)]
+	verbose: bool,
+}
+
+fn read(path: &Path, what: &str) -> Result<Vec<u8>, String> {
+	fs::read(path).map_err(|e| format!("Reading the {} {} failed: {}", what, path.display(), e))
+}
+
+impl Opt {
+	fn config(&self) -> Result<RemoteSignerConfig, String> {
+		let auth_token = self.auth_token_file.as_ref()
+			.map(|path| read(path, "auth token file")
+				.map(|token| String::from_utf8_lossy(&token).trim().to_owned()))
+			.transpose()?;
+		let ca_certificate = self.ca_cert.as_ref()
+			.map(|path| read(path, "CA certificate"))
+			.transpose()?;
+		let client_identity = match &self.client_identity {
+			Some(path) => {
+				let password = match &self.client_identity_password_file {
+					Some(path) => String::from_utf8_lossy(&read(path, "password file")?)
+						.trim_end_matches(&['\r', '\n'][..])
+						.to_owned(),
+					None => String::new(),
+				};
+				Some((read(path, "client identity")?, password))
+			},
+			None => None,
+		};
+
+		let config = RemoteSignerConfig {
+			timeout: Duration::from_millis(self.timeout),
+			auth_token,
+			tls: TlsConfig { ca_certificate, client_identity },
+			..Default::default()
+		};
+		config.validate()?;
+		Ok(config)
+	}
+}
+
+#[tokio::main]
+async fn main() {
+	let opt = Opt::from_args();
+	let config = match opt.config() {
+		Ok(config) => config,
+		Err(e) => {
+			eprintln!("{}", e);
+			std::process::exit(2);
+		},
+	};
+	let url = opt.url.strip_prefix("tssrs+").unwrap_or(&opt.url);
+	let client = match connect_json(url, &config).await {
+		Ok(client) => client,
+		Err(e) => {
+			eprintln!("Connecting to {} failed: {}", url, e);
+			std::process::exit(2);
+		},
+	};
+
+	let report = checks::run(&client, config.timeout, opt.destructive).await;
+	report.print(opt.verbose);
+	if !report.passed() {
+		std::process::exit(1);
+	}
+}
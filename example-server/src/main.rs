@@ -18,22 +18,279 @@
 
 /// Example Server of the Substrate Simple Remote Signer protocol.
 
+mod ws;
+
+use std::{convert::TryFrom, path::{Path, PathBuf}, str::FromStr, sync::Arc, time::Duration};
 use structopt::StructOpt;
 
-use tokio::stream::StreamExt;
+use futures::StreamExt;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use jsonrpc_http_server::hyper;
 
 use sc_cli::KeystoreParams;
 use sc_service::config::KeystoreConfig;
 use sc_keystore::LocalKeystore;
+use sp_core::crypto::{CryptoTypeId, ExposeSecret, KeyTypeId, SecretString};
+use sp_keystore::{Error as CryptoStoreError, SyncCryptoStorePtr};
 use se_remote_signer::{
     RemoteSignerApi,
-    server::GenericRemoteSignerServer
+    client::{RemoteKeystore, RemoteSignerConfig, TlsConfig},
+    composite::{CompositeKeystore, KeyTypes},
+    server::{EncryptedKeystore, FsKeyArchive, GenericRemoteSignerServer, KeyArchive},
 };
+#[cfg(feature = "hsm")]
+use se_remote_signer::server::Pkcs11Keystore;
 
 use tokio;
 use env_logger;
 
+/// An additional keystore serving only some key types and/or crypto schemes:
+/// `<key types>[/<schemes>]=<path>`, e.g. `gran=/keys/grandpa` or `*/ed25,ecds=/keys/hsm`.
+#[derive(Debug)]
+struct Route {
+    key_types: KeyTypes,
+    schemes: Option<Vec<CryptoTypeId>>,
+    path: PathBuf,
+}
+
+impl FromStr for Route {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (selector, path) = match s.splitn(2, '=').collect::<Vec<_>>()[..] {
+            [selector, path] if !path.is_empty() => (selector, path),
+            _ => return Err(format!("Expected `<key types>[/<schemes>]=<path>`, got '{}'", s)),
+        };
+        let (key_types, schemes) = match selector.splitn(2, '/').collect::<Vec<_>>()[..] {
+            [key_types, schemes] => (key_types, Some(schemes)),
+            [key_types] => (key_types, None),
+            _ => unreachable!(),
+        };
+
+        let key_types = if key_types == "*" {
+            KeyTypes::All
+        } else {
+            KeyTypes::Only(key_types.split(',')
+                .map(|k| KeyTypeId::try_from(k).map_err(|_| format!("Invalid key type '{}'", k)))
+                .collect::<Result<_, _>>()?)
+        };
+        let schemes = schemes.map(|schemes| schemes.split(',')
+            .map(|c| <[u8; 4]>::try_from(c.as_bytes())
+                .map(CryptoTypeId)
+                .map_err(|_| format!("Invalid crypto scheme '{}'", c)))
+            .collect::<Result<_, _>>()
+        ).transpose()?;
+
+        Ok(Route { key_types, schemes, path: path.into() })
+    }
+}
+
+/// How keys are stored on disk.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Backend {
+    /// `LocalKeystore`, plain JSON key files
+    Local,
+    /// `EncryptedKeystore`, key files encrypted with the password
+    Encrypted,
+    /// `Pkcs11Keystore`, ed25519 and ecdsa keys on a PKCS#11 token
+    #[cfg(feature = "hsm")]
+    Pkcs11,
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "local" => Ok(Backend::Local),
+            "encrypted" => Ok(Backend::Encrypted),
+            #[cfg(feature = "hsm")]
+            "pkcs11" => Ok(Backend::Pkcs11),
+            #[cfg(not(feature = "hsm"))]
+            "pkcs11" => Err(format!("The pkcs11 keystore backend needs the `hsm` feature")),
+            _ => Err(format!("Unknown keystore backend '{}'", s)),
+        }
+    }
+}
+
+fn open_keystore(
+    backend: Backend,
+    path: &PathBuf,
+    password: Option<SecretString>,
+) -> Result<SyncCryptoStorePtr, String> {
+    match backend {
+        Backend::Local => LocalKeystore::open(path, password)
+            .map(|k| Arc::new(k) as SyncCryptoStorePtr)
+            .map_err(|e| format!("{:}: {:}", path.display(), e)),
+        Backend::Encrypted => {
+            let password = password
+                .ok_or_else(|| format!("The encrypted keystore backend needs a password"))?;
+            EncryptedKeystore::open(path, password.expose_secret())
+                .map(|k| Arc::new(k) as SyncCryptoStorePtr)
+                .map_err(|e| format!("{:}: {:}", path.display(), e))
+        },
+        #[cfg(feature = "hsm")]
+        Backend::Pkcs11 => Err(format!("PKCS#11 tokens are opened with `open_token`")),
+    }
+}
+
+#[cfg(feature = "hsm")]
+fn open_token(opt: &Opt) -> Result<SyncCryptoStorePtr, String> {
+    let module = opt.pkcs11_module.as_ref()
+        .ok_or_else(|| format!("The pkcs11 keystore backend needs --pkcs11-module"))?;
+    let slot = opt.pkcs11_slot
+        .ok_or_else(|| format!("The pkcs11 keystore backend needs --pkcs11-slot"))?;
+    let filename = opt.pkcs11_pin_filename.as_ref()
+        .ok_or_else(|| format!("The pkcs11 keystore backend needs --pkcs11-pin-filename"))?;
+    let pin = std::fs::read_to_string(filename)
+        .map_err(|e| format!("Could not read the PIN: {:}", e))?;
+    Pkcs11Keystore::open(module, slot, pin.trim_end())
+        .map(|k| Arc::new(k) as SyncCryptoStorePtr)
+}
+
+/// How a relay talks to its upstream signer, like the node's `--remote-signer-*` flags.
+#[derive(Debug, StructOpt)]
+struct UpstreamParams {
+    /// Connection attempts before a call fails, `0` retries forever
+    #[structopt(long = "upstream-retries", default_value = "10")]
+    retries: u8,
+    /// Delay after the first failed connection attempt, in milliseconds.
+    /// Doubled with every further attempt
+    #[structopt(long = "upstream-backoff", default_value = "500")]
+    backoff: u64,
+    /// Upper bound of the delay between connection attempts, in milliseconds
+    #[structopt(long = "upstream-max-backoff", default_value = "30000")]
+    max_backoff: u64,
+    /// Time a single call, or connection attempt, may take, in milliseconds
+    #[structopt(long = "upstream-timeout", default_value = "10000")]
+    timeout: u64,
+    /// File holding a bearer token to authenticate at the upstream signer
+    #[structopt(long = "upstream-auth-token-filename", parse(from_os_str))]
+    token_filename: Option<PathBuf>,
+    /// PEM encoded CA certificate to trust for `https` and `wss` upstreams,
+    /// in addition to the system's
+    #[structopt(long = "upstream-ca-cert", parse(from_os_str))]
+    ca_cert: Option<PathBuf>,
+    /// PKCS#12 archive with the client certificate and key to present upstream
+    #[structopt(long = "upstream-client-identity", parse(from_os_str))]
+    client_identity: Option<PathBuf>,
+    /// File holding the password of the `--upstream-client-identity`
+    #[structopt(
+        long = "upstream-client-identity-password-filename",
+        parse(from_os_str),
+        requires = "client-identity",
+    )]
+    client_identity_password_filename: Option<PathBuf>,
+    /// Further `tssrs+` signers to fail over to, tried in the given order
+    #[structopt(long = "upstream-failover")]
+    failover: Vec<String>,
+    /// How long to cache the upstream's public key listings, in seconds
+    #[structopt(long = "upstream-cache-ttl", default_value = "0")]
+    cache_ttl: u64,
+    /// Verify every signature and VRF proof of the upstream before relaying it
+    #[structopt(long = "upstream-verify-signatures")]
+    verify_signatures: bool,
+}
+
+/// Read `path`, naming it as `what` in errors.
+fn read(path: &Path, what: &str) -> Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|e| format!("Could not read the {} {}: {:}", what, path.display(), e))
+}
+
+/// Strip the `tssrs+` prefix of an upstream signer URL.
+fn upstream_url(url: &str) -> Result<String, String> {
+    url.strip_prefix("tssrs+")
+        .map(str::to_owned)
+        .ok_or_else(|| format!("Upstream signer '{}' must be a tssrs+ URL", url))
+}
+
+impl UpstreamParams {
+    /// Read the referenced files and check the resulting settings.
+    fn config(&self) -> Result<RemoteSignerConfig, String> {
+        let auth_token = self.token_filename.as_ref()
+            .map(|path| read(path, "upstream auth token")
+                .map(|token| String::from_utf8_lossy(&token).trim().to_owned()))
+            .transpose()?;
+        let ca_certificate = self.ca_cert.as_ref()
+            .map(|path| read(path, "upstream CA certificate"))
+            .transpose()?;
+        let client_identity = match &self.client_identity {
+            Some(path) => {
+                let password = match &self.client_identity_password_filename {
+                    Some(path) => String::from_utf8_lossy(&read(path, "client identity password")?)
+                        .trim_end_matches(&['\r', '\n'][..])
+                        .to_owned(),
+                    None => String::new(),
+                };
+                Some((read(path, "upstream client identity")?, password))
+            },
+            None => None,
+        };
+        let failover = self.failover.iter()
+            .map(|url| upstream_url(url))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let config = RemoteSignerConfig {
+            max_retry: self.retries,
+            backoff: Duration::from_millis(self.backoff),
+            max_backoff: Duration::from_millis(self.max_backoff),
+            timeout: Duration::from_millis(self.timeout),
+            auth_token,
+            tls: TlsConfig { ca_certificate, client_identity },
+            failover,
+            cache_ttl: Duration::from_secs(self.cache_ttl),
+            verify_signatures: self.verify_signatures,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+/// Connect to the signer a relay forwards to, given as `tssrs+<url>`.
+fn open_upstream(url: &str, params: &UpstreamParams) -> Result<Arc<RemoteKeystore>, String> {
+    RemoteKeystore::open_with_config(upstream_url(url)?, params.config()?).map(Arc::new)
+}
+
+/// Change the password of the `encrypted` keystores at `paths` to the one in `filename`.
+fn change_password(
+    backend: Backend,
+    password: Option<&SecretString>,
+    filename: &Path,
+    paths: &[PathBuf],
+) -> Result<(), String> {
+    if backend != Backend::Encrypted {
+        return Err(format!("Only encrypted keystores can change their password"))
+    }
+    let password = password.ok_or_else(|| format!("The encrypted keystore backend needs a password"))?;
+    let new_password = std::fs::read_to_string(filename)
+        .map_err(|e| format!("Could not read the new password: {:}", e))?;
+    for path in paths {
+        EncryptedKeystore::open(path, password.expose_secret())
+            .and_then(|k| k.change_passphrase(new_password.trim_end()))
+            .map_err(|e| format!("{:}: {:}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Retires keys of the local keystores on disk and asks the upstream signer
+/// to remove all others.
+struct RelayArchive {
+    local: FsKeyArchive,
+    upstream: Arc<RemoteKeystore>,
+}
+
+#[async_trait::async_trait]
+impl KeyArchive for RelayArchive {
+    async fn archive(&self, key_type: KeyTypeId, public: &[u8]) -> Result<(), CryptoStoreError> {
+        match self.local.archive(key_type, public).await {
+            Err(CryptoStoreError::PairNotFound(_)) => self.upstream.remove_key(key_type, public).await,
+            result => result,
+        }
+    }
+}
+
 #[derive(Debug, StructOpt)]
+#[cfg_attr(not(feature = "hsm"), allow(dead_code))]
 #[structopt(
     name="substrate-remote-sign-server",
     about="keystore Server for Substrate's JSON-RPC Remote Signing Protocol"
@@ -47,9 +304,49 @@ struct Opt {
     /// The interface to listen on
     #[structopt(long = "interface", short="-i", default_value="127.0.0.1")]
     interface: String,
-    // Run in websocket-mode (instead of http)
+    // Run in websocket-mode (instead of http), clients may negotiate binary SCALE framing
     #[structopt(long = "websocket")]
     websocket: bool,
+    /// Serve on stdin/stdout (one JSON-RPC message per line) instead of the network,
+    /// as used by `tssrs+exec://`
+    #[structopt(long = "stdio", conflicts_with = "websocket")]
+    stdio: bool,
+    /// Serve the given key types and/or crypto schemes from another keystore,
+    /// `<key types>[/<schemes>]=<path>`. May be repeated, the first matching
+    /// route wins and the main keystore serves everything else.
+    #[structopt(long = "route")]
+    routes: Vec<Route>,
+    /// How to store keys: `local` or `encrypted` on disk, or `pkcs11` on a token.
+    /// With `pkcs11`, routes are served from local keystores, e.g. for the sr25519
+    /// keys tokens can't hold.
+    #[structopt(
+        long = "keystore-backend",
+        default_value = "local",
+        possible_values = &["local", "encrypted", "pkcs11"],
+    )]
+    backend: Backend,
+    /// Path to the PKCS#11 module of the token, e.g. `libsofthsm2.so`
+    #[structopt(long = "pkcs11-module", parse(from_os_str))]
+    pkcs11_module: Option<PathBuf>,
+    /// Slot id of the token
+    #[structopt(long = "pkcs11-slot")]
+    pkcs11_slot: Option<u64>,
+    /// File holding the user PIN of the token
+    #[structopt(long = "pkcs11-pin-filename", parse(from_os_str))]
+    pkcs11_pin_filename: Option<PathBuf>,
+    /// Relay to another signer, e.g. `tssrs+ws://signer:33033`, instead of serving the
+    /// keystore. Routes are still served from local keystores.
+    #[structopt(long = "upstream")]
+    upstream: Option<String>,
+    #[structopt(flatten)]
+    upstream_params: UpstreamParams,
+    /// Only answer http and websocket clients sending `Authorization: Bearer <token>`
+    /// with the token in this file
+    #[structopt(long = "auth-token-filename", parse(from_os_str))]
+    auth_token_filename: Option<PathBuf>,
+    /// Change the password of `encrypted` keystores to the one in this file and exit
+    #[structopt(long = "new-password-filename", parse(from_os_str))]
+    new_password_filename: Option<PathBuf>,
 }
 
 #[tokio::main]
@@ -57,18 +354,57 @@ async fn main() {
     env_logger::init();
     let opt = Opt::from_args();
     let base_path = std::env::current_dir().unwrap();
-    let keystore = match opt.keystore.keystore_config(&base_path) {
-        Ok((_, KeystoreConfig::Path { path, password })) => {
-            LocalKeystore::open(path, password).map_err(|e| format!("{:}", e))
-        },
+    let (path, password) = match opt.keystore.keystore_config(&base_path) {
+        Ok((_, KeystoreConfig::Path { path, password })) => Ok((path, password)),
         Err(e) => Err(format!("{:}", e)),
         Ok(_) => Err(format!("Only Local-Keystore Paramters supported")),
     }.unwrap();
 
+    let mut paths = opt.routes.iter().map(|r| r.path.clone()).collect::<Vec<_>>();
+    paths.push(path.clone());
+
+    if let Some(filename) = &opt.new_password_filename {
+        if let Err(e) = change_password(opt.backend, password.as_ref(), filename, &paths) {
+            eprintln!("Changing the password failed: {}", e);
+            std::process::exit(1);
+        }
+        eprintln!("Changed the password of {} keystore(s)", paths.len());
+        return
+    }
+
+    let authorization = opt.auth_token_filename.as_ref().map(|filename| {
+        let token = std::fs::read_to_string(filename).expect("Could not read the auth token");
+        Arc::new(format!("Bearer {}", token.trim()))
+    });
+
+    let upstream = opt.upstream.as_ref().map(|url| open_upstream(url, &opt.upstream_params).unwrap());
+    let (route_backend, store) = match (&upstream, opt.backend) {
+        (Some(upstream), _) => (Backend::Local, upstream.clone() as SyncCryptoStorePtr),
+        #[cfg(feature = "hsm")]
+        (None, Backend::Pkcs11) => (Backend::Local, open_token(&opt).unwrap()),
+        (None, backend) => (backend, open_keystore(backend, &path, password.clone()).unwrap()),
+    };
+
+    let mut keystore = CompositeKeystore::new();
+    for route in &opt.routes {
+        let route_store = open_keystore(route_backend, &route.path, password.clone()).unwrap();
+        keystore = keystore.with_route(route.key_types.clone(), route.schemes.clone(), route_store);
+    }
+    let keystore = keystore.with_store(KeyTypes::All, store);
+
     let server_addr = format!("{}:{}", opt.interface, opt.port).parse()
         .expect("Could not parse interface/port");
 
-    let (remote_server, mut receiver) = GenericRemoteSignerServer::proxy(keystore);
+    let (remote_server, mut receiver) = match upstream {
+        Some(upstream) => {
+            eprintln!("Relaying to {}", opt.upstream.as_ref().expect("upstream is set"));
+            GenericRemoteSignerServer::proxy_with_archive(
+                keystore,
+                RelayArchive { local: FsKeyArchive::new(paths), upstream },
+            )
+        },
+        None => GenericRemoteSignerServer::proxy_with_archive(keystore, FsKeyArchive::new(paths)),
+    };
 
     tokio::spawn(async move {
         loop {
@@ -78,23 +414,52 @@ async fn main() {
         }
     });
 
-    if opt.websocket {
-        let mut io = jsonrpc_ws_server::jsonrpc_core::IoHandler::new();
+    if opt.stdio {
+        let mut io = jsonrpc_http_server::jsonrpc_core::IoHandler::new();
         io.extend_with(RemoteSignerApi::to_delegate(remote_server));
 
-        let server = jsonrpc_ws_server::ServerBuilder::new(io)
-            .start(&server_addr)
-            .unwrap();
-        let _ = tokio::task::spawn_blocking(move || {
-            println!("Serving Remote Signer at ws://{:}", server_addr);
-            server.wait()
-        }).await;
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        let mut stdout = tokio::io::stdout();
+        eprintln!("Serving Remote Signer on stdio");
+        while let Ok(Some(line)) = lines.next_line().await {
+            if let Some(mut response) = io.handle_request(&line).await {
+                response.push('\n');
+                if stdout.write_all(response.as_bytes()).await.is_err()
+                    || stdout.flush().await.is_err()
+                {
+                    break
+                }
+            }
+        }
+
+    } else if opt.websocket {
+        let mut io = jsonrpc_http_server::jsonrpc_core::IoHandler::new();
+        io.extend_with(RemoteSignerApi::to_delegate(remote_server.clone()));
+
+        ws::serve(server_addr, Arc::new(io), remote_server, authorization)
+            .await
+            .expect("Websocket server failed");
 
     }  else {
         let mut io = jsonrpc_http_server::jsonrpc_core::IoHandler::new();
         io.extend_with(RemoteSignerApi::to_delegate(remote_server));
 
         let server = jsonrpc_http_server::ServerBuilder::new(io)
+            .request_middleware(move |request: hyper::Request<hyper::Body>| {
+                let authorized = authorization.as_ref().map_or(true, |expected| {
+                    request.headers().get(hyper::header::AUTHORIZATION)
+                        .map_or(false, |given| given.as_bytes() == expected.as_bytes())
+                });
+                if authorized {
+                    request.into()
+                } else {
+                    hyper::Response::builder()
+                        .status(hyper::StatusCode::UNAUTHORIZED)
+                        .body(hyper::Body::empty())
+                        .expect("Static response is valid")
+                        .into()
+                }
+            })
             .threads(3)
             .start_http(&server_addr)
             .unwrap();
@@ -103,4 +468,113 @@ async fn main() {
             server.wait()
         }).await;
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_routes() {
+        let route: Route = "aura,gran=/keys".parse().unwrap();
+        assert!(matches!(&route.key_types, KeyTypes::Only(ids)
+            if ids == &[KeyTypeId(*b"aura"), KeyTypeId(*b"gran")]));
+        assert_eq!(route.schemes, None);
+        assert_eq!(route.path, PathBuf::from("/keys"));
+
+        let route: Route = "*/sr25,ecds=/a=b".parse().unwrap();
+        assert!(matches!(route.key_types, KeyTypes::All));
+        assert_eq!(route.schemes, Some(vec![CryptoTypeId(*b"sr25"), CryptoTypeId(*b"ecds")]));
+        assert_eq!(route.path, PathBuf::from("/a=b"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn relays_to_an_upstream_signer() {
+        use jsonrpc_http_server::jsonrpc_core::IoHandler;
+        use se_remote_signer::client::testing::InProcessSigner;
+        use sp_core::bytes::to_hex;
+        use sp_keystore::SyncCryptoStore;
+
+        const AURA: KeyTypeId = KeyTypeId(*b"aura");
+
+        let dir = |name: &str| {
+            let path = std::env::temp_dir().join(format!("tssrs-relay-{}-{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&path);
+            path
+        };
+        let (upstream_path, local_path) = (dir("upstream"), dir("local"));
+
+        let upstream_store = LocalKeystore::open(&upstream_path, None).unwrap();
+        let public = SyncCryptoStore::ed25519_generate_new(&upstream_store, AURA, None).unwrap();
+        let signer = InProcessSigner::serve_with_archive(
+            "relay-upstream",
+            upstream_store,
+            FsKeyArchive::new(vec![upstream_path.clone()]),
+        ).unwrap();
+        let upstream = Arc::new(RemoteKeystore::open(signer.url(), Some(2)).unwrap());
+
+        let (relay, mut receiver) = GenericRemoteSignerServer::proxy_with_archive(
+            CompositeKeystore::new().with_store(KeyTypes::All, upstream.clone()),
+            RelayArchive { local: FsKeyArchive::new(vec![local_path]), upstream },
+        );
+        tokio::spawn(async move {
+            while receiver.next().await.is_some() {}
+        });
+        let mut io = IoHandler::new();
+        io.extend_with(RemoteSignerApi::to_delegate(relay));
+        let io = &io;
+        let call = move |method: &str, params: serde_json::Value| {
+            let request = serde_json::json!({
+                "jsonrpc": "2.0", "id": 1, "method": method, "params": params,
+            }).to_string();
+            async move {
+                let response = io.handle_request(&request).await.expect("Has a response");
+                serde_json::from_str::<serde_json::Value>(&response).unwrap()
+            }
+        };
+        let public = to_hex(&public.0, false);
+
+        let signature = call("signer_sign_with", serde_json::json!([AURA, ["ed25", public], "0x01"])).await;
+        assert_eq!(signature["result"].as_str().unwrap().len(), 2 + 128);
+
+        let removed = call("signer_remove_key", serde_json::json!([AURA, public])).await;
+        assert!(removed.get("error").is_none(), "{}", removed);
+        let has = call("signer_has_keys", serde_json::json!([[[public, AURA]]])).await;
+        assert_eq!(has["result"], false);
+        assert_eq!(upstream_path.join("archive").read_dir().unwrap().count(), 1);
+
+        let _ = std::fs::remove_dir_all(&upstream_path);
+    }
+
+    #[test]
+    fn configures_the_upstream_like_the_node() {
+        let opt = Opt::from_iter_safe(&[
+            "server", "--upstream", "tssrs+ws://signer:33033",
+            "--upstream-timeout", "2500", "--upstream-retries", "3",
+            "--upstream-failover", "tssrs+ws://backup:33033",
+        ]).unwrap();
+        let config = opt.upstream_params.config().unwrap();
+        assert_eq!(config.timeout, Duration::from_millis(2500));
+        assert_eq!(config.max_retry, 3);
+        assert_eq!(config.failover, vec!["ws://backup:33033".to_string()]);
+
+        let opt = Opt::from_iter_safe(&["server", "--upstream-failover", "ws://backup:33033"]).unwrap();
+        assert!(opt.upstream_params.config().is_err());
+    }
+
+    #[test]
+    fn only_changes_the_password_of_encrypted_keystores() {
+        let password = SecretString::new("secret".into());
+        let filename = Path::new("/nonexistent/password");
+        assert!(change_password(Backend::Local, Some(&password), filename, &[]).is_err());
+        assert!(change_password(Backend::Encrypted, None, filename, &[]).is_err());
+        assert!(change_password(Backend::Encrypted, Some(&password), filename, &[]).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_routes() {
+        for route in &["aura", "aura=", "auras=/keys", "aura/sr25519=/keys", "aura,=/keys"] {
+            assert!(route.parse::<Route>().is_err(), "{} parsed", route);
+        }
+    }
+}
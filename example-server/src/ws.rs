@@ -0,0 +1,125 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+/// Websocket server speaking JSON-RPC text frames or, if the client
+/// negotiates it during the handshake, binary SCALE frames.
+
+use std::{net::SocketAddr, sync::Arc};
+
+use futures::{channel::mpsc, SinkExt, StreamExt};
+use jsonrpc_http_server::jsonrpc_core::IoHandler;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::{
+    handshake::server::{ErrorResponse, Request, Response},
+    http::{self, HeaderValue, StatusCode},
+    Message,
+};
+
+use se_remote_signer::{scale::SUBPROTOCOL, server::GenericRemoteSignerServer};
+
+/// Accept websocket connections on `addr` until the listener fails.
+///
+/// With an `authorization` only handshakes sending it as `Authorization`
+/// header are accepted.
+pub async fn serve(
+    addr: SocketAddr,
+    io: Arc<IoHandler>,
+    server: GenericRemoteSignerServer,
+    authorization: Option<Arc<String>>,
+) -> std::io::Result<()> {
+    let listener = TcpListener::bind(&addr).await?;
+    println!("Serving Remote Signer at ws://{:}", addr);
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        tokio::spawn(handle_connection(stream, peer, io.clone(), server.clone(), authorization.clone()));
+    }
+}
+
+async fn handle_connection(
+    stream: TcpStream,
+    peer: SocketAddr,
+    io: Arc<IoHandler>,
+    server: GenericRemoteSignerServer,
+    authorization: Option<Arc<String>>,
+) {
+    let mut binary = false;
+    let negotiate = |request: &Request, mut response: Response| {
+        if let Some(expected) = &authorization {
+            let given = request.headers().get(http::header::AUTHORIZATION);
+            if given.map_or(true, |given| given.as_bytes() != expected.as_bytes()) {
+                let mut refused = ErrorResponse::new(None);
+                *refused.status_mut() = StatusCode::UNAUTHORIZED;
+                return Err(refused)
+            }
+        }
+        binary = request.headers()
+            .get_all("Sec-WebSocket-Protocol")
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .any(|p| p.trim() == SUBPROTOCOL);
+        if binary {
+            response.headers_mut()
+                .insert("Sec-WebSocket-Protocol", HeaderValue::from_static(SUBPROTOCOL));
+        }
+        Ok(response)
+    };
+
+    let socket = match tokio_tungstenite::accept_hdr_async(stream, negotiate).await {
+        Ok(socket) => socket,
+        Err(e) => {
+            log::warn!("Websocket handshake with {:} failed: {:}", peer, e);
+            return
+        }
+    };
+    log::info!("{:} connected ({:})", peer, if binary { "scale" } else { "json" });
+
+    let (mut sink, mut incoming) = socket.split();
+    let (responses, mut outgoing) = mpsc::unbounded::<Message>();
+
+    tokio::spawn(async move {
+        while let Some(message) = outgoing.next().await {
+            if sink.send(message).await.is_err() {
+                break
+            }
+        }
+    });
+
+    while let Some(Ok(message)) = incoming.next().await {
+        let responses = responses.clone();
+        match message {
+            Message::Binary(frame) if binary => {
+                let response = server.handle_scale(&frame);
+                tokio::spawn(async move {
+                    let _ = responses.unbounded_send(Message::Binary(response.await));
+                });
+            },
+            Message::Text(request) => {
+                let response = io.handle_request(&request);
+                tokio::spawn(async move {
+                    if let Some(response) = response.await {
+                        let _ = responses.unbounded_send(Message::Text(response));
+                    }
+                });
+            },
+            Message::Close(_) => break,
+            _ => {},
+        }
+    }
+    log::info!("{:} disconnected", peer);
+}
@@ -0,0 +1,85 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Compares the JSON-RPC and the binary SCALE wire formats for a
+//! `sign_with` round trip through `GenericRemoteSignerServer`, in process
+//! so only encoding and dispatch are measured.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use codec::{Decode, Encode};
+use futures::{executor::block_on, StreamExt};
+use sc_keystore::LocalKeystore;
+use sp_core::crypto::{CryptoTypePublicPair, KeyTypeId};
+use sp_keystore::SyncCryptoStore;
+
+use substrate_example_tssrs::{
+	RemoteSignerApi, HexBytes, PublicPair,
+	scale::{Request, ResponsePayload},
+	server::GenericRemoteSignerServer,
+};
+
+const TEST_TK: KeyTypeId = KeyTypeId(*b"test");
+
+fn sign_with(c: &mut Criterion) {
+	let keystore = LocalKeystore::in_memory();
+	let public = SyncCryptoStore::ed25519_generate_new(&keystore, TEST_TK, Some("//Alice"))
+		.expect("InMem Keystore doesn't fail");
+	let key = CryptoTypePublicPair(sp_core::ed25519::CRYPTO_ID, public.0.to_vec());
+
+	let (server, mut runner) = GenericRemoteSignerServer::proxy(keystore);
+	let rt = tokio::runtime::Runtime::new().expect("Runtime starts");
+	rt.spawn(async move { while runner.next().await.is_some() {} });
+
+	let mut io = jsonrpc_core::IoHandler::new();
+	io.extend_with(RemoteSignerApi::to_delegate(server.clone()));
+
+	let mut group = c.benchmark_group("sign_with");
+	for size in [32usize, 1024, 64 * 1024].iter() {
+		let msg = vec![0x42u8; *size];
+		group.throughput(Throughput::Bytes(*size as u64));
+
+		group.bench_with_input(BenchmarkId::new("json", size), &msg, |b, msg| {
+			b.iter(|| {
+				let request = serde_json::json!({
+					"jsonrpc": "2.0",
+					"id": 1,
+					"method": "signer_sign_with",
+					"params": [TEST_TK, PublicPair(key.clone()), HexBytes(msg.clone())],
+				}).to_string();
+				let response = block_on(io.handle_request(&request)).expect("Has a response");
+				let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+				let signature: HexBytes = serde_json::from_value(response["result"].clone()).unwrap();
+				assert_eq!(signature.len(), 64);
+			})
+		});
+
+		group.bench_with_input(BenchmarkId::new("scale", size), &msg, |b, msg| {
+			b.iter(|| {
+				let frame = (1u64, Request::SignWith(TEST_TK, key.clone(), msg.clone())).encode();
+				let response = block_on(server.handle_scale(&frame));
+				let (_, payload) = <(u64, ResponsePayload)>::decode(&mut &response[..]).unwrap();
+				let signature = Vec::<u8>::decode(&mut &payload.unwrap()[..]).unwrap();
+				assert_eq!(signature.len(), 64);
+			})
+		});
+	}
+	group.finish();
+}
+
+criterion_group!(benches, sign_with);
+criterion_main!(benches);
@@ -18,11 +18,33 @@
 
 /// Client implementation of SSRS using hyper
 
+mod bridge;
+mod config;
+mod exec;
+mod quorum;
+mod scale;
+mod status;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod transport;
+mod verify;
+
+pub use config::{RemoteSignerConfig, TlsConfig};
+pub use status::{Latency, RemoteSignerStatus};
+pub use quorum::QuorumKeystore;
+
 use async_trait::async_trait;
-use tokio::{runtime::Handle, sync::RwLock};
-use std::sync::Arc;
+use futures::{executor::block_on, future::{BoxFuture, FutureExt}};
+use tokio::sync::RwLock;
+use std::{
+	collections::HashMap,
+	convert::TryFrom,
+	future::Future,
+	sync::{Arc, atomic::{AtomicUsize, Ordering}},
+	time::{Duration, Instant},
+};
 use sp_core::{
-	crypto::{CryptoTypePublicPair, KeyTypeId },
+	crypto::{CryptoTypeId, CryptoTypePublicPair, KeyTypeId },
 	sr25519::{Public as Sr25519Public},
 };
 use sp_keystore::{
@@ -34,70 +56,445 @@ use sp_application_crypto::{ed25519, sr25519, ecdsa};
 
 use url::Url;
 
-use super::RemoteSignerApiClient as Client;
-use jsonrpc_client_transports::transports::{http, ws};
+use super::{RemoteSignerApiClient as Client, Capabilities, PublicPair};
+use jsonrpc_client_transports::RpcError;
+
+/// The wire format spoken with the signer.
+enum Connection {
+	/// JSON-RPC over http, websocket or stdio
+	Json(Client),
+	/// Binary SCALE frames over websocket
+	Scale(scale::ScaleClient),
+}
+
+/// Both wire formats exposed with the native types of [`CryptoStore`].
+impl Connection {
+	async fn sr25519_public_keys(&self, id: KeyTypeId) -> Result<Vec<sr25519::Public>, RpcError> {
+		match self {
+			Connection::Json(c) => c.sr25519_public_keys(id).await,
+			Connection::Scale(c) => c.sr25519_public_keys(id).await,
+		}
+	}
+
+	async fn sr25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<String>,
+	) -> Result<sr25519::Public, RpcError> {
+		match self {
+			Connection::Json(c) => c.sr25519_generate_new(id, seed).await,
+			Connection::Scale(c) => c.sr25519_generate_new(id, seed).await,
+		}
+	}
+
+	async fn ed25519_public_keys(&self, id: KeyTypeId) -> Result<Vec<ed25519::Public>, RpcError> {
+		match self {
+			Connection::Json(c) => c.ed25519_public_keys(id).await,
+			Connection::Scale(c) => c.ed25519_public_keys(id).await,
+		}
+	}
+
+	async fn ed25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<String>,
+	) -> Result<ed25519::Public, RpcError> {
+		match self {
+			Connection::Json(c) => c.ed25519_generate_new(id, seed).await,
+			Connection::Scale(c) => c.ed25519_generate_new(id, seed).await,
+		}
+	}
+
+	async fn ecdsa_public_keys(&self, id: KeyTypeId) -> Result<Vec<ecdsa::Public>, RpcError> {
+		match self {
+			Connection::Json(c) => c.ecdsa_public_keys(id).await,
+			Connection::Scale(c) => c.ecdsa_public_keys(id).await,
+		}
+	}
+
+	async fn ecdsa_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<String>,
+	) -> Result<ecdsa::Public, RpcError> {
+		match self {
+			Connection::Json(c) => c.ecdsa_generate_new(id, seed).await,
+			Connection::Scale(c) => c.ecdsa_generate_new(id, seed).await,
+		}
+	}
+
+	async fn insert_unknown(
+		&self,
+		key_type: KeyTypeId,
+		suri: String,
+		public: Vec<u8>,
+	) -> Result<(), RpcError> {
+		match self {
+			Connection::Json(c) => c.insert_unknown(key_type, suri, public.into()).await,
+			Connection::Scale(c) => c.insert_unknown(key_type, suri, public).await,
+		}
+	}
+
+	async fn supported_keys(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+	) -> Result<Vec<CryptoTypePublicPair>, RpcError> {
+		match self {
+			Connection::Json(c) => c
+				.supported_keys(id, keys.into_iter().map(PublicPair).collect())
+				.await
+				.map(|keys| keys.into_iter().map(Into::into).collect()),
+			Connection::Scale(c) => c.supported_keys(id, keys).await,
+		}
+	}
+
+	async fn keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, RpcError> {
+		match self {
+			Connection::Json(c) => c
+				.keys(id)
+				.await
+				.map(|keys| keys.into_iter().map(Into::into).collect()),
+			Connection::Scale(c) => c.keys(id).await,
+		}
+	}
+
+	async fn has_keys(&self, public_keys: Vec<(Vec<u8>, KeyTypeId)>) -> Result<bool, RpcError> {
+		match self {
+			Connection::Json(c) => c
+				.has_keys(public_keys.into_iter().map(|(k, t)| (k.into(), t)).collect())
+				.await,
+			Connection::Scale(c) => c.has_keys(public_keys).await,
+		}
+	}
+
+	async fn sign_with(
+		&self,
+		id: KeyTypeId,
+		key: CryptoTypePublicPair,
+		msg: Vec<u8>,
+	) -> Result<Vec<u8>, RpcError> {
+		match self {
+			Connection::Json(c) => c.sign_with(id, key.into(), msg.into()).await.map(Into::into),
+			Connection::Scale(c) => c.sign_with(id, key, msg).await,
+		}
+	}
+
+	async fn sign_with_any(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+		msg: Vec<u8>,
+	) -> Result<(CryptoTypePublicPair, Vec<u8>), RpcError> {
+		match self {
+			Connection::Json(c) => c
+				.sign_with_any(id, keys.into_iter().map(PublicPair).collect(), msg.into())
+				.await
+				.map(|(key, sig)| (key.into(), sig.into())),
+			Connection::Scale(c) => c.sign_with_any(id, keys, msg).await,
+		}
+	}
+
+	async fn sign_with_all(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+		msg: Vec<u8>,
+	) -> Result<Vec<Result<Vec<u8>, String>>, RpcError> {
+		match self {
+			Connection::Json(c) => c
+				.sign_with_all(id, keys.into_iter().map(PublicPair).collect(), msg.into())
+				.await
+				.map(|sigs| sigs.into_iter().map(|s| s.map(Into::into)).collect()),
+			Connection::Scale(c) => c.sign_with_all(id, keys, msg).await,
+		}
+	}
+
+	async fn sr25519_vrf_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: Sr25519Public,
+		transcript_data: crate::TransferableVRFTranscriptData,
+	) -> Result<VRFSignature, RpcError> {
+		match self {
+			Connection::Json(c) => c.sr25519_vrf_sign(key_type, public, transcript_data).await.map(Into::into),
+			Connection::Scale(c) => c.sr25519_vrf_sign(key_type, public, transcript_data).await,
+		}
+	}
+
+	async fn remove_key(&self, key_type: KeyTypeId, public: Vec<u8>) -> Result<(), RpcError> {
+		match self {
+			Connection::Json(c) => c.remove_key(key_type, public.into()).await,
+			Connection::Scale(c) => c.remove_key(key_type, public).await,
+		}
+	}
+
+	async fn rotate_keys(&self, key_types: Vec<KeyTypeId>) -> Result<Vec<u8>, RpcError> {
+		match self {
+			Connection::Json(c) => c.rotate_keys(key_types).await.map(Into::into),
+			Connection::Scale(c) => c.rotate_keys(key_types).await,
+		}
+	}
+
+	async fn generate_session_keys(
+		&self,
+		keys: Vec<(KeyTypeId, CryptoTypeId)>,
+	) -> Result<Vec<u8>, RpcError> {
+		match self {
+			Connection::Json(c) => c.generate_session_keys(keys).await.map(Into::into),
+			Connection::Scale(c) => c.generate_session_keys(keys).await,
+		}
+	}
+
+	async fn capabilities(&self) -> Result<Capabilities, RpcError> {
+		match self {
+			Connection::Json(c) => c.capabilities().await,
+			Connection::Scale(c) => c.capabilities().await,
+		}
+	}
+}
+
+/// Typed JSON-RPC client of the `http(s)` or `ws(s)` signer at `url`, e.g.
+/// to test a signer method by method.
+///
+/// Must be called within a tokio runtime, which then drives the connection.
+pub async fn connect_json(url: &str, config: &RemoteSignerConfig) -> Result<Client, RpcError> {
+	let url = config::parse_url(url).map_err(RpcError::Client)?;
+	match url.scheme() {
+		"http" | "https" => transport::http(&url, config).map(|(channel, _)| channel.into()),
+		"ws" | "wss" => {
+			let (socket, _) = transport::websocket(&url, config, None).await?;
+			Ok(transport::json_over_websocket(socket).0.into())
+		},
+		scheme => Err(RpcError::Client(format!("Can't connect to {} signers directly", scheme))),
+	}
+}
+
+/// What keeps a connection going, and tells whether it still does.
+enum Link {
+	/// The signer process we spawned
+	Child(exec::ChildHandle),
+	/// A network transport
+	Alive(transport::Alive),
+}
+
+impl Link {
+	fn is_alive(&self) -> bool {
+		match self {
+			Link::Child(child) => child.is_alive(),
+			Link::Alive(alive) => alive.load(Ordering::SeqCst),
+		}
+	}
+}
+
+/// Connection to the signer, living on the [`bridge::Bridge`] runtime.
+struct Signer {
+	client: RwLock<Option<Connection>>,
+	link: parking_lot::Mutex<Option<Link>>,
+	/// The signer and its failovers
+	urls: Vec<Url>,
+	/// Index of the signer in use, wraps around `urls`
+	endpoint: AtomicUsize,
+	config: RemoteSignerConfig,
+	cache: parking_lot::Mutex<HashMap<KeyTypeId, (Instant, Vec<CryptoTypePublicPair>)>>,
+	stats: parking_lot::Mutex<status::Stats>,
+}
 
 /// A remote based keystore that is either memory-based or filesystem-based.
+///
+/// The connection is driven by a background thread of its own, so both the
+/// async and the sync API may be used from any thread.
 pub struct RemoteKeystore {
-	client: RwLock<Option<Client>>,
-	url: Url,
-	max_retry: u8,
-	rt: Handle,
+	signer: Arc<Signer>,
+	bridge: bridge::Bridge,
 }
 
 impl RemoteKeystore {
-	/// Create a local keystore from filesystem.
+	/// Connect to the signer at `url`.
 	pub fn open(url: String, max_retry: Option<u8>) -> Result<Self, String> {
-		let url : Url = url
-			.parse()
-			.map_err(|e| format!("Parsing Remote Signer URL failed: {:?}", e))?;
+		Self::open_with_config(url, RemoteSignerConfig {
+			max_retry: max_retry.unwrap_or(10),
+			..Default::default()
+		})
+	}
 
-		match url.scheme() {
-			"http" | "https" | "ws" | "wss" => {},
-			_ => return Err(format!("Remote Signer doesn't speak {:}", url.scheme()))
-		};
+	/// Connect to the signer at `url` with the given settings.
+	pub fn open_with_config(url: String, config: RemoteSignerConfig) -> Result<Self, String> {
+		config.validate()?;
+		let urls = std::iter::once(&url)
+			.chain(config.failover.iter())
+			.map(|url| config::parse_url(url))
+			.collect::<Result<Vec<_>, _>>()?;
 
 		Ok(RemoteKeystore{
-			client: RwLock::new(None),
-			url,
-			max_retry: max_retry.unwrap_or(10),
-			rt: Handle::current(),
+			signer: Arc::new(Signer {
+				client: RwLock::new(None),
+				link: parking_lot::Mutex::new(None),
+				urls,
+				endpoint: AtomicUsize::new(0),
+				config,
+				cache: parking_lot::Mutex::new(HashMap::new()),
+				stats: parking_lot::Mutex::new(Default::default()),
+			}),
+			bridge: bridge::Bridge::start("remote-keystore")?,
 		})
 	}
 
-	/// Create a local keystore in memory.
+	/// Run `call` on the bridge, recording how long it took.
+	async fn run<F>(&self, call: F) -> Result<F::Output, CryptoStoreError>
+	where
+		F: Future + Send + 'static,
+		F::Output: Send + 'static,
+	{
+		let signer = self.signer.clone();
+		self.bridge.run(async move {
+			let start = Instant::now();
+			let output = call.await;
+			signer.stats.lock().record(start.elapsed());
+			output
+		}).await
+	}
+
+	/// Take the key `public` out of service on the signer.
+	pub async fn remove_key(&self, key_type: KeyTypeId, public: &[u8]) -> Result<(), CryptoStoreError> {
+		let (signer, public) = (self.signer.clone(), public.to_vec());
+		self.run(async move { signer.remove_key(key_type, &public).await }).await?
+	}
+
+	/// Replace all keys of the given session key types with fresh ones.
+	///
+	/// Returns the SCALE encoded session keys to pass to `session.setKeys`.
+	pub async fn rotate_keys(&self, key_types: Vec<KeyTypeId>) -> Result<Vec<u8>, CryptoStoreError> {
+		let signer = self.signer.clone();
+		self.run(async move { signer.rotate_keys(key_types).await }).await?
+	}
+
+	/// Generate all session `keys` of the runtime in a single call, all or nothing.
+	///
+	/// Returns the SCALE encoded session keys, like `author_rotateKeys`.
+	pub async fn generate_session_keys(
+		&self,
+		keys: Vec<(KeyTypeId, CryptoTypeId)>,
+	) -> Result<Vec<u8>, CryptoStoreError> {
+		let signer = self.signer.clone();
+		self.run(async move { signer.generate_session_keys(keys).await }).await?
+	}
+
+	/// Ask the signer what it offers.
+	pub async fn capabilities(&self) -> Result<Capabilities, CryptoStoreError> {
+		let signer = self.signer.clone();
+		self.run(async move { signer.capabilities().await }).await?
+	}
+
+	/// The signer currently in use, one of the configured failovers after
+	/// the primary one failed.
+	pub fn endpoint(&self) -> String {
+		self.signer.url().to_string()
+	}
+
+	/// Endpoints, connection state, last error, latencies and cached keys.
+	pub fn status(&self) -> RemoteSignerStatus {
+		let signer = &self.signer;
+		let stats = signer.stats.lock();
+		let ttl = signer.config.cache_ttl;
+		RemoteSignerStatus {
+			endpoints: signer.urls.iter().map(ToString::to_string).collect(),
+			current: signer.url().to_string(),
+			connected: signer.link.lock().as_ref().is_some_and(Link::is_alive),
+			last_error: stats.last_error.clone(),
+			latency: stats.latency(),
+			cached_keys: signer.cache.lock().iter()
+				.filter(|(_, (at, _))| at.elapsed() < ttl)
+				.map(|(id, (_, keys))| (String::from_utf8_lossy(&id.0).into_owned(), keys.len()))
+				.collect(),
+			invalid_signatures: stats.invalid_signatures,
+		}
+	}
+}
+
+impl Signer {
+	/// The signer currently in use.
+	fn url(&self) -> &Url {
+		&self.urls[self.endpoint.load(Ordering::SeqCst) % self.urls.len()]
+	}
+
+	/// Drop the connection and move on to the next signer.
+	async fn disconnect(&self) {
+		*self.client.write().await = None;
+		*self.link.lock() = None;
+		if self.urls.len() > 1 {
+			self.endpoint.fetch_add(1, Ordering::SeqCst);
+		}
+	}
+
+	/// Open a connection to `url`.
+	async fn connect(&self, url: &Url) -> Result<(Connection, Link), RpcError> {
+		match url.scheme() {
+			"http" | "https" => {
+				transport::http(url, &self.config)
+					.map(|(channel, alive)| (Connection::Json(channel.into()), Link::Alive(alive)))
+			},
+			"ws" | "wss" => {
+				let (socket, binary) = transport::websocket(url, &self.config, Some(crate::scale::SUBPROTOCOL)).await?;
+				if binary {
+					log::info!{
+						target: "remote_keystore",
+						"Using binary SCALE framing with {:}", url
+					};
+					let (client, alive) = scale::connect(socket);
+					Ok((Connection::Scale(client), Link::Alive(alive)))
+				} else {
+					let (channel, alive) = transport::json_over_websocket(socket);
+					Ok((Connection::Json(channel.into()), Link::Alive(alive)))
+				}
+			},
+			"exec" => {
+				exec::connect::<Client>(url).await
+					.map(|(client, child)| (Connection::Json(client), Link::Child(child)))
+			},
+			#[cfg(feature = "testing")]
+			"memory" => {
+				testing::connect(url)
+					.map(|(channel, alive)| (Connection::Json(channel.into()), Link::Alive(alive)))
+			},
+			_ => unreachable!("Only supported schemes are accepted by `open`; qed")
+		}
+	}
+
+	/// Connect, or reconnect if the connection broke, moving on through the
+	/// failover signers with exponential backoff.
 	async fn ensure_connected(&self) -> Result<(), CryptoStoreError> {
 		let mut w = self.client.write().await;
 		if w.is_some() {
-			return Ok(())
+			match &*self.link.lock() {
+				Some(link) if !link.is_alive() => {
+					log::warn!{
+						target: "remote_keystore",
+						"Connection to {:} broke, reconnecting", self.url()
+					};
+				},
+				_ => return Ok(()),
+			}
+			*w = None;
+			if self.urls.len() > 1 {
+				self.endpoint.fetch_add(1, Ordering::SeqCst);
+			}
 		}
 
-		log::info!{
-			target: "remote_keystore" ,
-			"Connecting to {:}", self.url
-		};
-
-		let mut counter = 0;
+		let mut counter = 0u32;
 		loop {
-			let client = match self.url.scheme() {
-				"http" | "https" => {
-					let (sender, receiver) = futures::channel::oneshot::channel();
-					let url = self.url.clone().into_string();
-					tokio::spawn(async move {
-						let client = http::connect(&url).await;
-						sender.send(client).map_err(|_| ()).expect("The caller did not wait for the server.");
-						Ok::<_, ()>(())
-					});
-					receiver.await.expect("Always sends something")
-				},
-				"ws" | "wss" => {
-					ws::connect::<Client>(&self.url).await
-				},
-				_ => unreachable!()
+			let url = self.url().clone();
+			log::info!{
+				target: "remote_keystore" ,
+				"Connecting to {:}", url
 			};
 
-			match client {
-				Ok(client) => {
+			let connected = tokio::time::timeout(self.config.timeout, self.connect(&url))
+				.await
+				.unwrap_or(Err(RpcError::Timeout));
+			match connected {
+				Ok((client, link)) => {
+					*self.link.lock() = Some(link);
 					*w = Some(client);
 					return Ok(());
 				},
@@ -106,37 +503,142 @@ impl RemoteKeystore {
 						target: "remote_keystore",
 						"Attempt {} failed: {}", counter, e
 					}
+					self.stats.lock().last_error = Some(format!("Connecting to {:} failed: {:}", url, e));
 				}
 			}
 
 			counter += 1;
-			if self.max_retry > 0 && counter >= self.max_retry {
+			if self.config.max_retry > 0 && counter >= self.config.max_retry as u32 {
 				log::error!{
 					target: "remote_keystore",
-					"Retrying to connect {:} failed {} times. Quitting.", self.url, counter
+					"Retrying to connect {:} failed {} times. Quitting.", url, counter
 				}
 				return Err(CryptoStoreError::Unavailable)
 			}
+			if self.urls.len() > 1 {
+				self.endpoint.fetch_add(1, Ordering::SeqCst);
+			}
+			tokio::time::sleep(self.config.backoff(counter - 1)).await;
 		}
+	}
 
+	/// Public keys of `id`, from the cache while it is fresh.
+	async fn listing(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, CryptoStoreError> {
+		let ttl = self.config.cache_ttl;
+		if let Some((at, keys)) = self.cache.lock().get(&id) {
+			if at.elapsed() < ttl {
+				return Ok(keys.clone())
+			}
+		}
+		let keys = self.fetch_keys(id).await?;
+		if ttl > Duration::from_secs(0) {
+			self.cache.lock().insert(id, (Instant::now(), keys.clone()));
+		}
+		Ok(keys)
+	}
 
+	/// Public keys of `scheme`, if listings are cached at all.
+	async fn cached_public_keys<P>(&self, id: KeyTypeId, scheme: CryptoTypeId) -> Option<Vec<P>>
+	where
+		P: for<'a> TryFrom<&'a [u8]>,
+	{
+		if self.config.cache_ttl == Duration::from_secs(0) {
+			return None
+		}
+		let keys = self.listing(id).await.ok()?;
+		Some(keys.into_iter()
+			.filter(|k| k.0 == scheme)
+			.filter_map(|k| P::try_from(&k.1[..]).ok())
+			.collect())
+	}
+
+	/// Remember `e` as the last error and turn it into a [`CryptoStoreError`].
+	fn failed(&self, e: impl std::fmt::Display) -> CryptoStoreError {
+		let message = format!("{:}", e);
+		self.stats.lock().last_error = Some(message.clone());
+		CryptoStoreError::Other(message)
+	}
+
+	/// Count and report that the signer handed out `what`, which did not verify.
+	fn invalid(&self, what: String) -> CryptoStoreError {
+		let message = format!("Signer {:} returned {}", self.url(), what);
+		log::error!(target: quorum::SECURITY_TARGET, "{}", message);
+		let mut stats = self.stats.lock();
+		stats.invalid_signatures += 1;
+		stats.last_error = Some(message.clone());
+		CryptoStoreError::ValidationError(message)
+	}
+
+	/// Forget all cached listings, after keys changed.
+	fn invalidate(&self) {
+		self.cache.lock().clear();
+	}
+
+	/// Run `call` on the connection, connecting first.
+	///
+	/// Only the call itself is subject to the timeout, connecting retries
+	/// on its own terms. A call taking too long drops the connection.
+	async fn call<T>(
+		&self,
+		call: impl for<'c> FnOnce(&'c Connection) -> BoxFuture<'c, Result<T, RpcError>>,
+	) -> Result<T, CryptoStoreError> {
+		self.ensure_connected().await?;
+		let timeout = self.config.timeout;
+		let result = {
+			let client = self.client.read().await;
+			let client = match client.as_ref() {
+				Some(client) => client,
+				None => return Err(self.failed("Connection to the signer was lost")),
+			};
+			tokio::time::timeout(timeout, call(client)).await
+		};
+		match result {
+			Ok(result) => result.map_err(|e| self.failed(e)),
+			Err(_) => {
+				let message = format!("Signer {:} did not answer within {:?}", self.url(), timeout);
+				log::warn!(target: "remote_keystore", "{}", message);
+				self.stats.lock().last_error = Some(message);
+				self.disconnect().await;
+				Err(CryptoStoreError::Unavailable)
+			},
+		}
+	}
+
+	async fn fetch_keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, CryptoStoreError> {
+		self.call(|c| c.keys(id).boxed()).await
+	}
+
+	async fn remove_key(&self, key_type: KeyTypeId, public: &[u8]) -> Result<(), CryptoStoreError> {
+		self.invalidate();
+		let public = public.to_vec();
+		self.call(|c| c.remove_key(key_type, public).boxed()).await
+	}
+
+	async fn rotate_keys(&self, key_types: Vec<KeyTypeId>) -> Result<Vec<u8>, CryptoStoreError> {
+		self.invalidate();
+		self.call(|c| c.rotate_keys(key_types).boxed()).await
+	}
+
+	async fn generate_session_keys(
+		&self,
+		keys: Vec<(KeyTypeId, CryptoTypeId)>,
+	) -> Result<Vec<u8>, CryptoStoreError> {
+		self.invalidate();
+		self.call(|c| c.generate_session_keys(keys).boxed()).await
+	}
+
+	async fn capabilities(&self) -> Result<Capabilities, CryptoStoreError> {
+		self.call(|c| c.capabilities().boxed()).await
 	}
 }
 
 #[async_trait]
-impl CryptoStore for RemoteKeystore {
+impl CryptoStore for Signer {
 	async fn keys(
 		&self,
 		id: KeyTypeId
 	) -> std::result::Result<Vec<CryptoTypePublicPair>, CryptoStoreError> {
-		self.ensure_connected().await?;
-		let client = self.client.read().await;
-		client
-			.as_ref()
-			.ok_or(CryptoStoreError::Unavailable)?
-			.keys(id)
-			.await
-			.map_err(|e|CryptoStoreError::Other(format!("{:}", e)) )
+		self.listing(id).await
 	}
 
 	async fn supported_keys(
@@ -144,14 +646,7 @@ impl CryptoStore for RemoteKeystore {
 		id: KeyTypeId,
 		keys: Vec<CryptoTypePublicPair>
 	) -> std::result::Result<Vec<CryptoTypePublicPair>, CryptoStoreError> {
-		self.ensure_connected().await?;
-		let client = self.client.read().await;
-		client
-			.as_ref()
-			.ok_or(CryptoStoreError::Unavailable)?
-			.supported_keys(id, keys)
-			.await
-			.map_err(|e|CryptoStoreError::Other(format!("{:}", e)) )
+		self.call(|c| c.supported_keys(id, keys).boxed()).await
 	}
 
 	async fn sign_with(
@@ -160,29 +655,54 @@ impl CryptoStore for RemoteKeystore {
 		key: &CryptoTypePublicPair,
 		msg: &[u8],
 	) -> std::result::Result<Vec<u8>, CryptoStoreError> {
-		self.ensure_connected().await?;
-		let client = self.client.read().await;
-		client
-			.as_ref()
-			.ok_or(CryptoStoreError::Unavailable)?
-			.sign_with(id, key.clone(), msg.to_vec())
+		let (pair, message) = (key.clone(), msg.to_vec());
+		let signature = self.call(|c| c.sign_with(id, pair, message).boxed()).await?;
+
+		if self.config.verify_signatures && !verify::signature(key, msg, &signature) {
+			return Err(self.invalid(format!("an invalid signature for {:?} key {:?}", id, key)))
+		}
+		Ok(signature)
+	}
+
+	async fn sign_with_any(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+		msg: &[u8],
+	) -> std::result::Result<(CryptoTypePublicPair, Vec<u8>), CryptoStoreError> {
+		let message = msg.to_vec();
+		let (key, signature) = self.call(|c| c.sign_with_any(id, keys, message).boxed()).await?;
+
+		if self.config.verify_signatures && !verify::signature(&key, msg, &signature) {
+			return Err(self.invalid(format!("an invalid signature for {:?} key {:?}", id, key)))
+		}
+		Ok((key, signature))
+	}
+
+	async fn sign_with_all(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+		msg: &[u8],
+	) -> std::result::Result<Vec<std::result::Result<Vec<u8>, CryptoStoreError>>, ()> {
+		let (pairs, message) = (keys.clone(), msg.to_vec());
+		let signatures = self.call(|c| c.sign_with_all(id, pairs, message).boxed())
 			.await
-			.map_err(|e|CryptoStoreError::Other(format!("{:}", e)) )
+			.map_err(|_| ())?;
+
+		Ok(keys.iter().zip(signatures).map(|(key, signature)| match signature {
+			Ok(signature) if self.config.verify_signatures && !verify::signature(key, msg, &signature) =>
+				Err(self.invalid(format!("an invalid signature for {:?} key {:?}", id, key))),
+			Ok(signature) => Ok(signature),
+			Err(e) => Err(CryptoStoreError::Other(e)),
+		}).collect())
 	}
 
 	async fn sr25519_public_keys(&self, key_type: KeyTypeId) -> Vec<sr25519::Public> {
-		if self.ensure_connected().await.is_err() {
-			return vec![]
-		};
-
-		let client = self.client.read().await;
-		match client.as_ref() {
-			Some(c) => c
-				.sr25519_public_keys(key_type)
-				.await
-				.unwrap_or(vec![]),
-			_ => unreachable!()
+		if let Some(keys) = self.cached_public_keys(key_type, sr25519::CRYPTO_ID).await {
+			return keys
 		}
+		self.call(|c| c.sr25519_public_keys(key_type).boxed()).await.unwrap_or_default()
 	}
 
 	async fn sr25519_generate_new(
@@ -190,30 +710,16 @@ impl CryptoStore for RemoteKeystore {
 		id: KeyTypeId,
 		seed: Option<&str>,
 	) -> std::result::Result<sr25519::Public, CryptoStoreError> {
-		self.ensure_connected().await?;
-
-		let client = self.client.read().await;
-		client
-			.as_ref()
-			.ok_or(CryptoStoreError::Unavailable)?
-			.sr25519_generate_new(id, seed.map(|s| s.to_string()))
-			.await
-			.map_err(|e|CryptoStoreError::Other(format!("{:}", e)) )
+		self.invalidate();
+		let seed = seed.map(|s| s.to_string());
+		self.call(|c| c.sr25519_generate_new(id, seed).boxed()).await
 	}
 
 	async fn ed25519_public_keys(&self, key_type: KeyTypeId) -> Vec<ed25519::Public> {
-		if self.ensure_connected().await.is_err() {
-			return vec![]
-		};
-
-		let client = self.client.read().await;
-		match client.as_ref() {
-			Some(c) => c
-				.ed25519_public_keys(key_type)
-				.await
-				.unwrap_or(vec![]),
-			_ => unreachable!()
+		if let Some(keys) = self.cached_public_keys(key_type, ed25519::CRYPTO_ID).await {
+			return keys
 		}
+		self.call(|c| c.ed25519_public_keys(key_type).boxed()).await.unwrap_or_default()
 	}
 
 	async fn ed25519_generate_new(
@@ -221,31 +727,16 @@ impl CryptoStore for RemoteKeystore {
 		id: KeyTypeId,
 		seed: Option<&str>,
 	) -> std::result::Result<ed25519::Public, CryptoStoreError> {
-		self.ensure_connected().await?;
-
-		let client = self.client.read().await;
-		client
-			.as_ref()
-			.ok_or(CryptoStoreError::Unavailable)?
-			.ed25519_generate_new(id, seed.map(|s| s.to_string()))
-			.await
-			.map_err(|e|CryptoStoreError::Other(format!("{:}", e)) )
+		self.invalidate();
+		let seed = seed.map(|s| s.to_string());
+		self.call(|c| c.ed25519_generate_new(id, seed).boxed()).await
 	}
 
 	async fn ecdsa_public_keys(&self, key_type: KeyTypeId) -> Vec<ecdsa::Public> {
-		if self.ensure_connected().await.is_err() {
-			return vec![]
-		};
-
-		let client = self.client.read().await;
-		match client.as_ref() {
-			Some(c) => c
-				.ecdsa_public_keys(key_type)
-				.await
-				.unwrap_or(vec![]),
-			_ => unreachable!()
+		if let Some(keys) = self.cached_public_keys(key_type, ecdsa::CRYPTO_ID).await {
+			return keys
 		}
-
+		self.call(|c| c.ecdsa_public_keys(key_type).boxed()).await.unwrap_or_default()
 	}
 
 	async fn ecdsa_generate_new(
@@ -253,45 +744,171 @@ impl CryptoStore for RemoteKeystore {
 		id: KeyTypeId,
 		seed: Option<&str>,
 	) -> std::result::Result<ecdsa::Public, CryptoStoreError> {
-		self.ensure_connected().await?;
-
-		let client = self.client.read().await;
-		client
-			.as_ref()
-			.ok_or(CryptoStoreError::Unavailable)?
-			.ecdsa_generate_new(id, seed.map(|s| s.to_string()))
-			.await
-			.map_err(|e|CryptoStoreError::Other(format!("{:}", e)) )
+		self.invalidate();
+		let seed = seed.map(|s| s.to_string());
+		self.call(|c| c.ecdsa_generate_new(id, seed).boxed()).await
 	}
 
 	async fn insert_unknown(&self, key_type: KeyTypeId, suri: &str, public: &[u8])
 		-> std::result::Result<(), ()>
 	{
-		self.ensure_connected().await.map_err(|_|())?;
-
-		let client = self.client.read().await;
-		client
-			.as_ref()
-			.ok_or(())?
-			.insert_unknown(key_type, suri.to_string(), public.to_vec())
+		self.invalidate();
+		let (suri, public) = (suri.to_string(), public.to_vec());
+		self.call(|c| c.insert_unknown(key_type, suri, public).boxed())
 			.await
-			.map_err(|_| () )
+			.map_err(|_| ())
 	}
 
 	async fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
-		if self.ensure_connected().await.is_err() {
-			return false
+		let public_keys = public_keys.to_vec();
+		self.call(|c| c.has_keys(public_keys).boxed()).await.unwrap_or(false)
+	}
+
+	async fn sr25519_vrf_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &Sr25519Public,
+		transcript_data: VRFTranscriptData,
+	) -> std::result::Result<VRFSignature, CryptoStoreError> {
+		let transcript = if self.config.verify_signatures {
+			Some(transcript_data.clone())
+		} else {
+			None
 		};
+		let key = *public;
+		let signature = self.call(|c| c.sr25519_vrf_sign(key_type, key, transcript_data.into()).boxed())
+			.await?;
 
-		let client = self.client.read().await;
-		match client.as_ref() {
-			Some(c) => c
-				.has_keys(public_keys.to_vec())
-				.await
-				.unwrap_or(false),
-			_ => false
+		match transcript {
+			Some(transcript) if !verify::vrf(public, transcript.clone(), &signature) => Err(self.invalid(
+				format!("an invalid VRF proof for {:?} key {:?}", key_type, public)
+			)),
+			_ => Ok(signature),
 		}
 	}
+}
+
+/// Every call is shipped to the bridge thread and awaited from there.
+#[async_trait]
+impl CryptoStore for RemoteKeystore {
+	async fn keys(
+		&self,
+		id: KeyTypeId
+	) -> std::result::Result<Vec<CryptoTypePublicPair>, CryptoStoreError> {
+		let signer = self.signer.clone();
+		self.run(async move { CryptoStore::keys(&*signer, id).await }).await?
+	}
+
+	async fn supported_keys(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>
+	) -> std::result::Result<Vec<CryptoTypePublicPair>, CryptoStoreError> {
+		let signer = self.signer.clone();
+		self.run(async move { CryptoStore::supported_keys(&*signer, id, keys).await }).await?
+	}
+
+	async fn sign_with(
+		&self,
+		id: KeyTypeId,
+		key: &CryptoTypePublicPair,
+		msg: &[u8],
+	) -> std::result::Result<Vec<u8>, CryptoStoreError> {
+		let (signer, key, msg) = (self.signer.clone(), key.clone(), msg.to_vec());
+		self.run(async move { CryptoStore::sign_with(&*signer, id, &key, &msg).await }).await?
+	}
+
+	async fn sign_with_any(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+		msg: &[u8],
+	) -> std::result::Result<(CryptoTypePublicPair, Vec<u8>), CryptoStoreError> {
+		let (signer, msg) = (self.signer.clone(), msg.to_vec());
+		self.run(async move { CryptoStore::sign_with_any(&*signer, id, keys, &msg).await }).await?
+	}
+
+	async fn sign_with_all(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+		msg: &[u8],
+	) -> std::result::Result<Vec<std::result::Result<Vec<u8>, CryptoStoreError>>, ()> {
+		let (signer, msg) = (self.signer.clone(), msg.to_vec());
+		self.run(async move { CryptoStore::sign_with_all(&*signer, id, keys, &msg).await })
+			.await
+			.map_err(|_| ())?
+	}
+
+	async fn sr25519_public_keys(&self, key_type: KeyTypeId) -> Vec<sr25519::Public> {
+		let signer = self.signer.clone();
+		self.run(async move { CryptoStore::sr25519_public_keys(&*signer, key_type).await })
+			.await
+			.unwrap_or_default()
+	}
+
+	async fn sr25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<sr25519::Public, CryptoStoreError> {
+		let (signer, seed) = (self.signer.clone(), seed.map(|s| s.to_string()));
+		self.run(async move {
+			CryptoStore::sr25519_generate_new(&*signer, id, seed.as_deref()).await
+		}).await?
+	}
+
+	async fn ed25519_public_keys(&self, key_type: KeyTypeId) -> Vec<ed25519::Public> {
+		let signer = self.signer.clone();
+		self.run(async move { CryptoStore::ed25519_public_keys(&*signer, key_type).await })
+			.await
+			.unwrap_or_default()
+	}
+
+	async fn ed25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<ed25519::Public, CryptoStoreError> {
+		let (signer, seed) = (self.signer.clone(), seed.map(|s| s.to_string()));
+		self.run(async move {
+			CryptoStore::ed25519_generate_new(&*signer, id, seed.as_deref()).await
+		}).await?
+	}
+
+	async fn ecdsa_public_keys(&self, key_type: KeyTypeId) -> Vec<ecdsa::Public> {
+		let signer = self.signer.clone();
+		self.run(async move { CryptoStore::ecdsa_public_keys(&*signer, key_type).await })
+			.await
+			.unwrap_or_default()
+	}
+
+	async fn ecdsa_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<ecdsa::Public, CryptoStoreError> {
+		let (signer, seed) = (self.signer.clone(), seed.map(|s| s.to_string()));
+		self.run(async move {
+			CryptoStore::ecdsa_generate_new(&*signer, id, seed.as_deref()).await
+		}).await?
+	}
+
+	async fn insert_unknown(&self, key_type: KeyTypeId, suri: &str, public: &[u8])
+		-> std::result::Result<(), ()>
+	{
+		let (signer, suri, public) = (self.signer.clone(), suri.to_string(), public.to_vec());
+		self.run(async move {
+			CryptoStore::insert_unknown(&*signer, key_type, &suri, &public).await
+		}).await.map_err(|_| ())?
+	}
+
+	async fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		let (signer, public_keys) = (self.signer.clone(), public_keys.to_vec());
+		self.run(async move { CryptoStore::has_keys(&*signer, &public_keys).await })
+			.await
+			.unwrap_or(false)
+	}
 
 	async fn sr25519_vrf_sign(
 		&self,
@@ -299,15 +916,10 @@ impl CryptoStore for RemoteKeystore {
 		public: &Sr25519Public,
 		transcript_data: VRFTranscriptData,
 	) -> std::result::Result<VRFSignature, CryptoStoreError> {
-
-		self.ensure_connected().await?;
-		let client = self.client.read().await;
-		client
-			.as_ref()
-			.ok_or(CryptoStoreError::Unavailable)?
-			.sr25519_vrf_sign(key_type, public.clone(), transcript_data.into())
-			.await
-			.map_err(|e|CryptoStoreError::Other(format!("{:}", e)) )
+		let (signer, public) = (self.signer.clone(), *public);
+		self.run(async move {
+			CryptoStore::sr25519_vrf_sign(&*signer, key_type, &public, transcript_data).await
+		}).await?
 	}
 }
 
@@ -316,7 +928,7 @@ impl SyncCryptoStore for RemoteKeystore {
 		&self,
 		id: KeyTypeId
 	) -> std::result::Result<Vec<CryptoTypePublicPair>, CryptoStoreError> {
-		self.rt.block_on(CryptoStore::keys(self, id))
+		block_on(CryptoStore::keys(self, id))
 	}
 
 	fn supported_keys(
@@ -324,7 +936,7 @@ impl SyncCryptoStore for RemoteKeystore {
 		id: KeyTypeId,
 		keys: Vec<CryptoTypePublicPair>
 	) -> std::result::Result<Vec<CryptoTypePublicPair>, CryptoStoreError> {
-		self.rt.block_on(CryptoStore::supported_keys(self, id, keys))
+		block_on(CryptoStore::supported_keys(self, id, keys))
 	}
 
 	fn sign_with(
@@ -333,11 +945,29 @@ impl SyncCryptoStore for RemoteKeystore {
 		key: &CryptoTypePublicPair,
 		msg: &[u8],
 	) -> std::result::Result<Vec<u8>, CryptoStoreError> {
-		self.rt.block_on(CryptoStore::sign_with(self, id, key, msg))
+		block_on(CryptoStore::sign_with(self, id, key, msg))
+	}
+
+	fn sign_with_any(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+		msg: &[u8],
+	) -> std::result::Result<(CryptoTypePublicPair, Vec<u8>), CryptoStoreError> {
+		block_on(CryptoStore::sign_with_any(self, id, keys, msg))
+	}
+
+	fn sign_with_all(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+		msg: &[u8],
+	) -> std::result::Result<Vec<std::result::Result<Vec<u8>, CryptoStoreError>>, ()> {
+		block_on(CryptoStore::sign_with_all(self, id, keys, msg))
 	}
 
 	fn sr25519_public_keys(&self, key_type: KeyTypeId) -> Vec<sr25519::Public> {
-		self.rt.block_on(CryptoStore::sr25519_public_keys(self, key_type))
+		block_on(CryptoStore::sr25519_public_keys(self, key_type))
 	}
 
 	fn sr25519_generate_new(
@@ -345,11 +975,11 @@ impl SyncCryptoStore for RemoteKeystore {
 		id: KeyTypeId,
 		seed: Option<&str>,
 	) -> std::result::Result<sr25519::Public, CryptoStoreError> {
-		self.rt.block_on(CryptoStore::sr25519_generate_new(self, id, seed))
+		block_on(CryptoStore::sr25519_generate_new(self, id, seed))
 	}
 
 	fn ed25519_public_keys(&self, key_type: KeyTypeId) -> Vec<ed25519::Public> {
-		self.rt.block_on(CryptoStore::ed25519_public_keys(self, key_type))
+		block_on(CryptoStore::ed25519_public_keys(self, key_type))
 	}
 
 	fn ed25519_generate_new(
@@ -357,11 +987,11 @@ impl SyncCryptoStore for RemoteKeystore {
 		id: KeyTypeId,
 		seed: Option<&str>,
 	) -> std::result::Result<ed25519::Public, CryptoStoreError> {
-		self.rt.block_on(CryptoStore::ed25519_generate_new(self, id, seed))
+		block_on(CryptoStore::ed25519_generate_new(self, id, seed))
 	}
 
 	fn ecdsa_public_keys(&self, key_type: KeyTypeId) -> Vec<ecdsa::Public> {
-		self.rt.block_on(CryptoStore::ecdsa_public_keys(self, key_type))
+		block_on(CryptoStore::ecdsa_public_keys(self, key_type))
 	}
 
 	fn ecdsa_generate_new(
@@ -369,17 +999,17 @@ impl SyncCryptoStore for RemoteKeystore {
 		id: KeyTypeId,
 		seed: Option<&str>,
 	) -> std::result::Result<ecdsa::Public, CryptoStoreError> {
-		self.rt.block_on(CryptoStore::ecdsa_generate_new(self, id, seed))
+		block_on(CryptoStore::ecdsa_generate_new(self, id, seed))
 	}
 
 	fn insert_unknown(&self, key_type: KeyTypeId, suri: &str, public: &[u8])
 		-> std::result::Result<(), ()>
 	{
-		self.rt.block_on(CryptoStore::insert_unknown(self, key_type, suri, public))
+		block_on(CryptoStore::insert_unknown(self, key_type, suri, public))
 	}
 
 	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
-		self.rt.block_on(CryptoStore::has_keys(self, public_keys))
+		block_on(CryptoStore::has_keys(self, public_keys))
 	}
 
 	fn sr25519_vrf_sign(
@@ -388,7 +1018,7 @@ impl SyncCryptoStore for RemoteKeystore {
 		public: &Sr25519Public,
 		transcript_data: VRFTranscriptData,
 	) -> std::result::Result<VRFSignature, CryptoStoreError> {
-		self.rt.block_on(CryptoStore::sr25519_vrf_sign(
+		block_on(CryptoStore::sr25519_vrf_sign(
 			self, key_type, public, transcript_data))
 	}
 }
@@ -404,3 +1034,160 @@ impl Into<Arc<dyn CryptoStore>> for RemoteKeystore {
 		Arc::new(self)
 	}
 }
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+	use super::*;
+	use futures::StreamExt;
+	use tokio::runtime::{Builder, Runtime};
+	use sc_keystore::LocalKeystore;
+	use sp_core::Pair;
+	use crate::{RemoteSignerApi, server::GenericRemoteSignerServer};
+
+	const TEST_TK: KeyTypeId = KeyTypeId(*b"test");
+
+	/// Serve an in-memory keystore over http, on a runtime of its own.
+	fn serve() -> (Runtime, jsonrpc_http_server::Server) {
+		let runtime = Runtime::new().unwrap();
+		let keystore = LocalKeystore::in_memory();
+		SyncCryptoStore::ed25519_generate_new(&keystore, TEST_TK, Some("//Alice")).unwrap();
+
+		let (server, mut receiver) = GenericRemoteSignerServer::proxy(keystore);
+		runtime.spawn(async move { while receiver.next().await.is_some() {} });
+
+		let mut io = jsonrpc_core::IoHandler::new();
+		io.extend_with(RemoteSignerApi::to_delegate(server));
+		let http = jsonrpc_http_server::ServerBuilder::new(io)
+			.start_http(&"127.0.0.1:0".parse().unwrap())
+			.unwrap();
+		(runtime, http)
+	}
+
+	#[test]
+	fn sync_and_async_calls_from_any_thread() {
+		let (_server_runtime, http) = serve();
+		let keystore = Arc::new(
+			RemoteKeystore::open(format!("http://{}", http.address()), Some(3)).unwrap()
+		);
+
+		// Outside of any runtime, and within a single threaded one.
+		let public = SyncCryptoStore::ed25519_public_keys(&*keystore, TEST_TK).remove(0);
+		let key = CryptoTypePublicPair(ed25519::CRYPTO_ID, AsRef::<[u8]>::as_ref(&public).to_vec());
+		let basic = Builder::new_current_thread().enable_all().build().unwrap();
+		assert!(basic.block_on(async {
+			SyncCryptoStore::has_keys(&*keystore, &[(key.1.clone(), TEST_TK)])
+		}));
+
+		// Blocking and async callers on the workers of a multi-threaded runtime,
+		// outnumbering them, and on plain threads at the same time.
+		let callers = Builder::new_multi_thread()
+			.worker_threads(2)
+			.enable_all()
+			.build()
+			.unwrap();
+		let tasks = (0..32u8).map(|i| {
+			let (keystore, key) = (keystore.clone(), key.clone());
+			callers.spawn(async move {
+				if i % 2 == 0 {
+					SyncCryptoStore::sign_with(&*keystore, TEST_TK, &key, &[i]).is_ok()
+				} else {
+					CryptoStore::sign_with(&*keystore, TEST_TK, &key, &[i]).await.is_ok()
+				}
+			})
+		}).collect::<Vec<_>>();
+		let threads = (0..8u8).map(|i| {
+			let (keystore, key) = (keystore.clone(), key.clone());
+			std::thread::spawn(move || {
+				SyncCryptoStore::sign_with(&*keystore, TEST_TK, &key, &[i]).is_ok()
+			})
+		}).collect::<Vec<_>>();
+
+		callers.block_on(async {
+			for task in tasks {
+				assert!(task.await.unwrap());
+			}
+		});
+		for thread in threads {
+			assert!(thread.join().unwrap());
+		}
+	}
+
+	#[test]
+	fn signs_with_any_and_all_keys() {
+		let (_server_runtime, http) = serve();
+		let keystore = RemoteKeystore::open_with_config(format!("http://{}", http.address()), RemoteSignerConfig {
+			verify_signatures: true,
+			..Default::default()
+		}).unwrap();
+		let public = ed25519::Pair::from_string("//Alice", None).unwrap().public();
+		let key = CryptoTypePublicPair(ed25519::CRYPTO_ID, public.0.to_vec());
+		let unknown = CryptoTypePublicPair(ed25519::CRYPTO_ID, vec![0u8; 32]);
+
+		let (used, _) = SyncCryptoStore::sign_with_any(
+			&keystore, TEST_TK, vec![unknown.clone(), key.clone()], b"msg",
+		).unwrap();
+		assert_eq!(used, key);
+
+		let signatures = SyncCryptoStore::sign_with_all(&keystore, TEST_TK, vec![key, unknown], b"msg").unwrap();
+		assert!(signatures[0].is_ok());
+		assert!(signatures[1].is_err());
+	}
+
+	#[test]
+	fn fails_over_to_the_next_signer() {
+		let (_server_runtime, http) = serve();
+		let keystore = RemoteKeystore::open_with_config("http://127.0.0.1:1".into(), RemoteSignerConfig {
+			backoff: Duration::from_millis(1),
+			failover: vec![format!("http://{}", http.address())],
+			..Default::default()
+		}).unwrap();
+
+		// The first call breaks the connection to the dead signer, the next
+		// one goes to the failover.
+		let _ = SyncCryptoStore::keys(&keystore, TEST_TK);
+		assert!(!SyncCryptoStore::keys(&keystore, TEST_TK).unwrap().is_empty());
+	}
+
+	#[test]
+	fn retries_as_configured_however_long_it_takes() {
+		// Accepts connections and drops them right away, counting them.
+		let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+		let url = format!("ws://{}", listener.local_addr().unwrap());
+		let attempts = Arc::new(AtomicUsize::new(0));
+		let counter = attempts.clone();
+		std::thread::spawn(move || for stream in listener.incoming() {
+			counter.fetch_add(1, Ordering::SeqCst);
+			drop(stream);
+		});
+
+		// Backing off takes longer than a call may.
+		let keystore = RemoteKeystore::open_with_config(url, RemoteSignerConfig {
+			max_retry: 4,
+			backoff: Duration::from_millis(50),
+			timeout: Duration::from_millis(100),
+			..Default::default()
+		}).unwrap();
+		assert!(SyncCryptoStore::keys(&keystore, TEST_TK).is_err());
+		assert_eq!(attempts.load(Ordering::SeqCst), 4);
+	}
+
+	#[test]
+	fn reports_status() {
+		let (_server_runtime, http) = serve();
+		let url = format!("http://{}/", http.address());
+		let keystore = RemoteKeystore::open_with_config(url.clone(), RemoteSignerConfig {
+			cache_ttl: Duration::from_secs(60),
+			..Default::default()
+		}).unwrap();
+		assert!(!keystore.status().connected);
+
+		let keys = SyncCryptoStore::keys(&keystore, TEST_TK).unwrap();
+		let status = keystore.status();
+		assert_eq!(status.endpoints, vec![url]);
+		assert!(status.connected);
+		assert_eq!(status.last_error, None);
+		assert_eq!(status.latency.samples, 1);
+		assert_eq!(status.cached_keys.get("test"), Some(&keys.len()));
+		assert_eq!(status.invalid_signatures, 0);
+	}
+}
@@ -0,0 +1,101 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+/// Background thread owning the runtime a signer connection lives on.
+///
+/// Calls are shipped to the thread and their results sent back over a
+/// channel, so awaiting them needs no runtime of the caller's, and blocking
+/// on them can't starve the connection: both are safe from any thread,
+/// runtime worker or not.
+
+use std::{future::Future, thread};
+use futures::channel::oneshot;
+use tokio::runtime::{Builder, Handle};
+use sp_keystore::Error as CryptoStoreError;
+
+/// Runtime thread of a single [`super::RemoteKeystore`], stopped on drop.
+pub struct Bridge {
+	handle: Handle,
+	shutdown: Option<oneshot::Sender<()>>,
+	thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Bridge {
+	/// Start the thread `name` and its runtime.
+	pub fn start(name: &str) -> Result<Self, String> {
+		let (handle_tx, handle_rx) = std::sync::mpsc::channel();
+		let (shutdown, stopped) = oneshot::channel::<()>();
+
+		let thread = thread::Builder::new()
+			.name(name.into())
+			.spawn(move || {
+				let runtime = match Builder::new_current_thread().enable_all().build() {
+					Ok(runtime) => runtime,
+					Err(e) => {
+						let _ = handle_tx.send(Err(format!("{:}", e)));
+						return
+					},
+				};
+				let _ = handle_tx.send(Ok(runtime.handle().clone()));
+				// Drives all spawned calls until the bridge is dropped.
+				let _ = runtime.block_on(stopped);
+			})
+			.map_err(|e| format!("Starting the {:} thread failed: {:}", name, e))?;
+
+		let handle = handle_rx.recv()
+			.map_err(|_| format!("The {:} thread exited", name))?
+			.map_err(|e| format!("Starting the {:} runtime failed: {:}", name, e))?;
+
+		Ok(Bridge { handle, shutdown: Some(shutdown), thread: Some(thread) })
+	}
+
+	/// Run `future` on the bridge, the result can be awaited on any executor.
+	pub fn run<F>(&self, future: F) -> impl Future<Output = Result<F::Output, CryptoStoreError>>
+	where
+		F: Future + Send + 'static,
+		F::Output: Send + 'static,
+	{
+		let (sender, receiver) = oneshot::channel();
+		self.handle.spawn(async move {
+			let _ = sender.send(future.await);
+		});
+		async move { receiver.await.map_err(|_| CryptoStoreError::Unavailable) }
+	}
+
+	/// Drive `future` on the bridge in the background.
+	pub fn spawn<F>(&self, future: F)
+	where
+		F: Future<Output = ()> + Send + 'static,
+	{
+		self.handle.spawn(future);
+	}
+}
+
+impl Drop for Bridge {
+	fn drop(&mut self) {
+		if let Some(shutdown) = self.shutdown.take() {
+			let _ = shutdown.send(());
+		}
+		if let Some(thread) = self.thread.take() {
+			// Never wait for ourselves.
+			if thread.thread().id() != thread::current().id() {
+				let _ = thread.join();
+			}
+		}
+	}
+}
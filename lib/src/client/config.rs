@@ -0,0 +1,175 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+/// Connection settings of a [`super::RemoteKeystore`].
+
+use std::time::Duration;
+use native_tls::{Certificate, Identity, TlsConnector};
+use url::Url;
+
+/// Schemes a signer may be reached by.
+#[cfg(not(feature = "testing"))]
+pub(crate) const SCHEMES: &[&str] = &["http", "https", "ws", "wss", "exec"];
+/// Schemes a signer may be reached by, including [`super::testing`] signers.
+#[cfg(feature = "testing")]
+pub(crate) const SCHEMES: &[&str] = &["http", "https", "ws", "wss", "exec", "memory"];
+
+/// TLS material for `https` and `wss` signers.
+#[derive(Clone, Debug, Default)]
+pub struct TlsConfig {
+	/// Additional PEM encoded CA certificate to trust
+	pub ca_certificate: Option<Vec<u8>>,
+	/// PKCS#12 archive and its password, to authenticate the node to the signer
+	pub client_identity: Option<(Vec<u8>, String)>,
+}
+
+/// How a [`super::RemoteKeystore`] talks to its signer.
+#[derive(Clone, Debug)]
+pub struct RemoteSignerConfig {
+	/// Connection attempts before giving up, `0` retries forever
+	pub max_retry: u8,
+	/// Delay after the first failed attempt, doubled with every further one
+	pub backoff: Duration,
+	/// Upper bound of the delay between attempts
+	pub max_backoff: Duration,
+	/// Time a single call, or connection attempt, may take before the
+	/// connection is dropped. Backing off between attempts doesn't count
+	pub timeout: Duration,
+	/// Bearer token sent along with `http(s)` and `ws(s)` requests
+	pub auth_token: Option<String>,
+	/// TLS material for `https` and `wss`
+	pub tls: TlsConfig,
+	/// Further signers, tried in order whenever the current one fails
+	pub failover: Vec<String>,
+	/// How long public key listings are cached, zero disables the cache
+	pub cache_ttl: Duration,
+	/// Check every signature and VRF proof against the requested key before
+	/// handing it out
+	pub verify_signatures: bool,
+}
+
+impl Default for RemoteSignerConfig {
+	fn default() -> Self {
+		RemoteSignerConfig {
+			max_retry: 10,
+			backoff: Duration::from_millis(500),
+			max_backoff: Duration::from_secs(30),
+			timeout: Duration::from_secs(10),
+			auth_token: None,
+			tls: TlsConfig::default(),
+			failover: vec![],
+			cache_ttl: Duration::from_secs(0),
+			verify_signatures: false,
+		}
+	}
+}
+
+/// Parse a signer `url` and check we speak its scheme.
+pub(crate) fn parse_url(url: &str) -> Result<Url, String> {
+	let url: Url = url
+		.parse()
+		.map_err(|e| format!("Parsing Remote Signer URL failed: {:?}", e))?;
+	if !SCHEMES.contains(&url.scheme()) {
+		return Err(format!("Remote Signer doesn't speak {:}", url.scheme()))
+	}
+	Ok(url)
+}
+
+impl RemoteSignerConfig {
+	/// Check the settings are usable, including the TLS material.
+	pub fn validate(&self) -> Result<(), String> {
+		if self.timeout == Duration::from_secs(0) {
+			return Err("The Remote Signer timeout must not be zero".into())
+		}
+		if self.max_backoff < self.backoff {
+			return Err("The maximal Remote Signer backoff must not be below the initial one".into())
+		}
+		if let Some(token) = &self.auth_token {
+			if token.is_empty() || !token.chars().all(|c| c.is_ascii_graphic()) {
+				return Err("The Remote Signer auth token must be non-empty printable ASCII".into())
+			}
+		}
+		for url in &self.failover {
+			parse_url(url)?;
+		}
+		self.tls_connector().map(|_| ())
+	}
+
+	/// The `Authorization` header value to send, if any.
+	pub(crate) fn authorization(&self) -> Option<String> {
+		self.auth_token.as_ref().map(|token| format!("Bearer {}", token))
+	}
+
+	/// TLS connector trusting the configured CA and presenting the identity.
+	pub(crate) fn tls_connector(&self) -> Result<TlsConnector, String> {
+		let mut builder = TlsConnector::builder();
+		if let Some(pem) = &self.tls.ca_certificate {
+			let certificate = Certificate::from_pem(pem)
+				.map_err(|e| format!("Invalid Remote Signer CA certificate: {:}", e))?;
+			builder.add_root_certificate(certificate);
+		}
+		if let Some((archive, password)) = &self.tls.client_identity {
+			let identity = Identity::from_pkcs12(archive, password)
+				.map_err(|e| format!("Invalid Remote Signer client identity: {:}", e))?;
+			builder.identity(identity);
+		}
+		builder.build().map_err(|e| format!("Setting up TLS failed: {:}", e))
+	}
+
+	/// Delay before connection attempt `attempt`, counting from zero.
+	pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+		self.backoff
+			.checked_mul(1u32.checked_shl(attempt).unwrap_or(u32::MAX))
+			.map_or(self.max_backoff, |delay| delay.min(self.max_backoff))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn backoff_doubles_up_to_the_maximum() {
+		let config = RemoteSignerConfig {
+			backoff: Duration::from_millis(100),
+			max_backoff: Duration::from_millis(350),
+			..Default::default()
+		};
+		assert_eq!(config.backoff(0), Duration::from_millis(100));
+		assert_eq!(config.backoff(1), Duration::from_millis(200));
+		assert_eq!(config.backoff(2), Duration::from_millis(350));
+		assert_eq!(config.backoff(40), Duration::from_millis(350));
+	}
+
+	#[test]
+	fn rejects_unusable_settings() {
+		assert!(RemoteSignerConfig::default().validate().is_ok());
+		let invalid = [
+			RemoteSignerConfig { timeout: Duration::from_secs(0), ..Default::default() },
+			RemoteSignerConfig { auth_token: Some("two words".into()), ..Default::default() },
+			RemoteSignerConfig { failover: vec!["ftp://signer".into()], ..Default::default() },
+			RemoteSignerConfig {
+				tls: TlsConfig { ca_certificate: Some(b"no pem".to_vec()), client_identity: None },
+				..Default::default()
+			},
+		];
+		for config in &invalid {
+			assert!(config.validate().is_err());
+		}
+	}
+}
@@ -0,0 +1,242 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+/// Stdio transport: spawns the signer as a child process and speaks
+/// newline delimited JSON-RPC over its stdin/stdout.
+
+use std::{
+	process::Stdio,
+	sync::{Arc, atomic::{AtomicBool, Ordering}},
+	time::Duration,
+};
+use futures::{
+	channel::{mpsc, oneshot},
+	future::{self, Either, FutureExt},
+	StreamExt,
+};
+use tokio::{
+	io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+	process::Command,
+};
+use tokio_stream::wrappers::LinesStream;
+use url::{Url, percent_encoding::percent_decode};
+
+use jsonrpc_client_transports::{RpcChannel, RpcError, transports::duplex::duplex};
+
+/// How long a signer that closed its connection has to exit before it is killed.
+const EXIT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Handle on a supervised signer process.
+///
+/// The process is killed once this handle is dropped.
+pub struct ChildHandle {
+	alive: Arc<AtomicBool>,
+	_kill: oneshot::Sender<()>,
+}
+
+impl ChildHandle {
+	/// Whether the process is still running and talking to us.
+	pub fn is_alive(&self) -> bool {
+		self.alive.load(Ordering::SeqCst)
+	}
+}
+
+/// Program and arguments for an `exec:///path/to/signer?arg=...&arg=...` URL.
+///
+/// Every `arg` is a single, percent-encoded argument, in the given order.
+fn command_from_url(url: &Url) -> Result<(String, Vec<String>), String> {
+	let program = percent_decode(url.path().as_bytes())
+		.decode_utf8()
+		.map_err(|e| format!("Signer path is not valid UTF-8: {:}", e))?
+		.into_owned();
+
+	if program.is_empty() || program == "/" {
+		return Err(format!("No signer program given in {:}", url))
+	}
+
+	let args = url.query_pairs()
+		.map(|(k, v)| match &*k {
+			"arg" => Ok(v.into_owned()),
+			_ => Err(format!("Unknown signer parameter '{:}', pass arguments as 'arg'", k)),
+		})
+		.collect::<Result<_, _>>()?;
+
+	Ok((program, args))
+}
+
+/// Spawn the signer given in `url` and connect a client to its stdio.
+pub async fn connect<TClient>(url: &Url) -> Result<(TClient, ChildHandle), RpcError>
+where
+	TClient: From<RpcChannel>,
+{
+	let (program, args) = command_from_url(url).map_err(RpcError::Client)?;
+
+	let mut child = Command::new(&program)
+		.args(&args)
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::inherit())
+		.kill_on_drop(true)
+		.spawn()
+		.map_err(|e| RpcError::Other(Box::new(e)))?;
+
+	let mut stdin = child.stdin.take().expect("stdin is piped; qed");
+	let stdout = child.stdout.take().expect("stdout is piped; qed");
+
+	log::info!{
+		target: "remote_keystore",
+		"Spawned signer {:} (pid {:})", program, child.id().unwrap_or_default()
+	};
+
+	let (sink, mut outgoing) = mpsc::unbounded::<String>();
+	let writer = async move {
+		while let Some(mut line) = outgoing.next().await {
+			line.push('\n');
+			if stdin.write_all(line.as_bytes()).await.is_err() || stdin.flush().await.is_err() {
+				break
+			}
+		}
+	};
+
+	let stream = LinesStream::new(BufReader::new(stdout).lines())
+		.take_while(|l| future::ready(l.is_ok()))
+		.map(|l| l.expect("Stream is closed upon first error."));
+
+	let (rpc_client, sender) = duplex(Box::pin(sink), Box::pin(stream));
+
+	let alive = Arc::new(AtomicBool::new(true));
+	let (kill, killed) = oneshot::channel::<()>();
+
+	let supervisor_alive = alive.clone();
+	tokio::spawn(async move {
+		let running = future::select(rpc_client.boxed(), writer.boxed());
+		let closed = future::select(running, killed).await;
+		supervisor_alive.store(false, Ordering::SeqCst);
+		if let Either::Right(_) = closed {
+			log::debug!{
+				target: "remote_keystore",
+				"Shutting down signer {:}", program
+			};
+			let _ = child.kill().await;
+			return
+		}
+
+		// Its connection closing usually means the signer is exiting.
+		match tokio::time::timeout(EXIT_TIMEOUT, child.wait()).await {
+			Ok(Ok(status)) => log::warn!{
+				target: "remote_keystore",
+				"Signer {:} terminated: {:}", program, status
+			},
+			Ok(Err(e)) => log::warn!{
+				target: "remote_keystore",
+				"Signer {:} closed its connection, waiting for it failed: {:}", program, e
+			},
+			Err(_) => {
+				log::warn!{
+					target: "remote_keystore",
+					"Signer {:} closed its connection, killing it", program
+				};
+				let _ = child.kill().await;
+			},
+		}
+	});
+
+	Ok((TClient::from(sender), ChildHandle { alive, _kill: kill }))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+	use std::{fs, os::unix::fs::PermissionsExt, path::{Path, PathBuf}, time::{Duration, Instant}};
+	use sp_core::crypto::KeyTypeId;
+	use sp_keystore::{Error as CryptoStoreError, SyncCryptoStore};
+	use url::Url;
+	use crate::client::{RemoteKeystore, RemoteSignerConfig};
+	use super::command_from_url;
+
+	const TEST_TK: KeyTypeId = KeyTypeId(*b"test");
+
+	/// Answers every request with an empty list, after noting its pid.
+	const STUB: &str = r#"#!/bin/sh
+echo $$ > "$0.pid"
+while read -r line; do
+	id=$(printf '%s' "$line" | sed -n 's/.*"id":\([0-9]*\).*/\1/p')
+	printf '{"jsonrpc":"2.0","result":[],"id":%s}\n' "$id"
+done
+"#;
+
+	fn stub() -> PathBuf {
+		let path = std::env::temp_dir().join(format!("tssrs-stub-signer-{}.sh", std::process::id()));
+		fs::write(&path, STUB).unwrap();
+		fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+		path
+	}
+
+	/// Kill the stub and wait for the keystore to notice.
+	fn kill(stub: &Path, keystore: &RemoteKeystore) {
+		let pid = fs::read_to_string(stub.with_extension("sh.pid")).unwrap();
+		let killed = std::process::Command::new("kill").args(["-9", pid.trim()]).status().unwrap();
+		assert!(killed.success());
+		let deadline = Instant::now() + Duration::from_secs(5);
+		while keystore.status().connected {
+			assert!(Instant::now() < deadline, "The dead signer was not noticed");
+			std::thread::sleep(Duration::from_millis(10));
+		}
+	}
+
+	#[test]
+	fn takes_one_argument_per_parameter() {
+		let url = Url::parse("exec:///bin/signer?arg=--keystore-path&arg=my%20keys&arg=a+b").unwrap();
+		assert_eq!(
+			command_from_url(&url).unwrap(),
+			("/bin/signer".into(), vec!["--keystore-path".into(), "my keys".into(), "a b".into()]),
+		);
+		let url = Url::parse("exec:///bin/signer?args=--stdio").unwrap();
+		assert!(command_from_url(&url).is_err());
+	}
+
+	#[test]
+	fn survives_the_signer_dying() {
+		let stub = stub();
+		let keystore = RemoteKeystore::open_with_config(
+			format!("exec://{}", stub.display()),
+			RemoteSignerConfig {
+				max_retry: 2,
+				backoff: Duration::from_millis(1),
+				timeout: Duration::from_secs(2),
+				cache_ttl: Duration::from_secs(0),
+				..Default::default()
+			},
+		).unwrap();
+		assert_eq!(SyncCryptoStore::keys(&keystore, TEST_TK).unwrap(), vec![]);
+
+		// respawned on the next call
+		kill(&stub, &keystore);
+		assert_eq!(SyncCryptoStore::keys(&keystore, TEST_TK).unwrap(), vec![]);
+
+		// gone for good
+		kill(&stub, &keystore);
+		fs::remove_file(&stub).unwrap();
+		assert!(matches!(
+			SyncCryptoStore::keys(&keystore, TEST_TK),
+			Err(CryptoStoreError::Unavailable),
+		));
+		assert!(SyncCryptoStore::sr25519_public_keys(&keystore, TEST_TK).is_empty());
+
+		let _ = fs::remove_file(stub.with_extension("sh.pid"));
+	}
+}
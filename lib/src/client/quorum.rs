@@ -0,0 +1,660 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+/// M-of-N signing over several independent signers.
+///
+/// Every key of a [`QuorumKeystore`] has to be provisioned on each of its
+/// signers. A signature is only handed out once `threshold` signers have
+/// produced it: for the deterministic ed25519 and ecdsa schemes they must
+/// return the identical signature, for sr25519 (whose signatures are
+/// randomized) `threshold` signatures must verify, and VRF outputs must be
+/// identical. Signatures and VRF proofs that don't verify never count
+/// towards the quorum. Signers answering differently from the quorum, also
+/// after it was reached, are logged as security events on the
+/// `remote_keystore::security` target and counted. So are signers outside
+/// the largest group of answers when no quorum is reached.
+
+use std::{sync::{Arc, atomic::{AtomicU64, Ordering}}, time::Duration};
+use async_trait::async_trait;
+use futures::{executor::block_on, future::join_all, stream::FuturesUnordered, StreamExt};
+use sp_core::{
+	crypto::{CryptoTypePublicPair, KeyTypeId},
+	sr25519::{self, Public as Sr25519Public},
+	ed25519, ecdsa,
+};
+use sp_keystore::{
+	CryptoStore, Error as CryptoStoreError, SyncCryptoStore,
+	vrf::{VRFTranscriptData, VRFSignature},
+};
+
+use crate::wire::VrfSignature;
+use super::{RemoteKeystore, bridge::Bridge, verify};
+
+/// Log target of security events.
+pub(super) const SECURITY_TARGET: &str = "remote_keystore::security";
+
+/// How long signers answering after the quorum are still checked for divergence.
+const STRAGGLER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Keystore requiring `threshold` of its signers to agree before signing.
+pub struct QuorumKeystore {
+	signers: Vec<Arc<dyn CryptoStore>>,
+	threshold: usize,
+	divergences: Arc<Vec<AtomicU64>>,
+	/// Checks the answers arriving after the quorum was reached.
+	bridge: Bridge,
+}
+
+fn blame(divergences: &[AtomicU64], index: usize, how: &str, what: &str) {
+	log::error!(target: SECURITY_TARGET, "Signer {:} {:} {:}", index, how, what);
+	divergences[index].fetch_add(1, Ordering::Relaxed);
+}
+
+/// Items listed by at least `threshold` of the `lists`.
+fn quorum_of<T: PartialEq>(lists: Vec<Vec<T>>, threshold: usize) -> Vec<T> {
+	let mut counted: Vec<(T, usize)> = vec![];
+	for list in lists {
+		for item in list {
+			match counted.iter_mut().find(|(i, _)| *i == item) {
+				Some((_, count)) => *count += 1,
+				None => counted.push((item, 1)),
+			}
+		}
+	}
+	counted.into_iter().filter(|(_, count)| *count >= threshold).map(|(i, _)| i).collect()
+}
+
+impl QuorumKeystore {
+	/// Require `threshold` of the given `signers` to agree.
+	pub fn new(signers: Vec<Arc<dyn CryptoStore>>, threshold: usize) -> Result<Self, String> {
+		if threshold == 0 || threshold > signers.len() {
+			return Err(format!(
+				"Quorum of {:} out of {:} signers is not possible", threshold, signers.len()
+			))
+		}
+		let divergences = Arc::new(signers.iter().map(|_| AtomicU64::new(0)).collect());
+		let bridge = Bridge::start("quorum-keystore")?;
+		Ok(QuorumKeystore { signers, threshold, divergences, bridge })
+	}
+
+	/// Connect to the remote signers at `urls`, requiring `threshold` of them to agree.
+	pub fn open(urls: Vec<String>, threshold: usize, max_retry: Option<u8>) -> Result<Self, String> {
+		let signers = urls.into_iter()
+			.map(|url| RemoteKeystore::open(url, max_retry).map(Into::into))
+			.collect::<Result<Vec<_>, _>>()?;
+		Self::new(signers, threshold)
+	}
+
+	/// Ask every signer, returning the first result `accept`ed by `threshold` of them.
+	///
+	/// Accepted results are grouped into votes by `same`. Once a vote wins,
+	/// every signer that answered differently is blamed for diverging, and
+	/// the signers yet to answer are checked against the winner in the
+	/// background, for at most [`STRAGGLER_TIMEOUT`]. Without a winner the
+	/// signers outside the largest vote are blamed, or all of them if it is tied.
+	async fn vote<T, Fut, F, A, S>(
+		&self,
+		what: String,
+		call: F,
+		accept: A,
+		same: S,
+	) -> Result<T, CryptoStoreError> where
+		T: Clone + Send + Sync + 'static,
+		Fut: std::future::Future<Output = Result<T, CryptoStoreError>> + Send + 'static,
+		F: Fn(Arc<dyn CryptoStore>) -> Fut,
+		A: Fn(&T) -> bool + Send + Sync + 'static,
+		S: Fn(&T, &T) -> bool + Send + Sync + 'static,
+	{
+		let mut pending = self.signers.iter()
+			.enumerate()
+			.map(|(index, signer)| {
+				let response = call(signer.clone());
+				async move { (index, response.await) }
+			})
+			.collect::<FuturesUnordered<_>>();
+
+		// Each distinct result with the signers that returned it.
+		let mut votes: Vec<(T, Vec<usize>)> = vec![];
+		let mut accepted = 0;
+		let mut remaining = self.signers.len();
+		let mut winner = None;
+		while let Some((index, response)) = pending.next().await {
+			remaining -= 1;
+			match response {
+				Ok(result) if accept(&result) => {
+					accepted += 1;
+					let vote = match votes.iter().position(|(r, _)| same(r, &result)) {
+						Some(vote) => vote,
+						None => { votes.push((result, vec![])); votes.len() - 1 },
+					};
+					votes[vote].1.push(index);
+					if votes[vote].1.len() >= self.threshold {
+						winner = Some(vote);
+						break
+					}
+				},
+				Ok(_) => self.blame(index, "returned an invalid result for", &what),
+				Err(e) => log::warn!{
+					target: "remote_keystore",
+					"Signer {:} failed on {:}: {:}", index, what, e
+				},
+			}
+
+			let best = votes.iter().map(|(_, signers)| signers.len()).max().unwrap_or(0);
+			if best + remaining < self.threshold {
+				break
+			}
+		}
+
+		let winner = match winner {
+			Some(winner) => votes.swap_remove(winner).0,
+			None => {
+				if votes.len() > 1 {
+					log::error!{
+						target: SECURITY_TARGET,
+						"Signers disagreed on {:}: {:?}",
+						what, votes.iter().map(|(_, signers)| signers).collect::<Vec<_>>()
+					};
+					// Without a majority there is no telling who is right.
+					let best = votes.iter().map(|(_, signers)| signers.len()).max().unwrap_or(0);
+					let majority = votes.iter().filter(|(_, signers)| signers.len() == best).count() == 1;
+					for (_, signers) in votes.iter().filter(|(_, signers)| !majority || signers.len() < best) {
+						for index in signers {
+							self.blame(*index, "disagreed with the other signers on", &what);
+						}
+					}
+				}
+				return Err(CryptoStoreError::Other(format!(
+					"No quorum for {:}: {:} of {:} signers answered acceptably, {:} must agree",
+					what, accepted, self.signers.len(), self.threshold
+				)))
+			},
+		};
+
+		for index in votes.into_iter().flat_map(|(_, signers)| signers) {
+			self.blame(index, "diverged from the quorum on", &what);
+		}
+
+		if !pending.is_empty() {
+			let agreed = winner.clone();
+			let divergences = self.divergences.clone();
+			self.bridge.spawn(async move {
+				let audit = async {
+					while let Some((index, response)) = pending.next().await {
+						match response {
+							Ok(result) if accept(&result) && same(&agreed, &result) => {},
+							Ok(_) => blame(&divergences, index, "diverged from the quorum on", &what),
+							Err(e) => log::warn!{
+								target: "remote_keystore",
+								"Signer {:} failed on {:}: {:}", index, what, e
+							},
+						}
+					}
+				};
+				if tokio::time::timeout(STRAGGLER_TIMEOUT, audit).await.is_err() {
+					log::warn!{
+						target: "remote_keystore",
+						"Not all signers answered {:} within {:?}", what, STRAGGLER_TIMEOUT
+					};
+				}
+			});
+		}
+
+		Ok(winner)
+	}
+
+	/// Log and count that signer `index` misbehaved.
+	fn blame(&self, index: usize, how: &str, what: &str) {
+		blame(&self.divergences, index, how, what)
+	}
+
+	/// How often each signer diverged from the quorum or returned an
+	/// invalid result, in the order of the signers.
+	pub fn divergences(&self) -> Vec<u64> {
+		self.divergences.iter().map(|d| d.load(Ordering::Relaxed)).collect()
+	}
+
+	/// Generate a key from `seed` on every signer, requiring all of them to agree.
+	async fn generate_everywhere<T, Fut, F>(&self, seed: Option<&str>, generate: F)
+		-> Result<T, CryptoStoreError> where
+		T: PartialEq,
+		Fut: std::future::Future<Output = Result<T, CryptoStoreError>>,
+		F: Fn(Arc<dyn CryptoStore>, String) -> Fut,
+	{
+		let seed = seed.ok_or_else(|| CryptoStoreError::Other(
+			"Keys of a quorum must be derived from the same seed on every signer".into()
+		))?;
+		let mut results = join_all(
+			self.signers.iter().map(|s| generate(s.clone(), seed.to_string()))
+		).await.into_iter().collect::<Result<Vec<_>, _>>()?;
+		let first = results.remove(0);
+		if results.iter().any(|r| *r != first) {
+			log::error!(target: SECURITY_TARGET, "Signers derived different keys from the same seed");
+			return Err(CryptoStoreError::Other("Signers derived different keys".into()))
+		}
+		Ok(first)
+	}
+}
+
+#[async_trait]
+impl CryptoStore for QuorumKeystore {
+	async fn keys(
+		&self,
+		id: KeyTypeId
+	) -> std::result::Result<Vec<CryptoTypePublicPair>, CryptoStoreError> {
+		let lists = join_all(self.signers.iter().map(|s| s.keys(id))).await
+			.into_iter()
+			.filter_map(Result::ok)
+			.collect();
+		Ok(quorum_of(lists, self.threshold))
+	}
+
+	async fn supported_keys(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>
+	) -> std::result::Result<Vec<CryptoTypePublicPair>, CryptoStoreError> {
+		let lists = join_all(self.signers.iter().map(|s| s.supported_keys(id, keys.clone()))).await
+			.into_iter()
+			.filter_map(Result::ok)
+			.collect();
+		Ok(quorum_of(lists, self.threshold))
+	}
+
+	async fn sign_with(
+		&self,
+		id: KeyTypeId,
+		key: &CryptoTypePublicPair,
+		msg: &[u8],
+	) -> std::result::Result<Vec<u8>, CryptoStoreError> {
+		let what = format!("signing with {:?} key {:?}", id, key);
+		let call = |signer: Arc<dyn CryptoStore>| {
+			let (key, msg) = (key.clone(), msg.to_vec());
+			async move { signer.sign_with(id, &key, &msg).await }
+		};
+
+		let randomized = key.0 == sr25519::CRYPTO_ID;
+		let (key, msg) = (key.clone(), msg.to_vec());
+		let accept = move |sig: &Vec<u8>| verify::signature(&key, &msg, sig);
+		if randomized {
+			self.vote(what, call, accept, |_, _| true).await
+		} else {
+			self.vote(what, call, accept, |a, b| a == b).await
+		}
+	}
+
+	async fn sr25519_public_keys(&self, key_type: KeyTypeId) -> Vec<sr25519::Public> {
+		let lists = join_all(self.signers.iter().map(|s| s.sr25519_public_keys(key_type))).await;
+		quorum_of(lists, self.threshold)
+	}
+
+	async fn sr25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<sr25519::Public, CryptoStoreError> {
+		self.generate_everywhere(seed, |signer, seed| async move {
+			signer.sr25519_generate_new(id, Some(&seed)).await
+		}).await
+	}
+
+	async fn ed25519_public_keys(&self, key_type: KeyTypeId) -> Vec<ed25519::Public> {
+		let lists = join_all(self.signers.iter().map(|s| s.ed25519_public_keys(key_type))).await;
+		quorum_of(lists, self.threshold)
+	}
+
+	async fn ed25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<ed25519::Public, CryptoStoreError> {
+		self.generate_everywhere(seed, |signer, seed| async move {
+			signer.ed25519_generate_new(id, Some(&seed)).await
+		}).await
+	}
+
+	async fn ecdsa_public_keys(&self, key_type: KeyTypeId) -> Vec<ecdsa::Public> {
+		let lists = join_all(self.signers.iter().map(|s| s.ecdsa_public_keys(key_type))).await;
+		quorum_of(lists, self.threshold)
+	}
+
+	async fn ecdsa_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<ecdsa::Public, CryptoStoreError> {
+		self.generate_everywhere(seed, |signer, seed| async move {
+			signer.ecdsa_generate_new(id, Some(&seed)).await
+		}).await
+	}
+
+	async fn insert_unknown(&self, key_type: KeyTypeId, suri: &str, public: &[u8])
+		-> std::result::Result<(), ()>
+	{
+		join_all(self.signers.iter().map(|s| s.insert_unknown(key_type, suri, public))).await
+			.into_iter()
+			.collect()
+	}
+
+	async fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		join_all(self.signers.iter().map(|s| s.has_keys(public_keys))).await
+			.into_iter()
+			.filter(|has| *has)
+			.count() >= self.threshold
+	}
+
+	async fn sr25519_vrf_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &Sr25519Public,
+		transcript_data: VRFTranscriptData,
+	) -> std::result::Result<VRFSignature, CryptoStoreError> {
+		// The VRF output is deterministic, only the proof is randomized.
+		let (signed_by, transcript) = (*public, transcript_data.clone());
+		self.vote(
+			format!("VRF signing with {:?} key {:?}", key_type, public),
+			|signer: Arc<dyn CryptoStore>| {
+				let (public, transcript_data) = (*public, transcript_data.clone());
+				async move {
+					signer.sr25519_vrf_sign(key_type, &public, transcript_data).await.map(VrfSignature)
+				}
+			},
+			move |signature| verify::vrf(&signed_by, transcript.clone(), &signature.0),
+			|a, b| a.0.output == b.0.output,
+		).await.map(Into::into)
+	}
+}
+
+impl SyncCryptoStore for QuorumKeystore {
+	fn keys(
+		&self,
+		id: KeyTypeId
+	) -> std::result::Result<Vec<CryptoTypePublicPair>, CryptoStoreError> {
+		block_on(CryptoStore::keys(self, id))
+	}
+
+	fn supported_keys(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>
+	) -> std::result::Result<Vec<CryptoTypePublicPair>, CryptoStoreError> {
+		block_on(CryptoStore::supported_keys(self, id, keys))
+	}
+
+	fn sign_with(
+		&self,
+		id: KeyTypeId,
+		key: &CryptoTypePublicPair,
+		msg: &[u8],
+	) -> std::result::Result<Vec<u8>, CryptoStoreError> {
+		block_on(CryptoStore::sign_with(self, id, key, msg))
+	}
+
+	fn sr25519_public_keys(&self, key_type: KeyTypeId) -> Vec<sr25519::Public> {
+		block_on(CryptoStore::sr25519_public_keys(self, key_type))
+	}
+
+	fn sr25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<sr25519::Public, CryptoStoreError> {
+		block_on(CryptoStore::sr25519_generate_new(self, id, seed))
+	}
+
+	fn ed25519_public_keys(&self, key_type: KeyTypeId) -> Vec<ed25519::Public> {
+		block_on(CryptoStore::ed25519_public_keys(self, key_type))
+	}
+
+	fn ed25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<ed25519::Public, CryptoStoreError> {
+		block_on(CryptoStore::ed25519_generate_new(self, id, seed))
+	}
+
+	fn ecdsa_public_keys(&self, key_type: KeyTypeId) -> Vec<ecdsa::Public> {
+		block_on(CryptoStore::ecdsa_public_keys(self, key_type))
+	}
+
+	fn ecdsa_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<ecdsa::Public, CryptoStoreError> {
+		block_on(CryptoStore::ecdsa_generate_new(self, id, seed))
+	}
+
+	fn insert_unknown(&self, key_type: KeyTypeId, suri: &str, public: &[u8])
+		-> std::result::Result<(), ()>
+	{
+		block_on(CryptoStore::insert_unknown(self, key_type, suri, public))
+	}
+
+	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		block_on(CryptoStore::has_keys(self, public_keys))
+	}
+
+	fn sr25519_vrf_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &Sr25519Public,
+		transcript_data: VRFTranscriptData,
+	) -> std::result::Result<VRFSignature, CryptoStoreError> {
+		block_on(CryptoStore::sr25519_vrf_sign(
+			self, key_type, public, transcript_data))
+	}
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+	use super::*;
+	use sc_keystore::LocalKeystore;
+	use sp_core::Pair;
+
+	const GRAN: KeyTypeId = KeyTypeId(*b"gran");
+
+	fn signers(n: usize) -> Vec<Arc<LocalKeystore>> {
+		(0..n).map(|_| Arc::new(LocalKeystore::in_memory())).collect()
+	}
+
+	fn quorum(signers: &[Arc<LocalKeystore>], threshold: usize) -> QuorumKeystore {
+		let signers = signers.iter().map(|s| s.clone() as Arc<dyn CryptoStore>).collect();
+		QuorumKeystore::new(signers, threshold).unwrap()
+	}
+
+	#[tokio::test]
+	async fn signs_once_enough_signers_agree() {
+		let stores = signers(3);
+		let keystore = quorum(&stores, 2);
+		let public = CryptoStore::ed25519_generate_new(&keystore, GRAN, Some("//Alice")).await.unwrap();
+		let key = CryptoTypePublicPair(ed25519::CRYPTO_ID, public.0.to_vec());
+
+		assert!(CryptoStore::sign_with(&keystore, GRAN, &key, b"msg").await.is_ok());
+
+		// Losing one signer still leaves a quorum.
+		let keystore = quorum(&[stores[0].clone(), stores[1].clone(), signers(1).remove(0)], 2);
+		assert!(CryptoStore::sign_with(&keystore, GRAN, &key, b"msg").await.is_ok());
+		assert_eq!(CryptoStore::ed25519_public_keys(&keystore, GRAN).await, vec![public]);
+
+		let keystore = quorum(&[stores[0].clone(), signers(1).remove(0)], 2);
+		assert!(CryptoStore::sign_with(&keystore, GRAN, &key, b"msg").await.is_err());
+		assert!(CryptoStore::ed25519_public_keys(&keystore, GRAN).await.is_empty());
+	}
+
+	#[tokio::test]
+	async fn counts_verified_sr25519_approvals() {
+		let stores = signers(3);
+		let keystore = quorum(&stores, 3);
+		let public = CryptoStore::sr25519_generate_new(&keystore, GRAN, Some("//Bob")).await.unwrap();
+		let key = CryptoTypePublicPair(sr25519::CRYPTO_ID, public.0.to_vec());
+
+		let signature = CryptoStore::sign_with(&keystore, GRAN, &key, b"msg").await.unwrap();
+		assert!(verify::signature(&key, b"msg", &signature));
+	}
+
+	/// A signer answering after `delay`, optionally with a signature or VRF
+	/// proof of the wrong message.
+	struct Delayed {
+		store: Arc<LocalKeystore>,
+		delay: Duration,
+		forge: bool,
+	}
+
+	#[async_trait]
+	impl CryptoStore for Delayed {
+		async fn keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, CryptoStoreError> {
+			CryptoStore::keys(&*self.store, id).await
+		}
+		async fn supported_keys(&self, id: KeyTypeId, keys: Vec<CryptoTypePublicPair>)
+			-> Result<Vec<CryptoTypePublicPair>, CryptoStoreError>
+		{
+			CryptoStore::supported_keys(&*self.store, id, keys).await
+		}
+		async fn sign_with(&self, id: KeyTypeId, key: &CryptoTypePublicPair, msg: &[u8])
+			-> Result<Vec<u8>, CryptoStoreError>
+		{
+			tokio::time::sleep(self.delay).await;
+			let msg = if self.forge { &b"forged"[..] } else { msg };
+			CryptoStore::sign_with(&*self.store, id, key, msg).await
+		}
+		async fn sr25519_public_keys(&self, id: KeyTypeId) -> Vec<sr25519::Public> {
+			CryptoStore::sr25519_public_keys(&*self.store, id).await
+		}
+		async fn sr25519_generate_new(&self, id: KeyTypeId, seed: Option<&str>)
+			-> Result<sr25519::Public, CryptoStoreError>
+		{
+			CryptoStore::sr25519_generate_new(&*self.store, id, seed).await
+		}
+		async fn ed25519_public_keys(&self, id: KeyTypeId) -> Vec<ed25519::Public> {
+			CryptoStore::ed25519_public_keys(&*self.store, id).await
+		}
+		async fn ed25519_generate_new(&self, id: KeyTypeId, seed: Option<&str>)
+			-> Result<ed25519::Public, CryptoStoreError>
+		{
+			CryptoStore::ed25519_generate_new(&*self.store, id, seed).await
+		}
+		async fn ecdsa_public_keys(&self, id: KeyTypeId) -> Vec<ecdsa::Public> {
+			CryptoStore::ecdsa_public_keys(&*self.store, id).await
+		}
+		async fn ecdsa_generate_new(&self, id: KeyTypeId, seed: Option<&str>)
+			-> Result<ecdsa::Public, CryptoStoreError>
+		{
+			CryptoStore::ecdsa_generate_new(&*self.store, id, seed).await
+		}
+		async fn insert_unknown(&self, id: KeyTypeId, suri: &str, public: &[u8]) -> Result<(), ()> {
+			CryptoStore::insert_unknown(&*self.store, id, suri, public).await
+		}
+		async fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+			CryptoStore::has_keys(&*self.store, public_keys).await
+		}
+		async fn sr25519_vrf_sign(
+			&self,
+			id: KeyTypeId,
+			public: &Sr25519Public,
+			transcript_data: VRFTranscriptData,
+		) -> Result<VRFSignature, CryptoStoreError> {
+			tokio::time::sleep(self.delay).await;
+			let transcript_data = if self.forge { transcript(0) } else { transcript_data };
+			CryptoStore::sr25519_vrf_sign(&*self.store, id, public, transcript_data).await
+		}
+	}
+
+	fn transcript(slot: u64) -> VRFTranscriptData {
+		VRFTranscriptData {
+			label: b"test",
+			items: vec![("slot", sp_keystore::vrf::VRFTranscriptValue::U64(slot))],
+		}
+	}
+
+	fn delayed(delays: &[(u64, bool)]) -> (QuorumKeystore, CryptoTypePublicPair) {
+		let signers = delays.iter()
+			.map(|(delay, forge)| {
+				let store = Arc::new(LocalKeystore::in_memory());
+				SyncCryptoStore::ed25519_generate_new(&*store, GRAN, Some("//Alice")).unwrap();
+				SyncCryptoStore::sr25519_generate_new(&*store, GRAN, Some("//Alice")).unwrap();
+				Arc::new(Delayed { store, delay: Duration::from_millis(*delay), forge: *forge })
+					as Arc<dyn CryptoStore>
+			})
+			.collect();
+		let public = ed25519::Pair::from_string("//Alice", None).unwrap().public();
+		let key = CryptoTypePublicPair(ed25519::CRYPTO_ID, public.0.to_vec());
+		(QuorumKeystore::new(signers, 2).unwrap(), key)
+	}
+
+	#[tokio::test]
+	async fn blames_a_bad_signer_answering_first() {
+		let (keystore, key) = delayed(&[(0, true), (50, false), (100, false)]);
+
+		let signature = CryptoStore::sign_with(&keystore, GRAN, &key, b"msg").await.unwrap();
+		assert!(verify::signature(&key, b"msg", &signature));
+		assert_eq!(keystore.divergences(), vec![1, 0, 0]);
+	}
+
+	#[tokio::test]
+	async fn blames_a_bad_signer_answering_after_the_quorum() {
+		let (keystore, key) = delayed(&[(0, false), (50, true), (0, false)]);
+
+		let signature = CryptoStore::sign_with(&keystore, GRAN, &key, b"msg").await.unwrap();
+		assert!(verify::signature(&key, b"msg", &signature));
+		tokio::time::sleep(Duration::from_millis(500)).await;
+		assert_eq!(keystore.divergences(), vec![0, 1, 0]);
+	}
+
+	#[tokio::test]
+	async fn blames_an_invalid_vrf_proof() {
+		let (keystore, _) = delayed(&[(0, true), (50, false), (100, false)]);
+		let public = sr25519::Pair::from_string("//Alice", None).unwrap().public();
+
+		let signature = CryptoStore::sr25519_vrf_sign(&keystore, GRAN, &public, transcript(1)).await.unwrap();
+		assert!(verify::vrf(&public, transcript(1), &signature));
+		assert_eq!(keystore.divergences(), vec![1, 0, 0]);
+	}
+
+	#[tokio::test]
+	async fn blames_the_minority_without_a_quorum() {
+		async fn vote_on(answers: &'static [u8], threshold: usize) -> Vec<u64> {
+			let keystore = quorum(&signers(answers.len()), threshold);
+			let next = std::sync::atomic::AtomicUsize::new(0);
+			let result = keystore.vote(
+				"test".into(),
+				|_| {
+					let answer = answers[next.fetch_add(1, Ordering::Relaxed)];
+					async move { Ok(answer) }
+				},
+				|_| true,
+				|a, b| a == b,
+			).await;
+			assert!(result.is_err());
+			keystore.divergences()
+		}
+
+		assert_eq!(vote_on(&[1, 2, 2, 3], 3).await, vec![1, 0, 0, 1]);
+		assert_eq!(vote_on(&[1, 2], 2).await, vec![1, 1]);
+		assert_eq!(vote_on(&[1, 1, 2, 2], 3).await, vec![1, 1, 1, 1]);
+	}
+
+	#[tokio::test]
+	async fn rejects_impossible_quorums() {
+		let stores = signers(2);
+		let stores = stores.iter().map(|s| s.clone() as Arc<dyn CryptoStore>).collect::<Vec<_>>();
+		assert!(QuorumKeystore::new(stores.clone(), 0).is_err());
+		assert!(QuorumKeystore::new(stores, 3).is_err());
+	}
+}
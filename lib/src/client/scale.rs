@@ -0,0 +1,335 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+/// WebSocket client for the binary SCALE framing (see [`crate::scale`]).
+
+use std::{collections::HashMap, sync::{Arc, atomic::{AtomicBool, Ordering}}};
+use codec::{Decode, Encode};
+use futures::{
+	channel::{mpsc, oneshot},
+	future, stream, SinkExt, StreamExt,
+};
+use sp_core::{
+	crypto::{CryptoTypeId, CryptoTypePublicPair, KeyTypeId},
+	ecdsa, ed25519, sr25519,
+};
+use sp_keystore::vrf::VRFSignature;
+use tokio_tungstenite::tungstenite::Message;
+
+use jsonrpc_client_transports::RpcError;
+
+use crate::{
+	Capabilities,
+	TransferableVRFTranscriptData,
+	scale::{Request, ResponsePayload, decode_vrf_signature},
+};
+
+use super::transport::{Alive, Socket};
+
+type Pending = (Request, oneshot::Sender<ResponsePayload>);
+
+/// Client speaking binary SCALE frames to a remote signer.
+pub struct ScaleClient {
+	sender: mpsc::UnboundedSender<Pending>,
+}
+
+enum Event {
+	Outgoing(Pending),
+	Incoming(Result<Message, tokio_tungstenite::tungstenite::Error>),
+	Closed,
+}
+
+/// Error code pending calls fail with once the connection is gone.
+const CONNECTION_CLOSED: i64 = -32000;
+/// Error code calls fail with whose response can't be decoded.
+const PARSE_ERROR: i64 = -32700;
+
+/// Speak binary SCALE frames over `socket`, on which the subprotocol was agreed.
+pub fn connect(socket: Socket) -> (ScaleClient, Alive) {
+	let (mut sink, incoming) = socket.split();
+	let (sender, outgoing) = mpsc::unbounded::<Pending>();
+	let alive = Arc::new(AtomicBool::new(true));
+	let worker_alive = alive.clone();
+
+	tokio::spawn(async move {
+		let mut pending: HashMap<u64, oneshot::Sender<ResponsePayload>> = HashMap::new();
+		let mut next_id = 0u64;
+		let mut events = stream::select(
+			outgoing.map(Event::Outgoing),
+			incoming.map(Event::Incoming).chain(stream::once(future::ready(Event::Closed))),
+		);
+
+		while let Some(event) = events.next().await {
+			match event {
+				Event::Outgoing((request, responder)) => {
+					let id = next_id;
+					next_id = next_id.wrapping_add(1);
+					pending.insert(id, responder);
+					if sink.send(Message::Binary((id, request).encode())).await.is_err() {
+						break
+					}
+				},
+				Event::Incoming(Ok(Message::Binary(frame))) => {
+					let (id, payload) = decode_response(&frame);
+					match id.and_then(|id| pending.remove(&id)) {
+						Some(responder) => { let _ = responder.send(payload); },
+						None => if let Err((_, message)) = payload {
+							log::warn!(target: "remote_keystore", "Unmatched error from signer: {:}", message);
+						},
+					}
+				},
+				Event::Incoming(Ok(Message::Close(_))) | Event::Incoming(Err(_)) | Event::Closed => break,
+				Event::Incoming(Ok(_)) => {},
+			}
+		}
+		worker_alive.store(false, Ordering::SeqCst);
+		for (_, responder) in pending.drain() {
+			let _ = responder.send(Err((CONNECTION_CLOSED, "Connection closed".into())));
+		}
+		log::debug!(target: "remote_keystore", "SCALE connection closed");
+	});
+
+	(ScaleClient { sender }, alive)
+}
+
+/// Split a response frame into the id of the call it answers and its payload.
+///
+/// A frame whose payload can't be decoded still fails the call it carries the
+/// id of, so that call doesn't wait forever.
+fn decode_response(mut frame: &[u8]) -> (Option<u64>, ResponsePayload) {
+	let id = match u64::decode(&mut frame) {
+		Ok(id) => id,
+		Err(e) => return (None, Err((PARSE_ERROR, format!("Undecodable frame: {:}", e)))),
+	};
+	match ResponsePayload::decode(&mut frame) {
+		Ok(payload) => (Some(id), payload),
+		Err(e) => (Some(id), Err((PARSE_ERROR, format!("Undecodable response: {:}", e)))),
+	}
+}
+
+impl ScaleClient {
+	async fn call<R: Decode>(&self, request: Request) -> Result<R, RpcError> {
+		let (responder, response) = oneshot::channel();
+		self.sender.unbounded_send((request, responder))
+			.map_err(|_| RpcError::Client("Connection closed".into()))?;
+		let payload = response.await
+			.map_err(|_| RpcError::Client("Connection closed".into()))?
+			.map_err(|(code, message)| RpcError::JsonRpcError(jsonrpc_core::Error {
+				code: code.into(),
+				message,
+				data: None,
+			}))?;
+		R::decode(&mut &payload[..]).map_err(|e| RpcError::ParseError(
+			std::any::type_name::<R>().into(),
+			Box::new(std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())),
+		))
+	}
+
+	pub async fn sr25519_public_keys(&self, id: KeyTypeId) -> Result<Vec<sr25519::Public>, RpcError> {
+		self.call(Request::Sr25519PublicKeys(id)).await
+	}
+
+	pub async fn sr25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<String>,
+	) -> Result<sr25519::Public, RpcError> {
+		self.call(Request::Sr25519GenerateNew(id, seed)).await
+	}
+
+	pub async fn ed25519_public_keys(&self, id: KeyTypeId) -> Result<Vec<ed25519::Public>, RpcError> {
+		self.call(Request::Ed25519PublicKeys(id)).await
+	}
+
+	pub async fn ed25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<String>,
+	) -> Result<ed25519::Public, RpcError> {
+		self.call(Request::Ed25519GenerateNew(id, seed)).await
+	}
+
+	pub async fn ecdsa_public_keys(&self, id: KeyTypeId) -> Result<Vec<ecdsa::Public>, RpcError> {
+		self.call(Request::EcdsaPublicKeys(id)).await
+	}
+
+	pub async fn ecdsa_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<String>,
+	) -> Result<ecdsa::Public, RpcError> {
+		self.call(Request::EcdsaGenerateNew(id, seed)).await
+	}
+
+	pub async fn insert_unknown(
+		&self,
+		key_type: KeyTypeId,
+		suri: String,
+		public: Vec<u8>,
+	) -> Result<(), RpcError> {
+		self.call(Request::InsertUnknown(key_type, suri, public)).await
+	}
+
+	pub async fn supported_keys(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+	) -> Result<Vec<CryptoTypePublicPair>, RpcError> {
+		self.call(Request::SupportedKeys(id, keys)).await
+	}
+
+	pub async fn keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, RpcError> {
+		self.call(Request::Keys(id)).await
+	}
+
+	pub async fn has_keys(&self, public_keys: Vec<(Vec<u8>, KeyTypeId)>) -> Result<bool, RpcError> {
+		self.call(Request::HasKeys(public_keys)).await
+	}
+
+	pub async fn sign_with(
+		&self,
+		id: KeyTypeId,
+		key: CryptoTypePublicPair,
+		msg: Vec<u8>,
+	) -> Result<Vec<u8>, RpcError> {
+		self.call(Request::SignWith(id, key, msg)).await
+	}
+
+	pub async fn sign_with_any(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+		msg: Vec<u8>,
+	) -> Result<(CryptoTypePublicPair, Vec<u8>), RpcError> {
+		self.call(Request::SignWithAny(id, keys, msg)).await
+	}
+
+	pub async fn sign_with_all(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+		msg: Vec<u8>,
+	) -> Result<Vec<Result<Vec<u8>, String>>, RpcError> {
+		self.call(Request::SignWithAll(id, keys, msg)).await
+	}
+
+	pub async fn sr25519_vrf_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: sr25519::Public,
+		transcript_data: TransferableVRFTranscriptData,
+	) -> Result<VRFSignature, RpcError> {
+		let encoded: Vec<u8> = self.call(
+			Request::Sr25519VrfSign(key_type, public, transcript_data.into())
+		).await?;
+		decode_vrf_signature(&encoded).map_err(RpcError::Client)
+	}
+
+	pub async fn remove_key(&self, key_type: KeyTypeId, public: Vec<u8>) -> Result<(), RpcError> {
+		self.call(Request::RemoveKey(key_type, public)).await
+	}
+
+	pub async fn rotate_keys(&self, key_types: Vec<KeyTypeId>) -> Result<Vec<u8>, RpcError> {
+		self.call(Request::RotateKeys(key_types)).await
+	}
+
+	pub async fn generate_session_keys(
+		&self,
+		keys: Vec<(KeyTypeId, CryptoTypeId)>,
+	) -> Result<Vec<u8>, RpcError> {
+		self.call(Request::GenerateSessionKeys(keys)).await
+	}
+
+	pub async fn capabilities(&self) -> Result<Capabilities, RpcError> {
+		self.call(Request::Capabilities).await
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+	use tokio_tungstenite::{WebSocketStream, tungstenite::protocol::Role};
+	use super::*;
+
+	const TEST_TK: KeyTypeId = KeyTypeId(*b"test");
+
+	async fn pair() -> (ScaleClient, Alive, WebSocketStream<tokio::io::DuplexStream>) {
+		let (client, server) = tokio::io::duplex(4096);
+		let client: Box<dyn crate::client::transport::Io> = Box::new(client);
+		let socket = WebSocketStream::from_raw_socket(client, Role::Client, None).await;
+		let server = WebSocketStream::from_raw_socket(server, Role::Server, None).await;
+		let (client, alive) = connect(socket);
+		(client, alive, server)
+	}
+
+	async fn next_request(server: &mut WebSocketStream<tokio::io::DuplexStream>) -> (u64, Request) {
+		match server.next().await {
+			Some(Ok(Message::Binary(frame))) => <(u64, Request)>::decode(&mut &frame[..]).unwrap(),
+			other => panic!("Expected a binary frame, got {:?}", other),
+		}
+	}
+
+	#[tokio::test]
+	async fn answers_calls() {
+		let (client, _alive, mut server) = pair().await;
+		let key = sr25519::Public::from_raw([1; 32]);
+		tokio::spawn(async move {
+			let (id, _) = next_request(&mut server).await;
+			let payload: ResponsePayload = Ok(vec![key].encode());
+			server.send(Message::Binary((id, payload).encode())).await.unwrap();
+			let (id, _) = next_request(&mut server).await;
+			let payload: ResponsePayload = Err((-32000, "nope".into()));
+			server.send(Message::Binary((id, payload).encode())).await.unwrap();
+			let (id, _) = next_request(&mut server).await;
+			let payload: ResponsePayload = Ok(vec![0xff]);
+			server.send(Message::Binary((id, payload).encode())).await.unwrap();
+			let _ = next_request(&mut server).await;
+			server.send(Message::Binary(vec![1, 2])).await.unwrap();
+			futures::future::pending::<()>().await;
+		});
+
+		assert_eq!(client.sr25519_public_keys(TEST_TK).await.unwrap(), vec![key]);
+		match client.sr25519_public_keys(TEST_TK).await {
+			Err(RpcError::JsonRpcError(e)) => assert_eq!(e.message, "nope"),
+			other => panic!("Expected the signer's error, got {:?}", other),
+		}
+		assert!(client.sr25519_public_keys(TEST_TK).await.is_err());
+		// an unmatched frame doesn't answer the pending call
+		assert!(tokio::time::timeout(
+			Duration::from_millis(100),
+			client.sr25519_public_keys(TEST_TK),
+		).await.is_err());
+	}
+
+	#[tokio::test]
+	async fn fails_pending_calls_on_disconnect() {
+		let (client, alive, mut server) = pair().await;
+		tokio::spawn(async move {
+			let _ = next_request(&mut server).await;
+			drop(server);
+		});
+
+		let result = tokio::time::timeout(
+			Duration::from_secs(5),
+			client.sr25519_public_keys(TEST_TK),
+		).await.expect("Pending call fails instead of hanging");
+		assert!(result.is_err());
+		assert!(!alive.load(Ordering::SeqCst));
+		assert!(client.sr25519_public_keys(TEST_TK).await.is_err());
+	}
+}
@@ -0,0 +1,108 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+/// Health of a [`super::RemoteKeystore`], for monitoring.
+
+use std::{collections::{BTreeMap, VecDeque}, time::Duration};
+use serde::Serialize;
+
+/// Number of recent calls the latency percentiles are computed over.
+const SAMPLES: usize = 1024;
+
+/// Latency percentiles of recent calls, in milliseconds.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Latency {
+	/// Number of calls the percentiles are computed over
+	pub samples: usize,
+	/// Median
+	pub p50: f64,
+	/// 90th percentile
+	pub p90: f64,
+	/// 99th percentile
+	pub p99: f64,
+}
+
+/// What a [`super::RemoteKeystore`] knows about its signers.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteSignerStatus {
+	/// The signer and its failovers, in order
+	pub endpoints: Vec<String>,
+	/// The signer in use
+	pub current: String,
+	/// Whether the connection to it is up
+	pub connected: bool,
+	/// The last failure talking to any signer
+	pub last_error: Option<String>,
+	/// Latency of recently completed calls, including reconnects
+	pub latency: Latency,
+	/// Number of cached public keys by key type
+	pub cached_keys: BTreeMap<String, usize>,
+	/// Signatures and VRF proofs that did not verify, if they are checked
+	pub invalid_signatures: u64,
+}
+
+/// Records the calls for the [`RemoteSignerStatus`].
+#[derive(Default)]
+pub(crate) struct Stats {
+	latencies: VecDeque<Duration>,
+	pub last_error: Option<String>,
+	pub invalid_signatures: u64,
+}
+
+impl Stats {
+	/// Record a completed call that `took` so long.
+	pub fn record(&mut self, took: Duration) {
+		if self.latencies.len() == SAMPLES {
+			self.latencies.pop_front();
+		}
+		self.latencies.push_back(took);
+	}
+
+	/// Percentiles of the recorded latencies.
+	pub fn latency(&self) -> Latency {
+		let mut sorted = self.latencies.iter().copied().collect::<Vec<_>>();
+		sorted.sort();
+		let at = |quantile: f64| {
+			let index = ((sorted.len() as f64 - 1.0) * quantile).round() as usize;
+			sorted.get(index).map_or(0.0, |d| d.as_secs_f64() * 1000.0)
+		};
+		Latency { samples: sorted.len(), p50: at(0.5), p90: at(0.9), p99: at(0.99) }
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn percentiles_of_recent_calls() {
+		let mut stats = Stats::default();
+		assert_eq!(stats.latency(), Latency::default());
+
+		for ms in (1..=SAMPLES as u64 + 100).rev() {
+			stats.record(Duration::from_millis(ms));
+		}
+		// The oldest, slowest calls were dropped.
+		let latency = stats.latency();
+		assert_eq!(latency.samples, SAMPLES);
+		assert_eq!(latency.p50, 513.0);
+		assert_eq!(latency.p99, 1014.0);
+	}
+}
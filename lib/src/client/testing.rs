@@ -0,0 +1,408 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+/// In-process signers for tests of code using a [`super::RemoteKeystore`].
+///
+/// An [`InProcessSigner`] serves a keystore through a
+/// [`GenericRemoteSignerServer`] on a thread of its own and is reached as
+/// `memory://<name>`, without any socket. Its [`Faults`] make it misbehave:
+/// answer late, break or refuse connections, fail calls with an error code
+/// or hand out wrong signatures.
+
+use std::{collections::HashMap, sync::{Arc, atomic::{AtomicBool, Ordering}}, time::Duration};
+use futures::{channel::{mpsc, oneshot}, future, FutureExt, StreamExt};
+use jsonrpc_core::{Error, IoHandler, MethodCall, Output, serde_json::{self, Value}};
+use jsonrpc_client_transports::{RpcChannel, RpcError, transports::duplex::duplex};
+use parking_lot::Mutex;
+use serde::{de::DeserializeOwned, Serialize};
+use sp_keystore::CryptoStore;
+use url::Url;
+
+use crate::{
+	HexBytes, PublicPair, RemoteSignerApi, VrfSignature,
+	server::{GenericRemoteSignerServer, KeyArchive, KeystoreReceiver},
+};
+use super::{bridge::Bridge, config, transport::{self, Alive}};
+
+/// The signers currently served, by name.
+static SIGNERS: Mutex<Vec<(String, Arc<Shared>)>> = parking_lot::const_mutex(Vec::new());
+
+#[derive(Default)]
+struct State {
+	latency: Duration,
+	refuse: bool,
+	errors: HashMap<String, Error>,
+	wrong_signatures: bool,
+	/// The open connections and their hang-ups
+	connections: Vec<(Alive, oneshot::Sender<()>)>,
+}
+
+/// Switches making an [`InProcessSigner`] misbehave, effective immediately.
+#[derive(Clone, Default)]
+pub struct Faults(Arc<Mutex<State>>);
+
+impl Faults {
+	/// Answer every call `latency` late.
+	pub fn set_latency(&self, latency: Duration) {
+		self.0.lock().latency = latency;
+	}
+
+	/// Refuse new connections, or accept them again.
+	pub fn refuse_connections(&self, refuse: bool) {
+		self.0.lock().refuse = refuse;
+	}
+
+	/// Break all open connections, failing the calls in flight. Clients
+	/// reconnect on their next call, unless connections are refused.
+	pub fn drop_connections(&self) {
+		for (alive, hangup) in self.0.lock().connections.drain(..) {
+			alive.store(false, Ordering::SeqCst);
+			let _ = hangup.send(());
+		}
+	}
+
+	/// Answer all calls of `method`, e.g. `signer_sign_with`, with `error`.
+	pub fn fail(&self, method: &str, error: Error) {
+		self.0.lock().errors.insert(method.into(), error);
+	}
+
+	/// Hand out corrupted signatures and VRF proofs, or correct ones again.
+	pub fn wrong_signatures(&self, wrong: bool) {
+		self.0.lock().wrong_signatures = wrong;
+	}
+
+	/// Behave again. Connections dropped before stay dropped.
+	pub fn reset(&self) {
+		let mut state = self.0.lock();
+		state.latency = Duration::from_secs(0);
+		state.refuse = false;
+		state.errors.clear();
+		state.wrong_signatures = false;
+	}
+}
+
+struct Shared {
+	handler: IoHandler,
+	faults: Faults,
+	/// Drives the keystore of the signer
+	_bridge: Bridge,
+}
+
+impl Shared {
+	/// Answer the JSON-RPC request `body` as the faults dictate.
+	async fn answer(&self, body: String) -> Option<String> {
+		let method = serde_json::from_str::<MethodCall>(&body).ok().map(|call| call.method);
+		let (latency, error, wrong_signatures) = {
+			let state = self.faults.0.lock();
+			let error = method.as_ref().and_then(|m| state.errors.get(m).cloned());
+			(state.latency, error, state.wrong_signatures)
+		};
+
+		if latency > Duration::from_secs(0) {
+			tokio::time::sleep(latency).await;
+		}
+		if let Some(error) = error {
+			return transport::failure(&body, error)
+		}
+		let response = self.handler.handle_request(&body).await?;
+		match method {
+			Some(method) if wrong_signatures => Some(corrupt(&method, response)),
+			_ => Some(response),
+		}
+	}
+}
+
+/// A [`GenericRemoteSignerServer`] reachable as `memory://<name>` until dropped.
+pub struct InProcessSigner {
+	name: String,
+	shared: Arc<Shared>,
+}
+
+impl InProcessSigner {
+	/// Serve `store` as `name`.
+	pub fn serve<Store: CryptoStore + 'static>(name: &str, store: Store) -> Result<Self, String> {
+		let (server, receiver) = GenericRemoteSignerServer::proxy(store);
+		Self::start(name, server, receiver)
+	}
+
+	/// Serve `store` as `name`, supporting key management by retiring keys
+	/// into `archive`.
+	pub fn serve_with_archive<Store: CryptoStore + 'static>(
+		name: &str,
+		store: Store,
+		archive: impl KeyArchive + 'static,
+	) -> Result<Self, String> {
+		let (server, receiver) = GenericRemoteSignerServer::proxy_with_archive(store, archive);
+		Self::start(name, server, receiver)
+	}
+
+	fn start<Store: CryptoStore + 'static>(
+		name: &str,
+		server: GenericRemoteSignerServer,
+		receiver: KeystoreReceiver<Store>,
+	) -> Result<Self, String> {
+		config::parse_url(&format!("memory://{}", name))?;
+		let mut signers = SIGNERS.lock();
+		if signers.iter().any(|(served, _)| served == name) {
+			return Err(format!("A signer is served as {} already", name))
+		}
+
+		let bridge = Bridge::start(&format!("signer-{}", name))?;
+		bridge.spawn(receiver.for_each(|_| future::ready(())));
+		let mut handler = IoHandler::new();
+		handler.extend_with(RemoteSignerApi::to_delegate(server));
+
+		let shared = Arc::new(Shared { handler, faults: Faults::default(), _bridge: bridge });
+		signers.push((name.into(), shared.clone()));
+		Ok(InProcessSigner { name: name.into(), shared })
+	}
+
+	/// The URL to open a [`super::RemoteKeystore`] with.
+	pub fn url(&self) -> String {
+		format!("memory://{}", self.name)
+	}
+
+	/// Switches making this signer misbehave.
+	pub fn faults(&self) -> &Faults {
+		&self.shared.faults
+	}
+}
+
+impl Drop for InProcessSigner {
+	fn drop(&mut self) {
+		SIGNERS.lock().retain(|(name, _)| name != &self.name);
+		self.shared.faults.drop_connections();
+	}
+}
+
+/// Connect to the [`InProcessSigner`] at `url`.
+pub(super) fn connect(url: &Url) -> Result<(RpcChannel, Alive), RpcError> {
+	let name = url.host_str().unwrap_or_default();
+	let shared = SIGNERS.lock().iter()
+		.find(|(served, _)| served == name)
+		.map(|(_, shared)| shared.clone())
+		.ok_or_else(|| RpcError::Client(format!("No signer is served as {:}", url)))?;
+	let alive = Arc::new(AtomicBool::new(true));
+	let hungup = {
+		let mut state = shared.faults.0.lock();
+		if state.refuse {
+			return Err(RpcError::Client(format!("{:} refused the connection", url)))
+		}
+		state.connections.retain(|(_, hangup)| !hangup.is_canceled());
+		let (hangup, hungup) = oneshot::channel();
+		state.connections.push((alive.clone(), hangup));
+		hungup
+	};
+
+	let (sink, requests) = mpsc::unbounded::<String>();
+	let (responses, stream) = mpsc::unbounded::<String>();
+	let (rpc_client, channel) = duplex(Box::pin(sink), Box::pin(stream));
+
+	let worker = requests
+		.map(move |body: String| {
+			let shared = shared.clone();
+			async move { shared.answer(body).await }
+		})
+		.buffer_unordered(8)
+		.for_each(move |response| {
+			if let Some(response) = response {
+				let _ = responses.unbounded_send(response);
+			}
+			future::ready(())
+		});
+
+	let worker_alive = alive.clone();
+	tokio::spawn(async move {
+		let connection = future::select(rpc_client.boxed(), worker.boxed());
+		let _ = future::select(connection, hungup).await;
+		worker_alive.store(false, Ordering::SeqCst);
+	});
+	Ok((channel, alive))
+}
+
+/// Re-encode `value` as a `T` changed by `f`.
+fn change<T: DeserializeOwned + Serialize>(value: &Value, f: impl FnOnce(T) -> T) -> Option<Value> {
+	let changed = f(serde_json::from_value(value.clone()).ok()?);
+	serde_json::to_value(changed).ok()
+}
+
+fn flip(mut signature: HexBytes) -> HexBytes {
+	if let Some(byte) = signature.0.first_mut() {
+		*byte ^= 1;
+	}
+	signature
+}
+
+/// Corrupt the signatures or VRF proof answered to a `method` call.
+fn corrupt(method: &str, response: String) -> String {
+	let mut success = match serde_json::from_str::<Output>(&response) {
+		Ok(Output::Success(success)) => success,
+		_ => return response,
+	};
+	let result = &success.result;
+	let corrupted = match method {
+		"signer_sign_with" => change(result, flip),
+		"signer_sign_with_any" => change(result, |(key, signature): (PublicPair, HexBytes)| {
+			(key, flip(signature))
+		}),
+		"signer_sign_with_all" => change(result, |signatures: Vec<Result<HexBytes, String>>| {
+			signatures.into_iter().map(|s| s.map(flip)).collect()
+		}),
+		"signer_sr25519_vrf_sign" => change(result, |mut signature: VrfSignature| {
+			let mut proof = signature.0.proof.to_bytes();
+			proof[0] ^= 1;
+			if let Ok(proof) = schnorrkel::vrf::VRFProof::from_bytes(&proof) {
+				signature.0.proof = proof;
+			}
+			signature
+		}),
+		_ => None,
+	};
+	match corrupted {
+		Some(corrupted) => {
+			success.result = corrupted;
+			serde_json::to_string(&Output::Success(success)).unwrap_or(response)
+		},
+		None => response,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use jsonrpc_core::ErrorCode;
+	use sc_keystore::LocalKeystore;
+	use sp_core::{crypto::{CryptoTypePublicPair, KeyTypeId, Pair}, ed25519};
+	use sp_keystore::{
+		Error as CryptoStoreError, SyncCryptoStore,
+		vrf::{VRFTranscriptData, VRFTranscriptValue},
+	};
+	use crate::client::{RemoteKeystore, RemoteSignerConfig};
+
+	const TEST_TK: KeyTypeId = KeyTypeId(*b"test");
+
+	fn signer(name: &str) -> (InProcessSigner, CryptoTypePublicPair) {
+		let keystore = LocalKeystore::in_memory();
+		let public = SyncCryptoStore::ed25519_generate_new(&keystore, TEST_TK, Some("//Alice")).unwrap();
+		let key = CryptoTypePublicPair(ed25519::CRYPTO_ID, public.0.to_vec());
+		(InProcessSigner::serve(name, keystore).unwrap(), key)
+	}
+
+	fn open(signer: &InProcessSigner) -> RemoteKeystore {
+		RemoteKeystore::open_with_config(signer.url(), RemoteSignerConfig {
+			max_retry: 2,
+			backoff: Duration::from_millis(1),
+			timeout: Duration::from_millis(500),
+			..Default::default()
+		}).unwrap()
+	}
+
+	fn verifies(key: &CryptoTypePublicPair, signature: &[u8]) -> bool {
+		ed25519::Pair::verify_weak(signature, b"msg", &key.1)
+	}
+
+	#[test]
+	fn serves_in_process() {
+		let (signer, key) = signer("serves");
+		let keystore = open(&signer);
+		assert!(SyncCryptoStore::keys(&keystore, TEST_TK).unwrap().contains(&key));
+		let signature = SyncCryptoStore::sign_with(&keystore, TEST_TK, &key, b"msg").unwrap();
+		assert!(verifies(&key, &signature));
+		assert!(InProcessSigner::serve("serves", LocalKeystore::in_memory()).is_err());
+	}
+
+	#[test]
+	fn injects_faults() {
+		let (signer, key) = signer("faults");
+		let keystore = open(&signer);
+		let faults = signer.faults();
+
+		faults.wrong_signatures(true);
+		let signature = SyncCryptoStore::sign_with(&keystore, TEST_TK, &key, b"msg").unwrap();
+		assert!(!verifies(&key, &signature));
+		let all = SyncCryptoStore::sign_with_all(&keystore, TEST_TK, vec![key.clone()], b"msg").unwrap();
+		assert!(!verifies(&key, all[0].as_ref().unwrap()));
+
+		faults.reset();
+		faults.fail("signer_sign_with", Error::new(ErrorCode::ServerError(-32042)));
+		assert!(SyncCryptoStore::sign_with(&keystore, TEST_TK, &key, b"msg").is_err());
+		assert!(SyncCryptoStore::has_keys(&keystore, &[(key.1.clone(), TEST_TK)]));
+
+		faults.reset();
+		faults.set_latency(Duration::from_secs(1));
+		assert!(matches!(
+			SyncCryptoStore::sign_with(&keystore, TEST_TK, &key, b"msg"),
+			Err(CryptoStoreError::Unavailable)
+		));
+
+		faults.reset();
+		let signature = SyncCryptoStore::sign_with(&keystore, TEST_TK, &key, b"msg").unwrap();
+		assert!(verifies(&key, &signature));
+	}
+
+	#[test]
+	fn verification_rejects_wrong_signatures() {
+		let (signer, key) = signer("verifies");
+		let keystore = RemoteKeystore::open_with_config(signer.url(), RemoteSignerConfig {
+			verify_signatures: true,
+			..Default::default()
+		}).unwrap();
+		let vrf_key = SyncCryptoStore::sr25519_generate_new(&keystore, TEST_TK, None).unwrap();
+		let transcript = VRFTranscriptData {
+			label: b"BABE",
+			items: vec![
+				("slot number", VRFTranscriptValue::U64(1)),
+				("current epoch", VRFTranscriptValue::U64(0)),
+				("chain randomness", VRFTranscriptValue::Bytes(vec![0; 32])),
+			],
+		};
+		assert!(SyncCryptoStore::sign_with(&keystore, TEST_TK, &key, b"msg").is_ok());
+		assert!(SyncCryptoStore::sr25519_vrf_sign(&keystore, TEST_TK, &vrf_key, transcript.clone()).is_ok());
+
+		signer.faults().wrong_signatures(true);
+		assert!(matches!(
+			SyncCryptoStore::sign_with(&keystore, TEST_TK, &key, b"msg"),
+			Err(CryptoStoreError::ValidationError(_))
+		));
+		assert!(matches!(
+			SyncCryptoStore::sr25519_vrf_sign(&keystore, TEST_TK, &vrf_key, transcript),
+			Err(CryptoStoreError::ValidationError(_))
+		));
+		assert_eq!(keystore.status().invalid_signatures, 2);
+	}
+
+	#[test]
+	fn drops_and_refuses_connections() {
+		let (signer, key) = signer("drops");
+		let keystore = open(&signer);
+		assert!(SyncCryptoStore::sign_with(&keystore, TEST_TK, &key, b"msg").is_ok());
+
+		// Reconnects after a drop, but not while connections are refused.
+		signer.faults().drop_connections();
+		assert!(SyncCryptoStore::sign_with(&keystore, TEST_TK, &key, b"msg").is_ok());
+		assert!(keystore.status().connected);
+
+		signer.faults().refuse_connections(true);
+		signer.faults().drop_connections();
+		assert!(SyncCryptoStore::sign_with(&keystore, TEST_TK, &key, b"msg").is_err());
+		assert!(!keystore.status().connected);
+
+		signer.faults().refuse_connections(false);
+		assert!(SyncCryptoStore::sign_with(&keystore, TEST_TK, &key, b"msg").is_ok());
+	}
+}
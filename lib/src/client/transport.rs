@@ -0,0 +1,191 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+/// HTTP and WebSocket transports honouring the [`RemoteSignerConfig`]:
+/// the auth token is sent as `Authorization: Bearer` header and TLS uses
+/// the configured CA and client identity.
+///
+/// Each returns a flag that turns `false` once the transport broke, so the
+/// connection gets replaced (possibly by a failover signer).
+
+use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+use futures::{channel::mpsc, future, FutureExt, SinkExt, StreamExt};
+use hyper::{Body, Client, client::HttpConnector, header};
+use hyper_tls::HttpsConnector;
+use jsonrpc_core::{Error, ErrorCode, Failure, Id, MethodCall, Output, Version, serde_json};
+use tokio::{io::{AsyncRead, AsyncWrite}, net::TcpStream};
+use tokio_tungstenite::{WebSocketStream, tungstenite::{self, http, Message}};
+use url::Url;
+
+use jsonrpc_client_transports::{RpcChannel, RpcError, transports::duplex::duplex};
+
+use super::config::RemoteSignerConfig;
+
+/// Whether a transport is still usable.
+pub type Alive = Arc<AtomicBool>;
+
+/// Byte stream a WebSocket runs on, plain or TLS.
+pub trait Io: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Io for T {}
+
+/// A client WebSocket connection.
+pub type Socket = WebSocketStream<Box<dyn Io>>;
+
+fn other<E: std::error::Error + Send + 'static>(e: E) -> RpcError {
+	RpcError::Other(Box::new(e))
+}
+
+/// JSON-RPC response failing the request `body` with `error`.
+pub fn failure(body: &str, error: Error) -> Option<String> {
+	let id: Id = serde_json::from_str::<MethodCall>(body).ok()?.id;
+	serde_json::to_string(&Output::Failure(Failure {
+		jsonrpc: Some(Version::V2),
+		error,
+		id,
+	})).ok()
+}
+
+/// JSON-RPC error response for the request `body`, which never got an answer.
+fn failure_for(body: &str, message: String) -> Option<String> {
+	failure(body, Error { code: ErrorCode::ServerError(-32000), message, data: None })
+}
+
+/// JSON-RPC over HTTP(S), one POST per request.
+pub fn http(url: &Url, config: &RemoteSignerConfig) -> Result<(RpcChannel, Alive), RpcError> {
+	let uri: hyper::Uri = url.as_str().parse().map_err(other)?;
+	let mut connector = HttpConnector::new();
+	connector.enforce_http(false);
+	let tls = config.tls_connector().map_err(RpcError::Client)?;
+	let client = Client::builder()
+		.build::<_, Body>(HttpsConnector::from((connector, tls.into())));
+	let authorization = config.authorization();
+
+	let (sink, requests) = mpsc::unbounded::<String>();
+	let (responses, stream) = mpsc::unbounded::<String>();
+	let (rpc_client, channel) = duplex(Box::pin(sink), Box::pin(stream));
+	let alive = Arc::new(AtomicBool::new(true));
+
+	let worker_alive = alive.clone();
+	let worker = requests
+		.map(move |body: String| {
+			let mut request = hyper::Request::post(uri.clone())
+				.header(header::CONTENT_TYPE, "application/json");
+			if let Some(authorization) = &authorization {
+				request = request.header(header::AUTHORIZATION, authorization.as_str());
+			}
+			let request = request.body(Body::from(body.clone()));
+			let client = client.clone();
+			async move {
+				let response = match request {
+					Ok(request) => client.request(request).await,
+					Err(e) => return (body, Err((false, e.to_string()))),
+				};
+				let result = match response {
+					Ok(r) if !r.status().is_success() => Err((false, format!("Signer answered {}", r.status()))),
+					Ok(r) => hyper::body::to_bytes(r.into_body()).await
+						.map(|b| String::from_utf8_lossy(&b).into_owned())
+						.map_err(|e| (true, e.to_string())),
+					Err(e) => Err((true, e.to_string())),
+				};
+				(body, result)
+			}
+		})
+		.buffer_unordered(8)
+		.for_each(move |(body, result)| {
+			let response = match result {
+				Ok(response) if response.is_empty() => None,
+				Ok(response) => Some(response),
+				Err((broken, message)) => {
+					if broken {
+						worker_alive.store(false, Ordering::SeqCst);
+					}
+					log::warn!(target: "remote_keystore", "Request to signer failed: {:}", message);
+					failure_for(&body, message)
+				},
+			};
+			if let Some(response) = response {
+				let _ = responses.unbounded_send(response);
+			}
+			future::ready(())
+		});
+
+	tokio::spawn(future::select(rpc_client.boxed(), worker.boxed()));
+	Ok((channel, alive))
+}
+
+/// Open a WebSocket to `url`, offering the sub`protocol` if any.
+///
+/// Returns whether the server accepted the subprotocol.
+pub async fn websocket(
+	url: &Url,
+	config: &RemoteSignerConfig,
+	protocol: Option<&str>,
+) -> Result<(Socket, bool), RpcError> {
+	let host = url.host_str()
+		.ok_or_else(|| RpcError::Client(format!("{:} has no host", url)))?
+		.to_owned();
+	let port = url.port_or_known_default().unwrap_or(80);
+	let tcp = TcpStream::connect((host.as_str(), port)).await.map_err(other)?;
+	let stream: Box<dyn Io> = if url.scheme() == "wss" {
+		let tls = tokio_native_tls::TlsConnector::from(
+			config.tls_connector().map_err(RpcError::Client)?
+		);
+		Box::new(tls.connect(&host, tcp).await.map_err(other)?)
+	} else {
+		Box::new(tcp)
+	};
+
+	let mut request = http::Request::get(url.as_str());
+	if let Some(protocol) = protocol {
+		request = request.header("Sec-WebSocket-Protocol", protocol);
+	}
+	if let Some(authorization) = config.authorization() {
+		request = request.header(header::AUTHORIZATION, authorization);
+	}
+	let request = request.body(()).map_err(other)?;
+
+	let (socket, response) = tokio_tungstenite::client_async(request, stream)
+		.await
+		.map_err(other)?;
+	let accepted = response.headers()
+		.get("Sec-WebSocket-Protocol")
+		.is_some_and(|p| Some(p.as_bytes()) == protocol.map(str::as_bytes));
+	Ok((socket, accepted))
+}
+
+/// JSON-RPC over text frames of an open WebSocket.
+pub fn json_over_websocket(socket: Socket) -> (RpcChannel, Alive) {
+	let (sink, stream) = socket.split();
+	let sink = sink.with(|text: String| future::ready(Ok::<_, tungstenite::Error>(Message::Text(text))));
+	let stream = stream
+		.take_while(|m| future::ready(m.is_ok()))
+		.filter_map(|m| future::ready(match m {
+			Ok(Message::Text(text)) => Some(text),
+			_ => None,
+		}));
+	let (rpc_client, channel) = duplex(Box::pin(sink), Box::pin(stream));
+
+	let alive = Arc::new(AtomicBool::new(true));
+	let worker_alive = alive.clone();
+	tokio::spawn(async move {
+		let _ = rpc_client.await;
+		worker_alive.store(false, Ordering::SeqCst);
+		log::debug!(target: "remote_keystore", "JSON WebSocket connection closed");
+	});
+	(channel, alive)
+}
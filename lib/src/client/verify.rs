@@ -0,0 +1,104 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+/// Checks of what a signer hands out against what it was asked for, so a
+/// buggy or compromised signer can't make the node publish invalid
+/// signatures.
+
+use std::convert::TryFrom;
+use sp_core::{crypto::{CryptoTypePublicPair, Pair as _}, ecdsa, ed25519, sr25519};
+use sp_keystore::vrf::{make_transcript, VRFSignature, VRFTranscriptData};
+
+/// Whether `signature` is a valid signature of `msg` by `key`.
+///
+/// Signatures of schemes other than sr25519, ed25519 and ecdsa never are.
+pub fn signature(key: &CryptoTypePublicPair, msg: &[u8], signature: &[u8]) -> bool {
+	match key.0 {
+		sr25519::CRYPTO_ID => sr25519::Pair::verify_weak(signature, msg, &key.1),
+		ed25519::CRYPTO_ID => ed25519::Pair::verify_weak(signature, msg, &key.1),
+		ecdsa::CRYPTO_ID => verify_ecdsa(signature, msg, &key.1),
+		_ => false,
+	}
+}
+
+/// `ecdsa::Pair::verify_weak` compares against the uncompressed public key,
+/// signers hand out compressed ones.
+fn verify_ecdsa(signature: &[u8], msg: &[u8], public: &[u8]) -> bool {
+	match (ecdsa::Public::try_from(public), ecdsa::Signature::try_from(signature)) {
+		(Ok(public), Ok(signature)) => ecdsa::Pair::verify(&signature, msg, &public),
+		_ => false,
+	}
+}
+
+/// Whether `signature` holds a valid VRF output and proof of `transcript`
+/// by `public`.
+pub fn vrf(public: &sr25519::Public, transcript: VRFTranscriptData, signature: &VRFSignature) -> bool {
+	schnorrkel::PublicKey::from_bytes(&public.0)
+		.and_then(|public| {
+			public.vrf_verify(make_transcript(transcript), &signature.output, &signature.proof)
+		})
+		.is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::crypto::CryptoTypeId;
+	use sp_keystore::vrf::VRFTranscriptValue;
+
+	fn transcript(slot: u64) -> VRFTranscriptData {
+		VRFTranscriptData {
+			label: b"test",
+			items: vec![("slot", VRFTranscriptValue::U64(slot))],
+		}
+	}
+
+	#[test]
+	fn checks_signatures() {
+		let ed = ed25519::Pair::from_string("//Alice", None).unwrap();
+		let key = CryptoTypePublicPair(ed25519::CRYPTO_ID, ed.public().0.to_vec());
+		let good = ed.sign(b"msg").0;
+		assert!(signature(&key, b"msg", &good));
+		assert!(!signature(&key, b"other", &good));
+		assert!(!signature(&key, b"msg", &good[1..]));
+
+		let ecdsa = ecdsa::Pair::from_string("//Alice", None).unwrap();
+		let key = CryptoTypePublicPair(ecdsa::CRYPTO_ID, ecdsa.public().0.to_vec());
+		assert!(signature(&key, b"msg", &ecdsa.sign(b"msg").0));
+		assert!(!signature(&key, b"msg", &good));
+
+		let sr = sr25519::Pair::from_string("//Alice", None).unwrap();
+		let key = CryptoTypePublicPair(sr25519::CRYPTO_ID, sr.public().0.to_vec());
+		assert!(signature(&key, b"msg", &sr.sign(b"msg").0));
+
+		let unknown = CryptoTypePublicPair(CryptoTypeId(*b"nope"), key.1);
+		assert!(!signature(&unknown, b"msg", &sr.sign(b"msg").0));
+	}
+
+	#[test]
+	fn checks_vrf_proofs() {
+		let pair = sr25519::Pair::from_string("//Alice", None).unwrap();
+		let (inout, proof, _) = pair.as_ref().vrf_sign(make_transcript(transcript(1)));
+		let signed = VRFSignature { output: inout.to_output(), proof };
+		assert!(vrf(&pair.public(), transcript(1), &signed));
+		assert!(!vrf(&pair.public(), transcript(2), &signed));
+
+		let bob = sr25519::Pair::from_string("//Bob", None).unwrap();
+		assert!(!vrf(&bob.public(), transcript(1), &signed));
+	}
+}
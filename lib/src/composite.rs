@@ -0,0 +1,597 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+/// A keystore combining an ordered list of other keystores, e.g. to keep
+/// some key types in a `LocalKeystore` while others live on a remote signer.
+///
+/// Every store is registered with the [`KeyTypes`] and, optionally, the
+/// crypto schemes it is responsible for. Listings are merged over all
+/// responsible stores that answer, new keys go to the first responsible store and
+/// signing goes to the first store actually holding the key.
+
+use async_trait::async_trait;
+use sp_core::{
+	crypto::{CryptoTypeId, CryptoTypePublicPair, KeyTypeId, Pair, Public as _},
+	sr25519::Public as Sr25519Public,
+};
+use sp_keystore::{
+	CryptoStore, Error as CryptoStoreError, SyncCryptoStore, SyncCryptoStorePtr,
+	vrf::{VRFTranscriptData, VRFSignature},
+};
+use sp_application_crypto::{ed25519, sr25519, ecdsa};
+
+/// The key types a store in a [`CompositeKeystore`] is responsible for.
+#[derive(Clone, Debug)]
+pub enum KeyTypes {
+	/// Any key type
+	All,
+	/// Only the listed key types
+	Only(Vec<KeyTypeId>),
+	/// Any but the listed key types
+	Except(Vec<KeyTypeId>),
+}
+
+impl KeyTypes {
+	/// Whether `id` is covered.
+	pub fn matches(&self, id: KeyTypeId) -> bool {
+		match self {
+			KeyTypes::All => true,
+			KeyTypes::Only(ids) => ids.contains(&id),
+			KeyTypes::Except(ids) => !ids.contains(&id),
+		}
+	}
+}
+
+struct Backend {
+	key_types: KeyTypes,
+	schemes: Option<Vec<CryptoTypeId>>,
+	store: SyncCryptoStorePtr,
+}
+
+impl Backend {
+	fn matches(&self, id: KeyTypeId, scheme: Option<CryptoTypeId>) -> bool {
+		self.key_types.matches(id) && self.supports(scheme)
+	}
+
+	fn supports(&self, scheme: Option<CryptoTypeId>) -> bool {
+		match (&self.schemes, scheme) {
+			(Some(schemes), Some(scheme)) => schemes.contains(&scheme),
+			_ => true,
+		}
+	}
+}
+
+/// Keystore routing over an ordered list of keystores.
+#[derive(Default)]
+pub struct CompositeKeystore {
+	stores: Vec<Backend>,
+}
+
+fn merge<T: PartialEq>(into: &mut Vec<T>, from: Vec<T>) {
+	for item in from {
+		if !into.contains(&item) {
+			into.push(item);
+		}
+	}
+}
+
+/// The crypto scheme of `public`, derived from `suri`.
+///
+/// Falls back to the length of `public` if `suri` isn't a valid secret,
+/// which only tells ecdsa keys apart.
+fn scheme_of(suri: &str, public: &[u8]) -> Option<CryptoTypeId> {
+	fn derives<P: Pair>(suri: &str, public: &[u8]) -> bool {
+		P::from_string(suri, None).is_ok_and(|pair| pair.public().to_raw_vec() == public)
+	}
+
+	if derives::<sr25519::Pair>(suri, public) {
+		Some(sr25519::CRYPTO_ID)
+	} else if derives::<ed25519::Pair>(suri, public) {
+		Some(ed25519::CRYPTO_ID)
+	} else if derives::<ecdsa::Pair>(suri, public) || public.len() == 33 {
+		Some(ecdsa::CRYPTO_ID)
+	} else {
+		None
+	}
+}
+
+/// A failing store doesn't fail a listing over all stores, it's left out.
+fn skipped(id: KeyTypeId, e: CryptoStoreError) {
+	log::warn!(target: "remote_keystore", "Leaving a keystore out of listing {:?}: {:}", id, e);
+}
+
+impl CompositeKeystore {
+	/// Create an empty composite keystore.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Route `key_types` to `routed` only and all other key types to `rest`,
+	/// if any, so keys of a routed type are never looked up in `rest`.
+	pub fn partition(
+		key_types: Vec<KeyTypeId>,
+		routed: SyncCryptoStorePtr,
+		rest: Option<SyncCryptoStorePtr>,
+	) -> Self {
+		let composite = Self::new().with_store(KeyTypes::Only(key_types.clone()), routed);
+		match rest {
+			Some(rest) => composite.with_store(KeyTypes::Except(key_types), rest),
+			None => composite,
+		}
+	}
+
+	/// Append `store` for the given key types. Earlier stores take precedence.
+	pub fn with_store(self, key_types: KeyTypes, store: SyncCryptoStorePtr) -> Self {
+		self.with_route(key_types, None, store)
+	}
+
+	/// Append `store` for the given key types, limited to the crypto
+	/// `schemes` if given. Earlier stores take precedence.
+	pub fn with_route(
+		mut self,
+		key_types: KeyTypes,
+		schemes: Option<Vec<CryptoTypeId>>,
+		store: SyncCryptoStorePtr,
+	) -> Self {
+		self.stores.push(Backend { key_types, schemes, store });
+		self
+	}
+
+	fn stores_for(
+		&self,
+		id: KeyTypeId,
+		scheme: Option<CryptoTypeId>,
+	) -> impl Iterator<Item = &SyncCryptoStorePtr> {
+		self.stores.iter().filter(move |b| b.matches(id, scheme)).map(|b| &b.store)
+	}
+
+	fn first_for(
+		&self,
+		id: KeyTypeId,
+		scheme: Option<CryptoTypeId>,
+	) -> Result<&SyncCryptoStorePtr, CryptoStoreError> {
+		self.stores_for(id, scheme).next().ok_or(CryptoStoreError::KeyNotSupported(id))
+	}
+
+	/// The backends for `id`, regardless of their schemes.
+	fn listing_for(&self, id: KeyTypeId) -> impl Iterator<Item = &Backend> {
+		self.stores.iter().filter(move |b| b.key_types.matches(id))
+	}
+
+	/// The store holding `public`, without asking if there is only one candidate.
+	async fn holder_of(
+		&self,
+		id: KeyTypeId,
+		scheme: CryptoTypeId,
+		public: &[u8],
+	) -> Result<&SyncCryptoStorePtr, CryptoStoreError> {
+		let candidates = self.stores_for(id, Some(scheme)).collect::<Vec<_>>();
+		if candidates.len() == 1 {
+			return Ok(candidates[0])
+		}
+		let query = [(public.to_vec(), id)];
+		for store in candidates {
+			if CryptoStore::has_keys(&**store, &query).await {
+				return Ok(store)
+			}
+		}
+		Err(CryptoStoreError::PairNotFound(format!("{:?}", id)))
+	}
+
+	fn sync_holder_of(
+		&self,
+		id: KeyTypeId,
+		scheme: CryptoTypeId,
+		public: &[u8],
+	) -> Result<&SyncCryptoStorePtr, CryptoStoreError> {
+		let candidates = self.stores_for(id, Some(scheme)).collect::<Vec<_>>();
+		if candidates.len() == 1 {
+			return Ok(candidates[0])
+		}
+		let query = [(public.to_vec(), id)];
+		candidates.into_iter()
+			.find(|store| SyncCryptoStore::has_keys(&***store, &query))
+			.ok_or_else(|| CryptoStoreError::PairNotFound(format!("{:?}", id)))
+	}
+}
+
+#[async_trait]
+impl CryptoStore for CompositeKeystore {
+	async fn keys(
+		&self,
+		id: KeyTypeId
+	) -> std::result::Result<Vec<CryptoTypePublicPair>, CryptoStoreError> {
+		let mut keys = vec![];
+		for backend in self.listing_for(id) {
+			let mut listed = match CryptoStore::keys(&*backend.store, id).await {
+				Ok(listed) => listed,
+				Err(e) => {
+					skipped(id, e);
+					continue
+				},
+			};
+			listed.retain(|k| backend.supports(Some(k.0)));
+			merge(&mut keys, listed);
+		}
+		Ok(keys)
+	}
+
+	async fn supported_keys(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>
+	) -> std::result::Result<Vec<CryptoTypePublicPair>, CryptoStoreError> {
+		let mut supported = vec![];
+		for backend in self.listing_for(id) {
+			let keys = keys.iter().filter(|k| backend.supports(Some(k.0))).cloned().collect();
+			match CryptoStore::supported_keys(&*backend.store, id, keys).await {
+				Ok(keys) => merge(&mut supported, keys),
+				Err(e) => skipped(id, e),
+			}
+		}
+		Ok(supported)
+	}
+
+	async fn sign_with(
+		&self,
+		id: KeyTypeId,
+		key: &CryptoTypePublicPair,
+		msg: &[u8],
+	) -> std::result::Result<Vec<u8>, CryptoStoreError> {
+		let store = self.holder_of(id, key.0, &key.1).await?;
+		CryptoStore::sign_with(&**store, id, key, msg).await
+	}
+
+	async fn sr25519_public_keys(&self, key_type: KeyTypeId) -> Vec<sr25519::Public> {
+		let mut keys = vec![];
+		for store in self.stores_for(key_type, Some(sr25519::CRYPTO_ID)) {
+			merge(&mut keys, CryptoStore::sr25519_public_keys(&**store, key_type).await);
+		}
+		keys
+	}
+
+	async fn sr25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<sr25519::Public, CryptoStoreError> {
+		let store = self.first_for(id, Some(sr25519::CRYPTO_ID))?;
+		CryptoStore::sr25519_generate_new(&**store, id, seed).await
+	}
+
+	async fn ed25519_public_keys(&self, key_type: KeyTypeId) -> Vec<ed25519::Public> {
+		let mut keys = vec![];
+		for store in self.stores_for(key_type, Some(ed25519::CRYPTO_ID)) {
+			merge(&mut keys, CryptoStore::ed25519_public_keys(&**store, key_type).await);
+		}
+		keys
+	}
+
+	async fn ed25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<ed25519::Public, CryptoStoreError> {
+		let store = self.first_for(id, Some(ed25519::CRYPTO_ID))?;
+		CryptoStore::ed25519_generate_new(&**store, id, seed).await
+	}
+
+	async fn ecdsa_public_keys(&self, key_type: KeyTypeId) -> Vec<ecdsa::Public> {
+		let mut keys = vec![];
+		for store in self.stores_for(key_type, Some(ecdsa::CRYPTO_ID)) {
+			merge(&mut keys, CryptoStore::ecdsa_public_keys(&**store, key_type).await);
+		}
+		keys
+	}
+
+	async fn ecdsa_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<ecdsa::Public, CryptoStoreError> {
+		let store = self.first_for(id, Some(ecdsa::CRYPTO_ID))?;
+		CryptoStore::ecdsa_generate_new(&**store, id, seed).await
+	}
+
+	async fn insert_unknown(&self, key_type: KeyTypeId, suri: &str, public: &[u8])
+		-> std::result::Result<(), ()>
+	{
+		let store = self.first_for(key_type, scheme_of(suri, public)).map_err(|_| ())?;
+		CryptoStore::insert_unknown(&**store, key_type, suri, public).await
+	}
+
+	async fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		'keys: for (public, id) in public_keys {
+			let query = [(public.clone(), *id)];
+			for store in self.stores_for(*id, None) {
+				if CryptoStore::has_keys(&**store, &query).await {
+					continue 'keys
+				}
+			}
+			return false
+		}
+		true
+	}
+
+	async fn sr25519_vrf_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &Sr25519Public,
+		transcript_data: VRFTranscriptData,
+	) -> std::result::Result<VRFSignature, CryptoStoreError> {
+		let store = self.holder_of(key_type, sr25519::CRYPTO_ID, public.as_ref()).await?;
+		CryptoStore::sr25519_vrf_sign(&**store, key_type, public, transcript_data).await
+	}
+}
+
+impl SyncCryptoStore for CompositeKeystore {
+	fn keys(
+		&self,
+		id: KeyTypeId
+	) -> std::result::Result<Vec<CryptoTypePublicPair>, CryptoStoreError> {
+		let mut keys = vec![];
+		for backend in self.listing_for(id) {
+			let mut listed = match SyncCryptoStore::keys(&*backend.store, id) {
+				Ok(listed) => listed,
+				Err(e) => {
+					skipped(id, e);
+					continue
+				},
+			};
+			listed.retain(|k| backend.supports(Some(k.0)));
+			merge(&mut keys, listed);
+		}
+		Ok(keys)
+	}
+
+	fn supported_keys(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>
+	) -> std::result::Result<Vec<CryptoTypePublicPair>, CryptoStoreError> {
+		let mut supported = vec![];
+		for backend in self.listing_for(id) {
+			let keys = keys.iter().filter(|k| backend.supports(Some(k.0))).cloned().collect();
+			match SyncCryptoStore::supported_keys(&*backend.store, id, keys) {
+				Ok(keys) => merge(&mut supported, keys),
+				Err(e) => skipped(id, e),
+			}
+		}
+		Ok(supported)
+	}
+
+	fn sign_with(
+		&self,
+		id: KeyTypeId,
+		key: &CryptoTypePublicPair,
+		msg: &[u8],
+	) -> std::result::Result<Vec<u8>, CryptoStoreError> {
+		let store = self.sync_holder_of(id, key.0, &key.1)?;
+		SyncCryptoStore::sign_with(&**store, id, key, msg)
+	}
+
+	fn sr25519_public_keys(&self, key_type: KeyTypeId) -> Vec<sr25519::Public> {
+		let mut keys = vec![];
+		for store in self.stores_for(key_type, Some(sr25519::CRYPTO_ID)) {
+			merge(&mut keys, SyncCryptoStore::sr25519_public_keys(&**store, key_type));
+		}
+		keys
+	}
+
+	fn sr25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<sr25519::Public, CryptoStoreError> {
+		let store = self.first_for(id, Some(sr25519::CRYPTO_ID))?;
+		SyncCryptoStore::sr25519_generate_new(&**store, id, seed)
+	}
+
+	fn ed25519_public_keys(&self, key_type: KeyTypeId) -> Vec<ed25519::Public> {
+		let mut keys = vec![];
+		for store in self.stores_for(key_type, Some(ed25519::CRYPTO_ID)) {
+			merge(&mut keys, SyncCryptoStore::ed25519_public_keys(&**store, key_type));
+		}
+		keys
+	}
+
+	fn ed25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<ed25519::Public, CryptoStoreError> {
+		let store = self.first_for(id, Some(ed25519::CRYPTO_ID))?;
+		SyncCryptoStore::ed25519_generate_new(&**store, id, seed)
+	}
+
+	fn ecdsa_public_keys(&self, key_type: KeyTypeId) -> Vec<ecdsa::Public> {
+		let mut keys = vec![];
+		for store in self.stores_for(key_type, Some(ecdsa::CRYPTO_ID)) {
+			merge(&mut keys, SyncCryptoStore::ecdsa_public_keys(&**store, key_type));
+		}
+		keys
+	}
+
+	fn ecdsa_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<ecdsa::Public, CryptoStoreError> {
+		let store = self.first_for(id, Some(ecdsa::CRYPTO_ID))?;
+		SyncCryptoStore::ecdsa_generate_new(&**store, id, seed)
+	}
+
+	fn insert_unknown(&self, key_type: KeyTypeId, suri: &str, public: &[u8])
+		-> std::result::Result<(), ()>
+	{
+		let store = self.first_for(key_type, scheme_of(suri, public)).map_err(|_| ())?;
+		SyncCryptoStore::insert_unknown(&**store, key_type, suri, public)
+	}
+
+	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		public_keys.iter().all(|(public, id)| {
+			let query = [(public.clone(), *id)];
+			self.stores_for(*id, None).any(|store| SyncCryptoStore::has_keys(&**store, &query))
+		})
+	}
+
+	fn sr25519_vrf_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &Sr25519Public,
+		transcript_data: VRFTranscriptData,
+	) -> std::result::Result<VRFSignature, CryptoStoreError> {
+		let store = self.sync_holder_of(key_type, sr25519::CRYPTO_ID, public.as_ref())?;
+		SyncCryptoStore::sr25519_vrf_sign(&**store, key_type, public, transcript_data)
+	}
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+	use super::*;
+	use std::sync::Arc;
+	use sc_keystore::LocalKeystore;
+
+	const AURA: KeyTypeId = KeyTypeId(*b"aura");
+	const IMON: KeyTypeId = KeyTypeId(*b"imon");
+
+	#[test]
+	fn routes_by_key_type() {
+		let remote = Arc::new(LocalKeystore::in_memory());
+		let local = Arc::new(LocalKeystore::in_memory());
+		let composite = CompositeKeystore::new()
+			.with_store(KeyTypes::Only(vec![AURA]), remote.clone())
+			.with_store(KeyTypes::All, local.clone());
+
+		let aura = SyncCryptoStore::sr25519_generate_new(&composite, AURA, None).unwrap();
+		let imon = SyncCryptoStore::sr25519_generate_new(&composite, IMON, None).unwrap();
+
+		assert_eq!(SyncCryptoStore::sr25519_public_keys(&*remote, AURA), vec![aura]);
+		assert!(SyncCryptoStore::sr25519_public_keys(&*remote, IMON).is_empty());
+		assert_eq!(SyncCryptoStore::sr25519_public_keys(&*local, IMON), vec![imon]);
+		assert!(SyncCryptoStore::has_keys(&composite, &[
+			(aura.0.to_vec(), AURA),
+			(imon.0.to_vec(), IMON),
+		]));
+	}
+
+	#[test]
+	fn partition_keeps_routed_types_out_of_the_rest() {
+		let remote = Arc::new(LocalKeystore::in_memory());
+		let local = Arc::new(LocalKeystore::in_memory());
+		let stale = SyncCryptoStore::sr25519_generate_new(&*local, AURA, None).unwrap();
+		let stale_key = CryptoTypePublicPair(sr25519::CRYPTO_ID, stale.0.to_vec());
+		let composite = CompositeKeystore::partition(vec![AURA], remote.clone(), Some(local.clone()));
+
+		assert!(SyncCryptoStore::sr25519_public_keys(&composite, AURA).is_empty());
+		assert!(SyncCryptoStore::keys(&composite, AURA).unwrap().is_empty());
+		assert!(!SyncCryptoStore::has_keys(&composite, &[(stale.0.to_vec(), AURA)]));
+		assert!(SyncCryptoStore::sign_with(&composite, AURA, &stale_key, b"msg").is_err());
+
+		let aura = SyncCryptoStore::sr25519_generate_new(&composite, AURA, None).unwrap();
+		let imon = SyncCryptoStore::sr25519_generate_new(&composite, IMON, None).unwrap();
+		assert_eq!(SyncCryptoStore::sr25519_public_keys(&*remote, AURA), vec![aura]);
+		assert_eq!(SyncCryptoStore::sr25519_public_keys(&*local, AURA), vec![stale]);
+		assert_eq!(SyncCryptoStore::sr25519_public_keys(&*local, IMON), vec![imon]);
+		assert!(SyncCryptoStore::sr25519_public_keys(&*remote, IMON).is_empty());
+	}
+
+	#[cfg(feature = "client")]
+	#[test]
+	fn listing_skips_failing_stores() {
+		let unreachable = Arc::new(
+			crate::client::RemoteKeystore::open("ws://127.0.0.1:1".into(), Some(1)).unwrap()
+		);
+		let local = Arc::new(LocalKeystore::in_memory());
+		let composite = CompositeKeystore::new()
+			.with_store(KeyTypes::All, unreachable)
+			.with_store(KeyTypes::All, local.clone());
+
+		let public = SyncCryptoStore::ed25519_generate_new(&*local, AURA, None).unwrap();
+		let key = CryptoTypePublicPair(ed25519::CRYPTO_ID, public.0.to_vec());
+		assert_eq!(
+			SyncCryptoStore::keys(&composite, AURA).unwrap(),
+			SyncCryptoStore::keys(&*local, AURA).unwrap(),
+		);
+		assert_eq!(
+			SyncCryptoStore::supported_keys(&composite, AURA, vec![key.clone()]).unwrap(),
+			vec![key],
+		);
+	}
+
+	#[test]
+	fn signs_with_the_store_holding_the_key() {
+		let first = Arc::new(LocalKeystore::in_memory());
+		let second = Arc::new(LocalKeystore::in_memory());
+		let composite = CompositeKeystore::new()
+			.with_store(KeyTypes::All, first.clone())
+			.with_store(KeyTypes::All, second.clone());
+
+		let public = SyncCryptoStore::ed25519_generate_new(&*second, AURA, None).unwrap();
+		let key = CryptoTypePublicPair(ed25519::CRYPTO_ID, public.0.to_vec());
+
+		assert!(SyncCryptoStore::keys(&composite, AURA).unwrap().contains(&key));
+		assert!(SyncCryptoStore::sign_with(&composite, AURA, &key, b"msg").is_ok());
+
+		let unknown = CryptoTypePublicPair(ed25519::CRYPTO_ID, vec![0u8; 32]);
+		assert!(SyncCryptoStore::sign_with(&composite, AURA, &unknown, b"msg").is_err());
+	}
+
+	#[test]
+	fn routes_by_crypto_scheme() {
+		let grandpa = Arc::new(LocalKeystore::in_memory());
+		let session = Arc::new(LocalKeystore::in_memory());
+		let composite = CompositeKeystore::new()
+			.with_route(KeyTypes::All, Some(vec![ed25519::CRYPTO_ID]), grandpa.clone())
+			.with_store(KeyTypes::All, session.clone());
+
+		let ed = SyncCryptoStore::ed25519_generate_new(&composite, AURA, None).unwrap();
+		let sr = SyncCryptoStore::sr25519_generate_new(&composite, AURA, None).unwrap();
+
+		assert_eq!(SyncCryptoStore::ed25519_public_keys(&*grandpa, AURA), vec![ed]);
+		assert_eq!(SyncCryptoStore::sr25519_public_keys(&*session, AURA), vec![sr]);
+		assert_eq!(SyncCryptoStore::sr25519_public_keys(&composite, AURA), vec![sr]);
+
+		let key = CryptoTypePublicPair(ed25519::CRYPTO_ID, ed.0.to_vec());
+		assert!(SyncCryptoStore::sign_with(&composite, AURA, &key, b"msg").is_ok());
+	}
+
+	#[test]
+	fn inserts_by_crypto_scheme() {
+		// Keys of unknown type are only kept by keystores with a path.
+		let path = std::env::temp_dir().join(format!("tssrs-composite-{}", std::process::id()));
+		let grandpa = Arc::new(LocalKeystore::open(path.join("grandpa"), None).unwrap());
+		let session = Arc::new(LocalKeystore::open(path.join("session"), None).unwrap());
+		let composite = CompositeKeystore::new()
+			.with_route(KeyTypes::All, Some(vec![ed25519::CRYPTO_ID]), grandpa.clone())
+			.with_store(KeyTypes::All, session.clone());
+
+		let ed = ed25519::Pair::from_string("//Alice", None).unwrap().public();
+		let sr = sr25519::Pair::from_string("//Alice", None).unwrap().public();
+		SyncCryptoStore::insert_unknown(&composite, AURA, "//Alice", &ed.0).unwrap();
+		SyncCryptoStore::insert_unknown(&composite, AURA, "//Alice", &sr.0).unwrap();
+
+		let (ed, sr) = ([(ed.0.to_vec(), AURA)], [(sr.0.to_vec(), AURA)]);
+		assert!(SyncCryptoStore::has_keys(&*grandpa, &ed));
+		assert!(!SyncCryptoStore::has_keys(&*grandpa, &sr));
+		assert!(SyncCryptoStore::has_keys(&*session, &sr));
+		assert!(!SyncCryptoStore::has_keys(&*session, &ed));
+
+		let _ = std::fs::remove_dir_all(&path);
+	}
+}
@@ -35,11 +35,11 @@ use jsonrpc_core::{Result as RpcResult, BoxFuture};
 use serde;
 
 use sp_core::{
-	crypto::{KeyTypeId, CryptoTypePublicPair},
+	crypto::{CryptoTypeId, KeyTypeId},
 	ed25519, sr25519, ecdsa
 };
 use sp_keystore::{
-	vrf::{VRFSignature, VRFTranscriptData, VRFTranscriptValue},
+	vrf::{VRFTranscriptData, VRFTranscriptValue},
 };
 
 #[cfg(feature = "server")]
@@ -48,26 +48,72 @@ pub mod server;
 #[cfg(feature = "client")]
 pub mod client;
 
+#[cfg(any(feature = "client", feature = "server"))]
+pub mod composite;
+
+pub mod scale;
+pub mod wire;
+
+pub use wire::{HexBytes, PublicPair, VrfSignature};
+
 /// Wrap VRFTranscriptData into a serializable format
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct TransferableVRFTranscriptData {
 	/// The transcript's label
-	pub label: Vec<u8>,
+	pub label: HexBytes,
 	/// Additional data to be registered into the transcript
+	#[serde(with = "wire::transcript_items")]
 	pub items: Vec<VRFTranscriptValue>,
 }
 
 impl From<VRFTranscriptData> for TransferableVRFTranscriptData {
 	fn from(d: VRFTranscriptData) -> TransferableVRFTranscriptData {
 		TransferableVRFTranscriptData {
-			label: d.label.to_vec(),
+			label: d.label.to_vec().into(),
 			items:  d.items.into_iter().map(|(_k, v)| v).collect()
 		}
 	}
 }
 
+/// What a signer offers, as answered by `signer_capabilities`.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize, codec::Encode, codec::Decode)]
+#[serde(rename_all = "camelCase")]
+pub struct Capabilities {
+	/// Name and version of the signer implementation
+	pub implementation: String,
+	/// Names of the methods the signer answers
+	pub methods: Vec<String>,
+	/// Whether the signer can remove and rotate keys
+	pub key_management: bool,
+}
+
+/// Names of all methods of the [`RemoteSignerApi`].
+pub const METHODS: &[&str] = &[
+	"signer_sr25519_public_keys",
+	"signer_sr25519_generate_new",
+	"signer_ed25519_public_keys",
+	"signer_ed25519_generate_new",
+	"signer_ecdsa_public_keys",
+	"signer_ecdsa_generate_new",
+	"signer_insert_unknown",
+	"signer_supported_keys",
+	"signer_keys",
+	"signer_has_keys",
+	"signer_sign_with",
+	"signer_sign_with_any",
+	"signer_sign_with_all",
+	"signer_sr25519_vrf_sign",
+	"signer_remove_key",
+	"signer_rotate_keys",
+	"signer_generate_session_keys",
+	"signer_capabilities",
+];
+
 /// Simple Substrate Remote Signer JSON RPC interface
 /// matches `sp-core::CryptoStore`
+///
+/// Byte fields use the v2 wire schema of [`wire`]: `0x`-prefixed hex strings,
+/// while the v1 arrays of numbers are still accepted as input.
 #[cfg_attr(feature = "client", rpc)]
 #[cfg_attr(all(not(feature = "client"), feature = "server"), rpc(server))]
 pub trait RemoteSignerApi {
@@ -127,7 +173,7 @@ pub trait RemoteSignerApi {
 	///
 	/// `Err` if there's some sort of weird filesystem error, but should generally be `Ok`.
 	#[rpc(name="signer_insert_unknown")]
-	fn insert_unknown(&self, key_type: KeyTypeId, suri: String, public: Vec<u8>) -> BoxFuture<RpcResult<()>>;
+	fn insert_unknown(&self, key_type: KeyTypeId, suri: String, public: HexBytes) -> BoxFuture<RpcResult<()>>;
 
 	/// Find intersection between provided keys and supported keys
 	///
@@ -137,20 +183,20 @@ pub trait RemoteSignerApi {
 	fn supported_keys(
 		&self,
 		id: KeyTypeId,
-		keys: Vec<CryptoTypePublicPair>
-	) -> BoxFuture<RpcResult<Vec<CryptoTypePublicPair>>>;
+		keys: Vec<PublicPair>
+	) -> BoxFuture<RpcResult<Vec<PublicPair>>>;
 
 	/// List all supported keys
 	///
 	/// Returns a set of public keys the signer supports.
 	#[rpc(name="signer_keys")]
-	fn keys(&self, id: KeyTypeId) -> BoxFuture<RpcResult<Vec<CryptoTypePublicPair>>>;
+	fn keys(&self, id: KeyTypeId) -> BoxFuture<RpcResult<Vec<PublicPair>>>;
 
 	/// Checks if the private keys for the given public key and key type combinations exist.
 	///
 	/// Returns `true` iff all private keys could be found.
 	#[rpc(name="signer_has_keys")]
-	fn has_keys(&self, public_keys: Vec<(Vec<u8>, KeyTypeId)>) -> BoxFuture<RpcResult<bool>>;
+	fn has_keys(&self, public_keys: Vec<(HexBytes, KeyTypeId)>) -> BoxFuture<RpcResult<bool>>;
 
 	/// Sign with key
 	///
@@ -163,9 +209,9 @@ pub trait RemoteSignerApi {
 	fn sign_with(
 		&self,
 		id: KeyTypeId,
-		key: CryptoTypePublicPair,
-		msg: Vec<u8>,
-	) -> BoxFuture<RpcResult<Vec<u8>>>;
+		key: PublicPair,
+		msg: HexBytes,
+	) -> BoxFuture<RpcResult<HexBytes>>;
 
 	/// Sign with any key
 	///
@@ -177,9 +223,9 @@ pub trait RemoteSignerApi {
 	fn sign_with_any(
 		&self,
 		id: KeyTypeId,
-		keys: Vec<CryptoTypePublicPair>,
-		msg: Vec<u8>,
-	) -> BoxFuture<RpcResult<(CryptoTypePublicPair, Vec<u8>)>>;
+		keys: Vec<PublicPair>,
+		msg: HexBytes,
+	) -> BoxFuture<RpcResult<(PublicPair, HexBytes)>>;
 
 	/// Sign with all keys
 	///
@@ -192,9 +238,9 @@ pub trait RemoteSignerApi {
 	fn sign_with_all(
 		&self,
 		id: KeyTypeId,
-		keys: Vec<CryptoTypePublicPair>,
-		msg: Vec<u8>,
-	) -> BoxFuture<RpcResult<Vec<Result<Vec<u8>, String>>>>;
+		keys: Vec<PublicPair>,
+		msg: HexBytes,
+	) -> BoxFuture<RpcResult<Vec<Result<HexBytes, String>>>>;
 
 	/// Generate VRF signature for given transcript data.
 	///
@@ -204,7 +250,7 @@ pub trait RemoteSignerApi {
 	///
 	/// Returns a result containing the signature data.
 	/// Namely, VRFOutput and VRFProof which are returned
-	/// inside the `VRFSignature` container struct.
+	/// hex encoded inside the `VrfSignature` container struct.
 	///
 	/// This function will return an error in the cases where
 	/// the public key and key type provided do not match a private
@@ -216,5 +262,44 @@ pub trait RemoteSignerApi {
 		key_type: KeyTypeId,
 		public: sr25519::Public,
 		transcript_data: TransferableVRFTranscriptData,
-	) -> BoxFuture<RpcResult<VRFSignature>>;
+	) -> BoxFuture<RpcResult<VrfSignature>>;
+
+	/// Remove a key
+	///
+	/// Takes the key out of service. Signers keep the retired secret
+	/// in an archive rather than destroying it.
+	///
+	/// `Err` if the key isn't known or the signer can't retire keys.
+	#[rpc(name="signer_remove_key")]
+	fn remove_key(&self, key_type: KeyTypeId, public: HexBytes) -> BoxFuture<RpcResult<()>>;
+
+	/// Rotate session keys
+	///
+	/// Generates a fresh key for each of the given session key types and
+	/// archives all previous keys of these types.
+	///
+	/// Returns the SCALE encoded session keys in the order of `key_types`,
+	/// ready to be passed to `session.setKeys`.
+	#[rpc(name="signer_rotate_keys")]
+	fn rotate_keys(&self, key_types: Vec<KeyTypeId>) -> BoxFuture<RpcResult<HexBytes>>;
+
+	/// Generate session keys
+	///
+	/// Mirrors `author_rotateKeys`: generates a new key for each of the
+	/// runtime's ordered `(KeyTypeId, CryptoTypeId)` session keys in one go.
+	/// Either all keys are generated or none is kept; signers that can't
+	/// retire keys refuse to generate more than one.
+	///
+	/// Returns the SCALE encoded session keys in the given order.
+	#[rpc(name="signer_generate_session_keys")]
+	fn generate_session_keys(
+		&self,
+		keys: Vec<(KeyTypeId, CryptoTypeId)>,
+	) -> BoxFuture<RpcResult<HexBytes>>;
+
+	/// Describe the signer
+	///
+	/// Answered without touching any key, e.g. to check the signer is up.
+	#[rpc(name="signer_capabilities")]
+	fn capabilities(&self) -> BoxFuture<RpcResult<Capabilities>>;
 }
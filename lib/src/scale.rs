@@ -0,0 +1,207 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+/// Binary SCALE framing of the `RemoteSignerApi`.
+///
+/// Instead of JSON-RPC text messages, each WebSocket binary message carries
+/// a SCALE encoded `(id, Request)` from the client and a SCALE encoded
+/// `(id, Result<Vec<u8>, (i64, String)>)` back from the server, where the
+/// `Ok` payload is the SCALE encoded return value of the method called.
+///
+/// Clients ask for it by offering the [`SUBPROTOCOL`] during the WebSocket
+/// handshake; a server that does not echo it back speaks plain JSON-RPC.
+
+use codec::{Decode, Encode};
+use sp_core::{
+	crypto::{CryptoTypeId, CryptoTypePublicPair, KeyTypeId},
+	sr25519,
+};
+use sp_keystore::vrf::{VRFSignature, VRFTranscriptValue};
+
+use crate::TransferableVRFTranscriptData;
+
+/// WebSocket subprotocol negotiated for binary SCALE framing.
+///
+/// Bumped whenever [`Request`] changes, so that peers disagreeing on its
+/// variants fall back to JSON-RPC instead of misreading each other's frames.
+pub const SUBPROTOCOL: &str = "tssrs-scale.v1";
+
+/// Id the server answers frames with that are too short to carry one.
+pub const NO_ID: u64 = u64::MAX;
+
+/// Error code and message, mirroring the JSON-RPC error object.
+pub type ErrorPayload = (i64, String);
+
+/// Result of a single call: the SCALE encoded return value or an error.
+pub type ResponsePayload = Result<Vec<u8>, ErrorPayload>;
+
+/// A single VRF transcript item.
+#[derive(Encode, Decode)]
+pub enum TranscriptItem {
+	/// Raw bytes
+	Bytes(Vec<u8>),
+	/// An `u64`
+	U64(u64),
+}
+
+/// SCALE counterpart of [`TransferableVRFTranscriptData`].
+#[derive(Encode, Decode)]
+pub struct Transcript {
+	/// The transcript's label
+	pub label: Vec<u8>,
+	/// Additional data to be registered into the transcript
+	pub items: Vec<TranscriptItem>,
+}
+
+impl From<TransferableVRFTranscriptData> for Transcript {
+	fn from(d: TransferableVRFTranscriptData) -> Transcript {
+		Transcript {
+			label: d.label.into(),
+			items: d.items.into_iter().map(|i| match i {
+				VRFTranscriptValue::Bytes(b) => TranscriptItem::Bytes(b),
+				VRFTranscriptValue::U64(u) => TranscriptItem::U64(u),
+			}).collect(),
+		}
+	}
+}
+
+impl From<Transcript> for TransferableVRFTranscriptData {
+	fn from(t: Transcript) -> TransferableVRFTranscriptData {
+		TransferableVRFTranscriptData {
+			label: t.label.into(),
+			items: t.items.into_iter().map(|i| match i {
+				TranscriptItem::Bytes(b) => VRFTranscriptValue::Bytes(b),
+				TranscriptItem::U64(u) => VRFTranscriptValue::U64(u),
+			}).collect(),
+		}
+	}
+}
+
+/// The methods of `RemoteSignerApi`, one variant each.
+#[derive(Encode, Decode)]
+pub enum Request {
+	/// `signer_sr25519_public_keys`
+	Sr25519PublicKeys(KeyTypeId),
+	/// `signer_sr25519_generate_new`
+	Sr25519GenerateNew(KeyTypeId, Option<String>),
+	/// `signer_ed25519_public_keys`
+	Ed25519PublicKeys(KeyTypeId),
+	/// `signer_ed25519_generate_new`
+	Ed25519GenerateNew(KeyTypeId, Option<String>),
+	/// `signer_ecdsa_public_keys`
+	EcdsaPublicKeys(KeyTypeId),
+	/// `signer_ecdsa_generate_new`
+	EcdsaGenerateNew(KeyTypeId, Option<String>),
+	/// `signer_insert_unknown`
+	InsertUnknown(KeyTypeId, String, Vec<u8>),
+	/// `signer_supported_keys`
+	SupportedKeys(KeyTypeId, Vec<CryptoTypePublicPair>),
+	/// `signer_keys`
+	Keys(KeyTypeId),
+	/// `signer_has_keys`
+	HasKeys(Vec<(Vec<u8>, KeyTypeId)>),
+	/// `signer_sign_with`
+	SignWith(KeyTypeId, CryptoTypePublicPair, Vec<u8>),
+	/// `signer_sign_with_any`
+	SignWithAny(KeyTypeId, Vec<CryptoTypePublicPair>, Vec<u8>),
+	/// `signer_sign_with_all`
+	SignWithAll(KeyTypeId, Vec<CryptoTypePublicPair>, Vec<u8>),
+	/// `signer_sr25519_vrf_sign`
+	Sr25519VrfSign(KeyTypeId, sr25519::Public, Transcript),
+	/// `signer_remove_key`
+	RemoveKey(KeyTypeId, Vec<u8>),
+	/// `signer_rotate_keys`
+	RotateKeys(Vec<KeyTypeId>),
+	/// `signer_generate_session_keys`
+	GenerateSessionKeys(Vec<(KeyTypeId, CryptoTypeId)>),
+	/// `signer_capabilities`
+	Capabilities,
+}
+
+/// Encode a VRF signature as `(output, proof)` bytes.
+pub fn encode_vrf_signature(sig: &VRFSignature) -> Vec<u8> {
+	(sig.output.to_bytes(), sig.proof.to_bytes()).encode()
+}
+
+/// Decode a VRF signature encoded by [`encode_vrf_signature`].
+pub fn decode_vrf_signature(mut bytes: &[u8]) -> Result<VRFSignature, String> {
+	let (output, proof) = <([u8; 32], [u8; 64])>::decode(&mut bytes)
+		.map_err(|e| format!("Invalid VRF signature encoding: {:}", e))?;
+	Ok(VRFSignature {
+		output: schnorrkel::vrf::VRFOutput::from_bytes(&output)
+			.map_err(|e| format!("Invalid VRF output: {:}", e))?,
+		proof: schnorrkel::vrf::VRFProof::from_bytes(&proof)
+			.map_err(|e| format!("Invalid VRF proof: {:}", e))?,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::{crypto::Pair, ed25519};
+	use sp_keystore::vrf::{make_transcript, VRFTranscriptData};
+
+	const TEST_TK: KeyTypeId = KeyTypeId(*b"test");
+
+	fn round_trip(request: Request) {
+		let frame = (7u64, request).encode();
+		let (id, decoded) = <(u64, Request)>::decode(&mut &frame[..]).unwrap();
+		assert_eq!(id, 7);
+		assert_eq!((id, decoded).encode(), frame);
+	}
+
+	#[test]
+	fn requests_round_trip() {
+		let key = CryptoTypePublicPair(ed25519::CRYPTO_ID, vec![1; 32]);
+		round_trip(Request::Sr25519GenerateNew(TEST_TK, Some("//Alice".into())));
+		round_trip(Request::HasKeys(vec![(vec![1; 32], TEST_TK)]));
+		round_trip(Request::SignWithAll(TEST_TK, vec![key.clone(), key], vec![1, 2, 3]));
+		round_trip(Request::Sr25519VrfSign(TEST_TK, sr25519::Public::from_raw([2; 32]), Transcript {
+			label: b"test".to_vec(),
+			items: vec![TranscriptItem::Bytes(vec![1, 2]), TranscriptItem::U64(3)],
+		}));
+		round_trip(Request::Capabilities);
+	}
+
+	#[test]
+	fn responses_round_trip() {
+		let ok: ResponsePayload = Ok(vec![1u8, 2, 3].encode());
+		let frame = (3u64, ok.clone()).encode();
+		assert_eq!(<(u64, ResponsePayload)>::decode(&mut &frame[..]).unwrap(), (3, ok));
+
+		let err: ResponsePayload = Err((-32000, "nope".into()));
+		let frame = (4u64, err.clone()).encode();
+		assert_eq!(<(u64, ResponsePayload)>::decode(&mut &frame[..]).unwrap(), (4, err));
+	}
+
+	#[test]
+	fn vrf_signatures_round_trip() {
+		let pair = sr25519::Pair::from_string("//Alice", None).unwrap();
+		let transcript = VRFTranscriptData {
+			label: b"test",
+			items: vec![("slot", VRFTranscriptValue::U64(3))],
+		};
+		let (inout, proof, _) = pair.as_ref().vrf_sign(make_transcript(transcript));
+		let signature = VRFSignature { output: inout.to_output(), proof };
+
+		let decoded = decode_vrf_signature(&encode_vrf_signature(&signature)).unwrap();
+		assert_eq!(decoded.output.to_bytes(), signature.output.to_bytes());
+		assert_eq!(decoded.proof.to_bytes()[..], signature.proof.to_bytes()[..]);
+		assert!(decode_vrf_signature(&[0; 10]).is_err());
+	}
+}
@@ -21,10 +21,12 @@
 
 use std::{
 	pin::Pin,
+	sync::Arc,
 	task::{Context, Poll}
 };
 use sp_core::{
 	crypto::{
+		CryptoTypeId,
 		CryptoTypePublicPair,
 		KeyTypeId,
 	},
@@ -48,10 +50,21 @@ use futures::{
 	stream:: Stream,
 };
 use std::convert::TryInto;
+use codec::{Decode, Encode};
 
 use sp_consensus_babe::BABE_ENGINE_ID;
 
-use crate::TransferableVRFTranscriptData;
+use crate::{Capabilities, TransferableVRFTranscriptData, HexBytes, PublicPair};
+
+mod encrypted;
+mod manage;
+#[cfg(feature = "hsm")]
+mod pkcs11;
+
+pub use encrypted::EncryptedKeystore;
+pub use manage::{FsKeyArchive, KeyArchive, session_key_scheme};
+#[cfg(feature = "hsm")]
+pub use self::pkcs11::Pkcs11Keystore;
 
 impl TryInto<VRFTranscriptData> for TransferableVRFTranscriptData {
 	type Error = String;
@@ -59,7 +72,7 @@ impl TryInto<VRFTranscriptData> for TransferableVRFTranscriptData {
 	fn try_into(self: TransferableVRFTranscriptData) -> Result<VRFTranscriptData, Self::Error> {
 
 		let (label, field_names) = {
-			if self.label.as_slice() == &BABE_ENGINE_ID {
+			if self.label[..] == BABE_ENGINE_ID[..] {
 				(&BABE_ENGINE_ID, vec!["slot number", "current epoch", "chain randomness"])
 			} else {
 				return Err(format!("VRF Label '{:?}' not supported", self.label))
@@ -87,19 +100,29 @@ enum State<Store: CryptoStore> {
 pub struct KeystoreReceiver<Store: CryptoStore> {
 	receiver: UnboundedReceiver<KeystoreRequest>,
 	state: State<Store>,
+	archive: Option<Arc<dyn KeyArchive>>,
 }
 
 impl<Store: CryptoStore> Unpin for KeystoreReceiver<Store> { }
 
 impl<Store: CryptoStore + 'static> KeystoreReceiver<Store> {
-	fn new(store: Store, receiver: UnboundedReceiver<KeystoreRequest>) -> Self {
+	fn new(
+		store: Store,
+		archive: Option<Arc<dyn KeyArchive>>,
+		receiver: UnboundedReceiver<KeystoreRequest>,
+	) -> Self {
 		KeystoreReceiver {
 			receiver,
 			state: State::Idle(store),
+			archive,
 		}
 	}
 
-	fn process_request(store: Store, request: KeystoreRequest) -> Pin<Box<dyn Future<Output = Store> + Send>> {
+	fn process_request(
+		store: Store,
+		archive: Option<Arc<dyn KeyArchive>>,
+		request: KeystoreRequest,
+	) -> Pin<Box<dyn Future<Output = Store> + Send>> {
 		let sender = request.sender;
 		match request.method {
 			RequestMethod::Sr25519PublicKeys(id) => {
@@ -204,6 +227,33 @@ impl<Store: CryptoStore + 'static> KeystoreReceiver<Store> {
 					return store;
 				})
 			},
+			RequestMethod::RemoveKey(key_type, public) => {
+				Box::pin(async move {
+					let result = match archive {
+						Some(archive) => archive.archive(key_type, &public).await,
+						None => Err(CryptoStoreError::Unavailable),
+					};
+					let _ = sender.send(KeystoreResponse::RemoveKey(result));
+					return store;
+				})
+			},
+			RequestMethod::RotateKeys(key_types) => {
+				Box::pin(async move {
+					let result = match archive {
+						Some(archive) => manage::rotate_keys(&store, &*archive, key_types).await,
+						None => Err(CryptoStoreError::Unavailable),
+					};
+					let _ = sender.send(KeystoreResponse::RotateKeys(result));
+					return store;
+				})
+			},
+			RequestMethod::GenerateSessionKeys(keys) => {
+				Box::pin(async move {
+					let result = manage::generate_session_keys(&store, archive.as_deref(), keys).await;
+					let _ = sender.send(KeystoreResponse::GenerateSessionKeys(result));
+					return store;
+				})
+			},
 		}
 	}
 }
@@ -221,7 +271,9 @@ impl<Store: CryptoStore + 'static> Stream for KeystoreReceiver<Store> {
 							return Poll::Ready(Some(()));
 						},
 						Poll::Ready(Some(request)) => {
-							let future = KeystoreReceiver::process_request(store, request);
+							let future = KeystoreReceiver::process_request(
+								store, this.archive.clone(), request,
+							);
 							this.state = State::Pending(future);
 						},
 						Poll::Pending => {
@@ -269,6 +321,9 @@ enum RequestMethod {
 	SignWith(KeyTypeId, CryptoTypePublicPair, Vec<u8>),
 	SignWithAny(KeyTypeId, Vec<CryptoTypePublicPair>, Vec<u8>),
 	SignWithAll(KeyTypeId, Vec<CryptoTypePublicPair>, Vec<u8>),
+	RemoveKey(KeyTypeId, Vec<u8>),
+	RotateKeys(Vec<KeyTypeId>),
+	GenerateSessionKeys(Vec<(KeyTypeId, CryptoTypeId)>),
 }
 
 struct KeystoreRequest {
@@ -299,6 +354,20 @@ enum KeystoreResponse {
 	SignWith(Result<Vec<u8>, CryptoStoreError>),
 	SignWithAny(Result<(CryptoTypePublicPair, Vec<u8>), CryptoStoreError>),
 	SignWithAll(Result<Vec<Result<Vec<u8>, CryptoStoreError>>, ()>),
+	RemoveKey(Result<(), CryptoStoreError>),
+	RotateKeys(Result<Vec<u8>, CryptoStoreError>),
+	GenerateSessionKeys(Result<Vec<u8>, CryptoStoreError>),
+}
+
+/// Map a failed key management call, keeping the reason.
+fn management_error(e: CryptoStoreError) -> RpcError {
+	match e {
+		CryptoStoreError::Unavailable =>
+			RpcError::invalid_params("This signer does not support retiring keys"),
+		CryptoStoreError::KeyNotSupported(key_type) =>
+			RpcError::invalid_params(format!("Unsupported session key type {:?}", key_type)),
+		e => RpcError { message: e.to_string(), ..RpcError::internal_error() },
+	}
 }
 
 
@@ -306,8 +375,10 @@ enum KeystoreResponse {
 /// any (async) [`CryptoStore`] for you. Allowing you to easily
 /// wrap any existing CryptoStore implementation and just expose
 /// that over the API.
+#[derive(Clone)]
 pub struct GenericRemoteSignerServer {
 	sender: UnboundedSender<KeystoreRequest>,
+	key_management: bool,
 }
 
 impl GenericRemoteSignerServer {
@@ -318,7 +389,20 @@ impl GenericRemoteSignerServer {
 	/// run to completion – see the `remote-sign-server` for an example usage.
 	pub fn proxy<Store: CryptoStore + 'static>(store: Store) -> (Self, KeystoreReceiver<Store>) {
 		let (sender, receiver) = unbounded::<KeystoreRequest>();
-		(GenericRemoteSignerServer { sender }, KeystoreReceiver::new(store, receiver))
+		let server = GenericRemoteSignerServer { sender, key_management: false };
+		(server, KeystoreReceiver::new(store, None, receiver))
+	}
+
+	/// Like [`Self::proxy`], additionally supporting the removal and
+	/// rotation of keys, which are retired into the given `archive`.
+	pub fn proxy_with_archive<Store: CryptoStore + 'static>(
+		store: Store,
+		archive: impl KeyArchive + 'static,
+	) -> (Self, KeystoreReceiver<Store>) {
+		let (sender, receiver) = unbounded::<KeystoreRequest>();
+		let archive: Arc<dyn KeyArchive> = Arc::new(archive);
+		let server = GenericRemoteSignerServer { sender, key_management: true };
+		(server, KeystoreReceiver::new(store, Some(archive), receiver))
 	}
 
 
@@ -335,6 +419,100 @@ impl GenericRemoteSignerServer {
 		self.sender.unbounded_send(request).expect("Unbounded Send doesn't fail");
 		receiver
 	}
+
+	/// Handle a single binary frame of the SCALE transport (see [`crate::scale`]).
+	///
+	/// Returns the encoded response frame. A frame that can't be decoded is
+	/// answered with a parse error carrying its id (or [`NO_ID`] if even
+	/// that is missing), so the client doesn't wait for it forever.
+	///
+	/// [`NO_ID`]: crate::scale::NO_ID
+	pub fn handle_scale(&self, mut frame: &[u8]) -> BoxFuture<Vec<u8>> {
+		use crate::RemoteSignerApi;
+		use crate::scale::{NO_ID, Request, ResponsePayload, encode_vrf_signature};
+
+		fn respond<T: Encode + Send + 'static>(
+			id: u64,
+			result: BoxFuture<RpcResult<T>>,
+		) -> BoxFuture<Vec<u8>> {
+			async move {
+				let payload: ResponsePayload = result.await
+					.map(|v| v.encode())
+					.map_err(|e| (e.code.code(), e.message));
+				(id, payload).encode()
+			}.boxed()
+		}
+
+		fn reject(id: u64, e: codec::Error) -> BoxFuture<Vec<u8>> {
+			log::debug!(target: "remote_signer", "Rejecting undecodable frame {:}: {:}", id, e);
+			let payload: ResponsePayload = Err((
+				jsonrpc_core::ErrorCode::ParseError.code(),
+				format!("Undecodable request: {:}", e),
+			));
+			futures::future::ready((id, payload).encode()).boxed()
+		}
+
+		let id = match u64::decode(&mut frame) {
+			Ok(id) => id,
+			Err(e) => return reject(NO_ID, e),
+		};
+		let request = match Request::decode(&mut frame) {
+			Ok(request) => request,
+			Err(e) => return reject(id, e),
+		};
+
+		match request {
+			Request::Sr25519PublicKeys(key_type) =>
+				respond(id, self.sr25519_public_keys(key_type)),
+			Request::Sr25519GenerateNew(key_type, seed) =>
+				respond(id, self.sr25519_generate_new(key_type, seed)),
+			Request::Ed25519PublicKeys(key_type) =>
+				respond(id, self.ed25519_public_keys(key_type)),
+			Request::Ed25519GenerateNew(key_type, seed) =>
+				respond(id, self.ed25519_generate_new(key_type, seed)),
+			Request::EcdsaPublicKeys(key_type) =>
+				respond(id, self.ecdsa_public_keys(key_type)),
+			Request::EcdsaGenerateNew(key_type, seed) =>
+				respond(id, self.ecdsa_generate_new(key_type, seed)),
+			Request::InsertUnknown(key_type, suri, public) =>
+				respond(id, self.insert_unknown(key_type, suri, public.into())),
+			Request::SupportedKeys(key_type, keys) => respond(id, self.supported_keys(
+				key_type,
+				keys.into_iter().map(PublicPair).collect(),
+			)),
+			Request::Keys(key_type) =>
+				respond(id, self.keys(key_type)),
+			Request::HasKeys(public_keys) => respond(id, self.has_keys(
+				public_keys.into_iter().map(|(k, t)| (k.into(), t)).collect(),
+			)),
+			Request::SignWith(key_type, key, msg) =>
+				respond(id, self.sign_with(key_type, key.into(), msg.into())),
+			Request::SignWithAny(key_type, keys, msg) => respond(id, self.sign_with_any(
+				key_type,
+				keys.into_iter().map(PublicPair).collect(),
+				msg.into(),
+			)),
+			Request::SignWithAll(key_type, keys, msg) => respond(id, self.sign_with_all(
+				key_type,
+				keys.into_iter().map(PublicPair).collect(),
+				msg.into(),
+			)),
+			Request::Sr25519VrfSign(key_type, public, transcript) => {
+				let result = self.sr25519_vrf_sign(key_type, public, transcript.into());
+				respond(id, async move {
+					result.await.map(|sig| encode_vrf_signature(&sig.0))
+				}.boxed())
+			},
+			Request::RemoveKey(key_type, public) =>
+				respond(id, self.remove_key(key_type, public.into())),
+			Request::RotateKeys(key_types) =>
+				respond(id, self.rotate_keys(key_types)),
+			Request::GenerateSessionKeys(keys) =>
+				respond(id, self.generate_session_keys(keys)),
+			Request::Capabilities =>
+				respond(id, self.capabilities()),
+		}
+	}
 }
 
 impl crate::RemoteSignerApi for GenericRemoteSignerServer {
@@ -421,41 +599,49 @@ impl crate::RemoteSignerApi for GenericRemoteSignerServer {
 		}.boxed()
     }
 
-    fn insert_unknown(&self, key_type: KeyTypeId, suri: String, public: Vec<u8>) -> BoxFuture<RpcResult<()>> {
-		let _ = self.send_request(RequestMethod::InsertUnknown(key_type, suri, public));
+    fn insert_unknown(&self, key_type: KeyTypeId, suri: String, public: HexBytes) -> BoxFuture<RpcResult<()>> {
+		let receiver = self.send_request(RequestMethod::InsertUnknown(key_type, suri, public.into()));
 		async move {
-			Ok(())
+			if let Ok(KeystoreResponse::InsertUnknown(result)) = receiver.await {
+				result.map_err(|_| RpcError::internal_error())
+			} else {
+				Err(RpcError::internal_error())
+			}
 		}.boxed()
 	}
 
     fn supported_keys(
 		&self,
 		id: KeyTypeId,
-		keys: Vec<CryptoTypePublicPair>
-	) -> BoxFuture<RpcResult<Vec<CryptoTypePublicPair>>> {
+		keys: Vec<PublicPair>
+	) -> BoxFuture<RpcResult<Vec<PublicPair>>> {
+		let keys = keys.into_iter().map(Into::into).collect();
 		let receiver = self.send_request(RequestMethod::SupportedKeys(id, keys));
 		async move {
 			if let Ok(KeystoreResponse::SupportedKeys(keys)) = receiver.await {
-				keys.map_err(|_| RpcError::internal_error())
+				keys.map(|k| k.into_iter().map(PublicPair).collect())
+					.map_err(|_| RpcError::internal_error())
 			} else {
 				Ok(vec![])
 			}
 		}.boxed()
     }
 
-    fn keys(&self, id: KeyTypeId) -> BoxFuture<RpcResult<Vec<CryptoTypePublicPair>>> {
+    fn keys(&self, id: KeyTypeId) -> BoxFuture<RpcResult<Vec<PublicPair>>> {
 		let receiver = self.send_request(RequestMethod::Keys(id));
 		async move {
 			if let Ok(KeystoreResponse::Keys(keys)) = receiver.await {
-				keys.map_err(|_| RpcError::internal_error())
+				keys.map(|k| k.into_iter().map(PublicPair).collect())
+					.map_err(|_| RpcError::internal_error())
 			} else {
 				Ok(vec![])
 			}
 		}.boxed()
     }
 
-    fn has_keys(&self, public_keys: Vec<(Vec<u8>, KeyTypeId)>) -> BoxFuture<RpcResult<bool>> {
-		let receiver = self.send_request(RequestMethod::HasKeys(public_keys.to_vec()));
+    fn has_keys(&self, public_keys: Vec<(HexBytes, KeyTypeId)>) -> BoxFuture<RpcResult<bool>> {
+		let public_keys = public_keys.into_iter().map(|(k, t)| (k.into(), t)).collect();
+		let receiver = self.send_request(RequestMethod::HasKeys(public_keys));
 		async move {
 			if let Ok(KeystoreResponse::HasKeys(exists)) = receiver.await {
 				Ok(exists)
@@ -468,13 +654,13 @@ impl crate::RemoteSignerApi for GenericRemoteSignerServer {
     fn sign_with(
 		&self,
 		id: KeyTypeId,
-		key: CryptoTypePublicPair,
-		msg: Vec<u8>,
-	) -> BoxFuture<RpcResult<Vec<u8>>> {
-		let receiver = self.send_request(RequestMethod::SignWith(id, key, msg));
+		key: PublicPair,
+		msg: HexBytes,
+	) -> BoxFuture<RpcResult<HexBytes>> {
+		let receiver = self.send_request(RequestMethod::SignWith(id, key.into(), msg.into()));
 		async move {
 			if let Ok(KeystoreResponse::SignWith(result)) = receiver.await {
-				result.map_err(|_| RpcError::internal_error())
+				result.map(HexBytes).map_err(|_| RpcError::internal_error())
 			} else {
 				Err(RpcError::internal_error())
 			}
@@ -484,13 +670,15 @@ impl crate::RemoteSignerApi for GenericRemoteSignerServer {
 	fn sign_with_any(
 		&self,
 		id: KeyTypeId,
-		keys: Vec<CryptoTypePublicPair>,
-		msg: Vec<u8>
-	) -> BoxFuture<RpcResult<(CryptoTypePublicPair, Vec<u8>)>> {
-		let receiver = self.send_request(RequestMethod::SignWithAny(id, keys, msg));
+		keys: Vec<PublicPair>,
+		msg: HexBytes
+	) -> BoxFuture<RpcResult<(PublicPair, HexBytes)>> {
+		let keys = keys.into_iter().map(Into::into).collect();
+		let receiver = self.send_request(RequestMethod::SignWithAny(id, keys, msg.into()));
 		async move {
 			if let Ok(KeystoreResponse::SignWithAny(result)) =  receiver.await {
-				result.map_err(|_| RpcError::internal_error())
+				result.map(|(key, sig)| (PublicPair(key), HexBytes(sig)))
+					.map_err(|_| RpcError::internal_error())
 			} else {
 				Err(RpcError::internal_error())
 			}
@@ -500,14 +688,15 @@ impl crate::RemoteSignerApi for GenericRemoteSignerServer {
 	fn sign_with_all(
 		&self,
 		id: KeyTypeId,
-		keys: Vec<CryptoTypePublicPair>,
-		msg: Vec<u8>,
-	) -> BoxFuture<RpcResult<Vec<Result<Vec<u8>, String>>>> {
-		let receiver = self.send_request(RequestMethod::SignWithAll(id, keys, msg));
+		keys: Vec<PublicPair>,
+		msg: HexBytes,
+	) -> BoxFuture<RpcResult<Vec<Result<HexBytes, String>>>> {
+		let keys = keys.into_iter().map(Into::into).collect();
+		let receiver = self.send_request(RequestMethod::SignWithAll(id, keys, msg.into()));
 		async move {
 			if let Ok(KeystoreResponse::SignWithAll(result)) = receiver.await {
 				result.map_err(|_| RpcError::internal_error())
-				.map(|v| v.into_iter().map(|i| i.map_err(|e| e.to_string())).collect())
+				.map(|v| v.into_iter().map(|i| i.map(HexBytes).map_err(|e| e.to_string())).collect())
 			} else {
 				Err(RpcError::internal_error())
 			}
@@ -519,14 +708,14 @@ impl crate::RemoteSignerApi for GenericRemoteSignerServer {
 		key_type: KeyTypeId,
 		public: sp_application_crypto::sr25519::Public,
 		transcript_data: crate::TransferableVRFTranscriptData,
-	) -> BoxFuture<RpcResult<sp_keystore::vrf::VRFSignature>> {
+	) -> BoxFuture<RpcResult<crate::VrfSignature>> {
 
 		match transcript_data.try_into() {
 			Ok(vrf_data) => {
 				let receiver = self.send_request(RequestMethod::Sr25519VrfSign(key_type, public, vrf_data));
 				async move {
 						if let Ok(KeystoreResponse::Sr25519VrfSign(result)) = receiver.await {
-							result.map_err(|_| RpcError::internal_error())
+							result.map(Into::into).map_err(|_| RpcError::internal_error())
 						} else {
 							Err(RpcError::internal_error())
 						}
@@ -535,6 +724,51 @@ impl crate::RemoteSignerApi for GenericRemoteSignerServer {
 			Err(e) => async move { Err(RpcError::invalid_params(e)) }.boxed(),
 		}
     }
+
+	fn remove_key(&self, key_type: KeyTypeId, public: HexBytes) -> BoxFuture<RpcResult<()>> {
+		let receiver = self.send_request(RequestMethod::RemoveKey(key_type, public.into()));
+		async move {
+			if let Ok(KeystoreResponse::RemoveKey(result)) = receiver.await {
+				result.map_err(management_error)
+			} else {
+				Err(RpcError::internal_error())
+			}
+		}.boxed()
+	}
+
+	fn rotate_keys(&self, key_types: Vec<KeyTypeId>) -> BoxFuture<RpcResult<HexBytes>> {
+		let receiver = self.send_request(RequestMethod::RotateKeys(key_types));
+		async move {
+			if let Ok(KeystoreResponse::RotateKeys(result)) = receiver.await {
+				result.map(HexBytes).map_err(management_error)
+			} else {
+				Err(RpcError::internal_error())
+			}
+		}.boxed()
+	}
+
+	fn generate_session_keys(
+		&self,
+		keys: Vec<(KeyTypeId, CryptoTypeId)>,
+	) -> BoxFuture<RpcResult<HexBytes>> {
+		let receiver = self.send_request(RequestMethod::GenerateSessionKeys(keys));
+		async move {
+			if let Ok(KeystoreResponse::GenerateSessionKeys(result)) = receiver.await {
+				result.map(HexBytes).map_err(management_error)
+			} else {
+				Err(RpcError::internal_error())
+			}
+		}.boxed()
+	}
+
+	fn capabilities(&self) -> BoxFuture<RpcResult<Capabilities>> {
+		let capabilities = Capabilities {
+			implementation: concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION")).into(),
+			methods: crate::METHODS.iter().map(|m| m.to_string()).collect(),
+			key_management: self.key_management,
+		};
+		async move { Ok(capabilities) }.boxed()
+	}
 }
 
 #[cfg(test)]
@@ -577,19 +811,29 @@ mod tests {
 		jsonrpc_test::Rpc::new(RemoteSignerApi::to_delegate(server))
 	}
 
-	#[tokio::test(core_threads=4)]
+	#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+	async fn test_capabilities() {
+		let rpc = setup(0).await;
+		let r = rpc.request("signer_capabilities", &Vec::<u8>::new());
+		let res : Capabilities = serde_json::from_str(&r).unwrap();
+		assert_eq!(res.methods.len(), crate::METHODS.len());
+		assert!(res.methods.iter().any(|m| m == "signer_capabilities"));
+		assert!(!res.key_management);
+	}
+
+	#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
 	async fn test_keys() {
 		let rpc = setup(2).await;
 		let r = rpc.request("signer_keys", &[TEST_TK]);
-		let res : Vec<CryptoTypePublicPair> = serde_json::from_str(&r).unwrap();
+		let res : Vec<PublicPair> = serde_json::from_str(&r).unwrap();
 		assert_eq!(res.len(), 9);
 
 		let r = rpc.request("signer_keys", &[TEST_TK_NOPE]);
-		let res : Vec<CryptoTypePublicPair> = serde_json::from_str(&r).unwrap();
+		let res : Vec<PublicPair> = serde_json::from_str(&r).unwrap();
 		assert_eq!(res.len(), 0);
 	}
 
-	#[tokio::test(core_threads=4)]
+	#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
 	async fn test_sr25519_public_keys() {
 		let rpc = setup(2).await;
 		let r = rpc.request("signer_sr25519_public_keys", &[TEST_TK]);
@@ -601,7 +845,7 @@ mod tests {
 		assert_eq!(res.len(), 0);
 	}
 
-	#[tokio::test(core_threads=4)]
+	#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
 	async fn test_ed25519_public_keys() {
 		let rpc = setup(2).await;
 		let r = rpc.request("signer_ed25519_public_keys", &[TEST_TK]);
@@ -613,7 +857,7 @@ mod tests {
 		assert_eq!(res.len(), 0);
 	}
 
-	#[tokio::test(core_threads=4)]
+	#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
 	#[ignore]
 	async fn test_ecdsa_public_keys() {
 		let rpc = setup(2).await;
@@ -625,4 +869,171 @@ mod tests {
 		let res : Vec<ecdsa::Public> = serde_json::from_str(&r).unwrap();
 		assert_eq!(res.len(), 0);
 	}
+
+	#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+	async fn test_sign_with_accepts_v1_and_v2() {
+		use sp_core::Pair;
+
+		let rpc = setup(2).await;
+		let public = ed25519::Pair::from_string("//Bob", None).unwrap().public();
+		let msg = vec![1u8, 2, 3];
+
+		let v1 = rpc.request("signer_sign_with", &serde_json::json!([
+			TEST_TK,
+			[ed25519::CRYPTO_ID.0, public.0.to_vec()],
+			msg,
+		]));
+		let v2 = rpc.request("signer_sign_with", &serde_json::json!([
+			TEST_TK,
+			["ed25", sp_core::bytes::to_hex(&public.0, false)],
+			"0x010203",
+		]));
+
+		let v1 : HexBytes = serde_json::from_str(&v1).unwrap();
+		let v2 : HexBytes = serde_json::from_str(&v2).unwrap();
+		assert_eq!(v1.len(), 64);
+		assert_eq!(v1, v2);
+	}
+
+	#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+	async fn test_vrf_sign_accepts_v1_and_answers_hex() {
+		use sp_core::Pair;
+
+		let rpc = setup(2).await;
+		let public = sr25519::Pair::from_string("//Alice", None).unwrap().public();
+
+		let v1 = rpc.request("signer_sr25519_vrf_sign", &serde_json::json!([
+			TEST_TK,
+			public,
+			{ "label": BABE_ENGINE_ID, "items": [{ "U64": 1 }, { "U64": 2 }, { "Bytes": vec![7u8; 32] }] },
+		]));
+		let v2 = rpc.request("signer_sr25519_vrf_sign", &serde_json::json!([
+			TEST_TK,
+			public,
+			{
+				"label": "0x42414245",
+				"items": [{ "U64": 1 }, { "U64": 2 }, { "Bytes": sp_core::bytes::to_hex(&[7u8; 32], false) }],
+			},
+		]));
+
+		let v1 : serde_json::Value = serde_json::from_str(&v1).unwrap();
+		let v2 : serde_json::Value = serde_json::from_str(&v2).unwrap();
+		assert!(v1["output"].as_str().unwrap().starts_with("0x"));
+		assert!(v1["proof"].as_str().unwrap().starts_with("0x"));
+		assert_eq!(v1["output"], v2["output"]);
+	}
+
+	#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+	async fn test_handle_scale() {
+		use crate::scale::{NO_ID, Request, ResponsePayload};
+
+		let keystore = LocalKeystore::in_memory();
+		keystore.sr25519_generate_new(TEST_TK, Some("//Alice")).await.unwrap();
+		let (server, mut runner) = GenericRemoteSignerServer::proxy(keystore);
+		tokio::task::spawn(async move {
+			runner.next().await;
+		});
+
+		let frame = (1u64, Request::Sr25519PublicKeys(TEST_TK)).encode();
+		let response = server.handle_scale(&frame).await;
+		let (id, payload) = <(u64, ResponsePayload)>::decode(&mut &response[..]).unwrap();
+		assert_eq!(id, 1);
+		let keys = Vec::<sr25519::Public>::decode(&mut &payload.unwrap()[..]).unwrap();
+		assert_eq!(keys.len(), 1);
+
+		// unknown variant: answered with the id it came with
+		let mut frame = 2u64.encode();
+		frame.push(0xff);
+		let response = server.handle_scale(&frame).await;
+		let (id, payload) = <(u64, ResponsePayload)>::decode(&mut &response[..]).unwrap();
+		assert_eq!(id, 2);
+		assert_eq!(payload.unwrap_err().0, jsonrpc_core::ErrorCode::ParseError.code());
+
+		// too short to even carry an id
+		let response = server.handle_scale(&[1, 2]).await;
+		let (id, payload) = <(u64, ResponsePayload)>::decode(&mut &response[..]).unwrap();
+		assert_eq!(id, NO_ID);
+		assert!(payload.is_err());
+	}
+
+	#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+	async fn test_rotate_keys_archives_old_keys() {
+		const AURA : KeyTypeId = KeyTypeId(*b"aura");
+		const GRAN : KeyTypeId = KeyTypeId(*b"gran");
+
+		let path = std::env::temp_dir().join(format!("tssrs-rotate-{}", std::process::id()));
+		let keystore = LocalKeystore::open(&path, None).unwrap();
+		let old = keystore.sr25519_generate_new(AURA, None).await.unwrap();
+
+		let (server, mut runner) = GenericRemoteSignerServer::proxy_with_archive(
+			keystore,
+			FsKeyArchive::new(vec![path.clone()]),
+		);
+		tokio::task::spawn(async move {
+			for _ in 0..2u8 {
+				runner.next().await;
+			}
+		});
+		let rpc = jsonrpc_test::Rpc::new(RemoteSignerApi::to_delegate(server));
+
+		let r = rpc.request("signer_rotate_keys", &[[AURA, GRAN]]);
+		let session_keys : HexBytes = serde_json::from_str(&r).unwrap();
+		assert_eq!(session_keys.len(), 64);
+
+		let r = rpc.request("signer_sr25519_public_keys", &[AURA]);
+		let res : Vec<sr25519::Public> = serde_json::from_str(&r).unwrap();
+		assert_eq!(res, vec![<sr25519::Public as sp_core::Public>::from_slice(&session_keys[..32])]);
+		assert!(!res.contains(&old));
+		assert_eq!(path.join("archive").read_dir().unwrap().count(), 1);
+
+		let _ = std::fs::remove_dir_all(&path);
+	}
+
+	#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+	async fn test_generate_session_keys_is_all_or_nothing() {
+		let path = std::env::temp_dir().join(format!("tssrs-generate-{}", std::process::id()));
+		let (server, mut runner) = GenericRemoteSignerServer::proxy_with_archive(
+			LocalKeystore::open(&path, None).unwrap(),
+			FsKeyArchive::new(vec![path.clone()]),
+		);
+		tokio::task::spawn(async move {
+			for _ in 0..3u8 {
+				runner.next().await;
+			}
+		});
+		let rpc = jsonrpc_test::Rpc::new(RemoteSignerApi::to_delegate(server));
+
+		let r = rpc.request("signer_generate_session_keys", &serde_json::json!([[
+			[TEST_TK_NOPE, sr25519::CRYPTO_ID],
+			[TEST_TK_NOPE, CryptoTypeId(*b"nope")],
+		]]));
+		assert!(serde_json::from_str::<HexBytes>(&r).is_err());
+
+		let r = rpc.request("signer_sr25519_public_keys", &[TEST_TK_NOPE]);
+		let res : Vec<sr25519::Public> = serde_json::from_str(&r).unwrap();
+		assert_eq!(res.len(), 0);
+
+		let r = rpc.request("signer_generate_session_keys", &serde_json::json!([[
+			[TEST_TK_NOPE, sr25519::CRYPTO_ID],
+			[TEST_TK_NOPE, ed25519::CRYPTO_ID],
+		]]));
+		let session_keys : HexBytes = serde_json::from_str(&r).unwrap();
+		assert_eq!(session_keys.len(), 64);
+
+		let _ = std::fs::remove_dir_all(&path);
+	}
+
+	#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+	async fn test_generate_session_keys_needs_archive() {
+		let rpc = setup(2).await;
+		let r = rpc.request("signer_generate_session_keys", &serde_json::json!([[
+			[TEST_TK_NOPE, sr25519::CRYPTO_ID],
+			[TEST_TK_NOPE, ed25519::CRYPTO_ID],
+		]]));
+		assert!(serde_json::from_str::<HexBytes>(&r).is_err());
+
+		let r = rpc.request("signer_sr25519_public_keys", &[TEST_TK_NOPE]);
+		let res : Vec<sr25519::Public> = serde_json::from_str(&r).unwrap();
+		assert_eq!(res.len(), 0);
+	}
 }
@@ -0,0 +1,545 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+/// Keystore encrypting its key files at rest.
+///
+/// Every key file holds the secret URI of one key, sealed with
+/// XChaCha20-Poly1305 under a random data key and bound to its file name.
+/// The data key itself is stored in `master.key`, wrapped with a key derived
+/// from the passphrase by scrypt. Changing the passphrase only re-wraps the
+/// data key, the key files stay untouched.
+///
+/// Key files are named like those of `LocalKeystore`, so the
+/// [`FsKeyArchive`](super::FsKeyArchive) works on both.
+
+use std::{
+	collections::HashMap,
+	convert::TryFrom,
+	fs,
+	path::{Path, PathBuf},
+};
+use async_trait::async_trait;
+use chacha20poly1305::{
+	aead::{Aead, NewAead, Payload},
+	Key, XChaCha20Poly1305, XNonce,
+};
+use codec::{Decode, Encode};
+use parking_lot::RwLock;
+use rand::{rngs::OsRng, RngCore};
+use sp_core::{
+	crypto::{CryptoTypePublicPair, KeyTypeId, Pair as PairT},
+	hexdisplay::HexDisplay,
+	sr25519::Public as Sr25519Public,
+};
+use sp_keystore::{
+	CryptoStore, Error as CryptoStoreError, SyncCryptoStore,
+	vrf::{VRFTranscriptData, VRFSignature, make_transcript},
+};
+use sp_application_crypto::{ed25519, sr25519, ecdsa};
+use zeroize::Zeroizing;
+
+const MASTER_KEY_FILE: &str = "master.key";
+const NONCE_LEN: usize = 24;
+
+/// scrypt cost, `N = 2^15, r = 8, p = 1`: 32 MiB and a fraction of a second.
+const KDF_LOG_N: u8 = 15;
+const KDF_R: u32 = 8;
+const KDF_P: u32 = 1;
+
+/// Content of `master.key`: the data key wrapped with the passphrase.
+#[derive(Encode, Decode)]
+struct MasterKeyFile {
+	version: u8,
+	salt: [u8; 16],
+	log_n: u8,
+	r: u32,
+	p: u32,
+	nonce: [u8; NONCE_LEN],
+	wrapped: Vec<u8>,
+}
+
+type DataKey = Zeroizing<[u8; 32]>;
+type Secrets = HashMap<(KeyTypeId, Vec<u8>), Zeroizing<String>>;
+
+fn derive_key(passphrase: &str, salt: &[u8], log_n: u8, r: u32, p: u32) -> Result<DataKey, String> {
+	let params = scrypt::Params::new(log_n, r, p)
+		.map_err(|e| format!("Invalid KDF parameters: {:}", e))?;
+	let mut key = Zeroizing::new([0u8; 32]);
+	scrypt::scrypt(passphrase.as_bytes(), salt, &params, &mut key[..])
+		.map_err(|e| format!("Key derivation failed: {:}", e))?;
+	Ok(key)
+}
+
+fn seal(key: &[u8; 32], aad: &[u8], msg: &[u8]) -> ([u8; NONCE_LEN], Vec<u8>) {
+	let mut nonce = [0u8; NONCE_LEN];
+	OsRng.fill_bytes(&mut nonce);
+	let sealed = XChaCha20Poly1305::new(Key::from_slice(key))
+		.encrypt(XNonce::from_slice(&nonce), Payload { msg, aad })
+		.expect("Encrypting in memory doesn't fail");
+	(nonce, sealed)
+}
+
+fn open_sealed(key: &[u8; 32], aad: &[u8], nonce: &[u8], sealed: &[u8]) -> Option<Vec<u8>> {
+	XChaCha20Poly1305::new(Key::from_slice(key))
+		.decrypt(XNonce::from_slice(nonce), Payload { msg: sealed, aad })
+		.ok()
+}
+
+/// Write `data` to `path` through a temporary file, so it is replaced at once.
+fn write_atomic(path: &Path, data: &[u8]) -> std::io::Result<()> {
+	let tmp = path.with_extension("tmp");
+	fs::write(&tmp, data)?;
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::PermissionsExt;
+		fs::set_permissions(&tmp, fs::Permissions::from_mode(0o600))?;
+	}
+	fs::rename(&tmp, path)
+}
+
+/// A filesystem keystore with encrypted key files.
+pub struct EncryptedKeystore {
+	path: PathBuf,
+	data_key: RwLock<DataKey>,
+	/// Keys generated from a seed are only kept in memory.
+	ephemeral: RwLock<Secrets>,
+}
+
+impl EncryptedKeystore {
+	/// Open the keystore at `path`, creating it if it doesn't exist yet.
+	///
+	/// Fails if `passphrase` doesn't unlock an existing keystore.
+	pub fn open<T: Into<PathBuf>>(path: T, passphrase: &str) -> Result<Self, String> {
+		let path = path.into();
+		fs::create_dir_all(&path)
+			.map_err(|e| format!("Creating {:} failed: {:}", path.display(), e))?;
+
+		let master = path.join(MASTER_KEY_FILE);
+		let data_key = if master.exists() {
+			let encoded = fs::read(&master)
+				.map_err(|e| format!("Reading {:} failed: {:}", master.display(), e))?;
+			let file = MasterKeyFile::decode(&mut &encoded[..])
+				.map_err(|e| format!("Corrupt {:}: {:}", master.display(), e))?;
+			if file.version != 1 {
+				return Err(format!("Unsupported keystore version {:}", file.version))
+			}
+			let kek = derive_key(passphrase, &file.salt, file.log_n, file.r, file.p)?;
+			let plain = Zeroizing::new(open_sealed(&kek, MASTER_KEY_FILE.as_bytes(), &file.nonce, &file.wrapped)
+				.ok_or_else(|| "Wrong passphrase".to_string())?);
+			let mut data_key = Zeroizing::new([0u8; 32]);
+			if plain.len() != 32 {
+				return Err(format!("Corrupt {:}", master.display()))
+			}
+			data_key.copy_from_slice(&plain);
+			data_key
+		} else {
+			let mut data_key = Zeroizing::new([0u8; 32]);
+			OsRng.fill_bytes(&mut data_key[..]);
+			Self::write_master_key(&path, &data_key, passphrase)?;
+			data_key
+		};
+
+		Ok(EncryptedKeystore {
+			path,
+			data_key: RwLock::new(data_key),
+			ephemeral: Default::default(),
+		})
+	}
+
+	/// Change the passphrase protecting the keystore.
+	///
+	/// Only `master.key` is rewritten, the key files stay as they are.
+	pub fn change_passphrase(&self, passphrase: &str) -> Result<(), String> {
+		Self::write_master_key(&self.path, &self.data_key.read(), passphrase)
+	}
+
+	fn write_master_key(path: &Path, data_key: &[u8; 32], passphrase: &str) -> Result<(), String> {
+		let mut salt = [0u8; 16];
+		OsRng.fill_bytes(&mut salt);
+		let kek = derive_key(passphrase, &salt, KDF_LOG_N, KDF_R, KDF_P)?;
+		let (nonce, wrapped) = seal(&kek, MASTER_KEY_FILE.as_bytes(), data_key);
+		let file = MasterKeyFile {
+			version: 1,
+			salt,
+			log_n: KDF_LOG_N,
+			r: KDF_R,
+			p: KDF_P,
+			nonce,
+			wrapped,
+		};
+		write_atomic(&path.join(MASTER_KEY_FILE), &file.encode())
+			.map_err(|e| format!("Writing {:} failed: {:}", MASTER_KEY_FILE, e))
+	}
+
+	fn file_name(key_type: KeyTypeId, public: &[u8]) -> String {
+		format!("{}{}", HexDisplay::from(&key_type.0), HexDisplay::from(&public))
+	}
+
+	fn store_secret(&self, key_type: KeyTypeId, public: &[u8], suri: &str) -> Result<(), CryptoStoreError> {
+		let name = Self::file_name(key_type, public);
+		let (nonce, sealed) = seal(&self.data_key.read(), name.as_bytes(), suri.as_bytes());
+		write_atomic(&self.path.join(&name), &[&nonce[..], &sealed[..]].concat())
+			.map_err(|e| CryptoStoreError::Other(format!("Writing key failed: {:}", e)))
+	}
+
+	fn secret(&self, key_type: KeyTypeId, public: &[u8]) -> Result<Option<Zeroizing<String>>, CryptoStoreError> {
+		if let Some(suri) = self.ephemeral.read().get(&(key_type, public.to_vec())) {
+			return Ok(Some(suri.clone()))
+		}
+
+		let name = Self::file_name(key_type, public);
+		let content = match fs::read(self.path.join(&name)) {
+			Ok(content) => content,
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+			Err(e) => return Err(CryptoStoreError::Other(format!("Reading key failed: {:}", e))),
+		};
+		if content.len() < NONCE_LEN {
+			return Err(CryptoStoreError::Other(format!("Corrupt key file {:}", name)))
+		}
+		let (nonce, sealed) = content.split_at(NONCE_LEN);
+		let plain = open_sealed(&self.data_key.read(), name.as_bytes(), nonce, sealed)
+			.ok_or_else(|| CryptoStoreError::Other(format!("Key file {:} failed authentication", name)))?;
+		String::from_utf8(plain)
+			.map(|s| Some(Zeroizing::new(s)))
+			.map_err(|_| CryptoStoreError::Other(format!("Corrupt key file {:}", name)))
+	}
+
+	fn key_pair<Pair: PairT>(
+		&self,
+		key_type: KeyTypeId,
+		public: &Pair::Public,
+	) -> Result<Option<Pair>, CryptoStoreError> {
+		let suri = match self.secret(key_type, public.as_ref())? {
+			Some(suri) => suri,
+			None => return Ok(None),
+		};
+		let pair = Pair::from_string(&suri, None)
+			.map_err(|_| CryptoStoreError::ValidationError("Invalid seed".into()))?;
+		if &pair.public() != public {
+			return Err(CryptoStoreError::ValidationError("Invalid public key".into()))
+		}
+		Ok(Some(pair))
+	}
+
+	fn generate<Pair: PairT>(
+		&self,
+		key_type: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<Pair::Public, CryptoStoreError> {
+		match seed {
+			Some(seed) => {
+				let pair = Pair::from_string(seed, None)
+					.map_err(|_| CryptoStoreError::ValidationError("Invalid seed".into()))?;
+				self.ephemeral.write().insert(
+					(key_type, pair.public().as_ref().to_vec()),
+					Zeroizing::new(seed.to_string()),
+				);
+				Ok(pair.public())
+			},
+			None => {
+				let (pair, phrase, _) = Pair::generate_with_phrase(None);
+				let phrase = Zeroizing::new(phrase);
+				self.store_secret(key_type, pair.public().as_ref(), &phrase)?;
+				Ok(pair.public())
+			},
+		}
+	}
+
+	/// Raw public keys of all keys of `key_type`.
+	fn raw_public_keys(&self, key_type: KeyTypeId) -> Vec<Vec<u8>> {
+		let mut keys = self.ephemeral.read().keys()
+			.filter(|(t, _)| *t == key_type)
+			.map(|(_, public)| public.clone())
+			.collect::<Vec<_>>();
+
+		let prefix = format!("{}", HexDisplay::from(&key_type.0));
+		if let Ok(entries) = fs::read_dir(&self.path) {
+			for entry in entries.flatten() {
+				let name = entry.file_name();
+				let name = match name.to_str() {
+					Some(name) if name.starts_with(&prefix) => name,
+					_ => continue,
+				};
+				if let Ok(public) = sp_core::bytes::from_hex(&name[prefix.len()..]) {
+					if !keys.contains(&public) {
+						keys.push(public);
+					}
+				}
+			}
+		}
+		keys
+	}
+
+	fn sign<Pair: PairT>(
+		&self,
+		key_type: KeyTypeId,
+		public: Result<Pair::Public, ()>,
+		msg: &[u8],
+	) -> Result<Option<Vec<u8>>, CryptoStoreError> where Pair::Signature: Encode {
+		let public = public.map_err(|_| CryptoStoreError::ValidationError("Invalid public key".into()))?;
+		Ok(self.key_pair::<Pair>(key_type, &public)?.map(|pair| pair.sign(msg).encode()))
+	}
+}
+
+#[async_trait]
+impl CryptoStore for EncryptedKeystore {
+	async fn keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, CryptoStoreError> {
+		SyncCryptoStore::keys(self, id)
+	}
+
+	async fn sr25519_public_keys(&self, id: KeyTypeId) -> Vec<sr25519::Public> {
+		SyncCryptoStore::sr25519_public_keys(self, id)
+	}
+
+	async fn sr25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<sr25519::Public, CryptoStoreError> {
+		SyncCryptoStore::sr25519_generate_new(self, id, seed)
+	}
+
+	async fn ed25519_public_keys(&self, id: KeyTypeId) -> Vec<ed25519::Public> {
+		SyncCryptoStore::ed25519_public_keys(self, id)
+	}
+
+	async fn ed25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ed25519::Public, CryptoStoreError> {
+		SyncCryptoStore::ed25519_generate_new(self, id, seed)
+	}
+
+	async fn ecdsa_public_keys(&self, id: KeyTypeId) -> Vec<ecdsa::Public> {
+		SyncCryptoStore::ecdsa_public_keys(self, id)
+	}
+
+	async fn ecdsa_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ecdsa::Public, CryptoStoreError> {
+		SyncCryptoStore::ecdsa_generate_new(self, id, seed)
+	}
+
+	async fn insert_unknown(&self, id: KeyTypeId, suri: &str, public: &[u8]) -> Result<(), ()> {
+		SyncCryptoStore::insert_unknown(self, id, suri, public)
+	}
+
+	async fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		SyncCryptoStore::has_keys(self, public_keys)
+	}
+
+	async fn supported_keys(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+	) -> Result<Vec<CryptoTypePublicPair>, CryptoStoreError> {
+		SyncCryptoStore::supported_keys(self, id, keys)
+	}
+
+	async fn sign_with(
+		&self,
+		id: KeyTypeId,
+		key: &CryptoTypePublicPair,
+		msg: &[u8],
+	) -> Result<Vec<u8>, CryptoStoreError> {
+		SyncCryptoStore::sign_with(self, id, key, msg)
+	}
+
+	async fn sr25519_vrf_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &Sr25519Public,
+		transcript_data: VRFTranscriptData,
+	) -> Result<VRFSignature, CryptoStoreError> {
+		SyncCryptoStore::sr25519_vrf_sign(self, key_type, public, transcript_data)
+	}
+}
+
+impl SyncCryptoStore for EncryptedKeystore {
+	fn keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, CryptoStoreError> {
+		let mut keys = vec![];
+		keys.extend(SyncCryptoStore::sr25519_public_keys(self, id).into_iter().map(Into::into));
+		keys.extend(SyncCryptoStore::ed25519_public_keys(self, id).into_iter().map(Into::into));
+		keys.extend(SyncCryptoStore::ecdsa_public_keys(self, id).into_iter().map(Into::into));
+		Ok(keys)
+	}
+
+	fn supported_keys(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+	) -> Result<Vec<CryptoTypePublicPair>, CryptoStoreError> {
+		let all_keys = SyncCryptoStore::keys(self, id)?;
+		Ok(keys.into_iter().filter(|key| all_keys.contains(key)).collect())
+	}
+
+	fn sign_with(
+		&self,
+		id: KeyTypeId,
+		key: &CryptoTypePublicPair,
+		msg: &[u8],
+	) -> Result<Vec<u8>, CryptoStoreError> {
+		let signature = match key.0 {
+			ed25519::CRYPTO_ID =>
+				self.sign::<ed25519::Pair>(id, ed25519::Public::try_from(&key.1[..]), msg)?,
+			sr25519::CRYPTO_ID =>
+				self.sign::<sr25519::Pair>(id, sr25519::Public::try_from(&key.1[..]), msg)?,
+			ecdsa::CRYPTO_ID =>
+				self.sign::<ecdsa::Pair>(id, ecdsa::Public::try_from(&key.1[..]), msg)?,
+			_ => return Err(CryptoStoreError::KeyNotSupported(id)),
+		};
+		signature.ok_or_else(|| CryptoStoreError::PairNotFound(format!("{:?}", key)))
+	}
+
+	fn sr25519_public_keys(&self, key_type: KeyTypeId) -> Vec<sr25519::Public> {
+		self.raw_public_keys(key_type).into_iter()
+			.filter_map(|k| sr25519::Public::try_from(&k[..]).ok())
+			.collect()
+	}
+
+	fn sr25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<sr25519::Public, CryptoStoreError> {
+		self.generate::<sr25519::Pair>(id, seed)
+	}
+
+	fn ed25519_public_keys(&self, key_type: KeyTypeId) -> Vec<ed25519::Public> {
+		self.raw_public_keys(key_type).into_iter()
+			.filter_map(|k| ed25519::Public::try_from(&k[..]).ok())
+			.collect()
+	}
+
+	fn ed25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ed25519::Public, CryptoStoreError> {
+		self.generate::<ed25519::Pair>(id, seed)
+	}
+
+	fn ecdsa_public_keys(&self, key_type: KeyTypeId) -> Vec<ecdsa::Public> {
+		self.raw_public_keys(key_type).into_iter()
+			.filter_map(|k| ecdsa::Public::try_from(&k[..]).ok())
+			.collect()
+	}
+
+	fn ecdsa_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ecdsa::Public, CryptoStoreError> {
+		self.generate::<ecdsa::Pair>(id, seed)
+	}
+
+	fn insert_unknown(&self, key_type: KeyTypeId, suri: &str, public: &[u8]) -> Result<(), ()> {
+		self.store_secret(key_type, public, suri).map_err(|_| ())
+	}
+
+	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		public_keys.iter().all(|(public, key_type)| {
+			self.ephemeral.read().contains_key(&(*key_type, public.clone()))
+				|| self.path.join(Self::file_name(*key_type, public)).exists()
+		})
+	}
+
+	fn sr25519_vrf_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &Sr25519Public,
+		transcript_data: VRFTranscriptData,
+	) -> Result<VRFSignature, CryptoStoreError> {
+		let transcript = make_transcript(transcript_data);
+		let pair = self.key_pair::<sr25519::Pair>(key_type, public)?
+			.ok_or_else(|| CryptoStoreError::PairNotFound("Not found".to_owned()))?;
+		let (inout, proof, _) = pair.as_ref().vrf_sign(transcript);
+		Ok(VRFSignature {
+			output: inout.to_output(),
+			proof,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const TEST_TK: KeyTypeId = KeyTypeId(*b"test");
+
+	fn temp_dir(name: &str) -> PathBuf {
+		let path = std::env::temp_dir()
+			.join(format!("tssrs-encrypted-{}-{}", name, std::process::id()));
+		let _ = fs::remove_dir_all(&path);
+		path
+	}
+
+	#[test]
+	fn keys_survive_reopening_and_are_not_plaintext() {
+		let path = temp_dir("reopen");
+		let keystore = EncryptedKeystore::open(&path, "secret").unwrap();
+		let public = SyncCryptoStore::ed25519_generate_new(&keystore, TEST_TK, None).unwrap();
+		let key = CryptoTypePublicPair(ed25519::CRYPTO_ID, public.0.to_vec());
+		drop(keystore);
+
+		let content = fs::read(path.join(EncryptedKeystore::file_name(TEST_TK, &public.0))).unwrap();
+		assert!(std::str::from_utf8(&content).is_err());
+
+		assert!(EncryptedKeystore::open(&path, "wrong").is_err());
+		let keystore = EncryptedKeystore::open(&path, "secret").unwrap();
+		assert_eq!(SyncCryptoStore::ed25519_public_keys(&keystore, TEST_TK), vec![public]);
+		assert!(SyncCryptoStore::sign_with(&keystore, TEST_TK, &key, b"msg").is_ok());
+
+		let _ = fs::remove_dir_all(&path);
+	}
+
+	#[test]
+	fn changing_the_passphrase_keeps_the_keys() {
+		let path = temp_dir("rotate");
+		let keystore = EncryptedKeystore::open(&path, "old").unwrap();
+		let public = SyncCryptoStore::sr25519_generate_new(&keystore, TEST_TK, None).unwrap();
+		keystore.change_passphrase("new").unwrap();
+		drop(keystore);
+
+		assert!(EncryptedKeystore::open(&path, "old").is_err());
+		let keystore = EncryptedKeystore::open(&path, "new").unwrap();
+		let key = CryptoTypePublicPair(sr25519::CRYPTO_ID, public.0.to_vec());
+		assert!(SyncCryptoStore::sign_with(&keystore, TEST_TK, &key, b"msg").is_ok());
+
+		let _ = fs::remove_dir_all(&path);
+	}
+
+	#[test]
+	fn tampered_key_files_are_rejected() {
+		let path = temp_dir("tamper");
+		let keystore = EncryptedKeystore::open(&path, "secret").unwrap();
+		let public = SyncCryptoStore::ed25519_generate_new(&keystore, TEST_TK, None).unwrap();
+		let file = path.join(EncryptedKeystore::file_name(TEST_TK, &public.0));
+		let mut content = fs::read(&file).unwrap();
+		*content.last_mut().unwrap() ^= 1;
+		fs::write(&file, content).unwrap();
+
+		let key = CryptoTypePublicPair(ed25519::CRYPTO_ID, public.0.to_vec());
+		assert!(SyncCryptoStore::sign_with(&keystore, TEST_TK, &key, b"msg").is_err());
+
+		let _ = fs::remove_dir_all(&path);
+	}
+}
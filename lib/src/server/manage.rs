@@ -0,0 +1,305 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+/// Managing session keys: atomic generation, removal and rotation.
+///
+/// `CryptoStore` has no notion of deleting a key, so the server needs a
+/// [`KeyArchive`] next to the store that knows how to take a key out of
+/// service. Retired secrets are never destroyed but moved aside.
+
+use std::{
+	path::PathBuf,
+	time::{SystemTime, UNIX_EPOCH},
+};
+use async_trait::async_trait;
+use sp_core::{
+	crypto::{CryptoTypeId, KeyTypeId},
+	hexdisplay::HexDisplay,
+};
+use sp_keystore::{CryptoStore, Error as CryptoStoreError};
+use sp_application_crypto::{ed25519, sr25519, ecdsa};
+
+/// Takes keys out of service.
+#[async_trait]
+pub trait KeyArchive: Send + Sync {
+	/// Retire the secret of `public`, so the keystore no longer offers it.
+	async fn archive(&self, key_type: KeyTypeId, public: &[u8]) -> Result<(), CryptoStoreError>;
+}
+
+/// Archive for `LocalKeystore` directories.
+///
+/// Retired key files are moved into an `archive` folder within the keystore
+/// directory, suffixed with the time they were retired.
+pub struct FsKeyArchive {
+	paths: Vec<PathBuf>,
+}
+
+impl FsKeyArchive {
+	/// Archive keys of the keystores at the given `paths`.
+	pub fn new(paths: Vec<PathBuf>) -> Self {
+		FsKeyArchive { paths }
+	}
+}
+
+#[async_trait]
+impl KeyArchive for FsKeyArchive {
+	async fn archive(&self, key_type: KeyTypeId, public: &[u8]) -> Result<(), CryptoStoreError> {
+		let name = format!("{}{}", HexDisplay::from(&key_type.0), HexDisplay::from(&public));
+		let retired_at = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.map(|d| d.as_secs())
+			.unwrap_or_default();
+
+		for path in &self.paths {
+			let file = path.join(&name);
+			if !file.exists() {
+				continue
+			}
+			let archive = path.join("archive");
+			return std::fs::create_dir_all(&archive)
+				.and_then(|_| std::fs::rename(&file, archive.join(format!("{}.{}", name, retired_at))))
+				.map_err(|e| CryptoStoreError::Other(format!("Archiving {} failed: {}", name, e)))
+		}
+		Err(CryptoStoreError::PairNotFound(name))
+	}
+}
+
+/// The crypto scheme of the well-known session key types.
+pub fn session_key_scheme(key_type: KeyTypeId) -> Option<CryptoTypeId> {
+	match &key_type.0 {
+		b"babe" | b"aura" | b"imon" | b"audi" | b"para" | b"asgn" => Some(sr25519::CRYPTO_ID),
+		b"gran" => Some(ed25519::CRYPTO_ID),
+		b"beef" => Some(ecdsa::CRYPTO_ID),
+		_ => None,
+	}
+}
+
+async fn public_keys<Store: CryptoStore>(
+	store: &Store,
+	key_type: KeyTypeId,
+	scheme: CryptoTypeId,
+) -> Vec<Vec<u8>> {
+	match scheme {
+		sr25519::CRYPTO_ID => store.sr25519_public_keys(key_type).await
+			.into_iter().map(|k| k.0.to_vec()).collect(),
+		ed25519::CRYPTO_ID => store.ed25519_public_keys(key_type).await
+			.into_iter().map(|k| k.0.to_vec()).collect(),
+		ecdsa::CRYPTO_ID => store.ecdsa_public_keys(key_type).await
+			.into_iter().map(|k| k.0.to_vec()).collect(),
+		_ => vec![],
+	}
+}
+
+async fn generate<Store: CryptoStore>(
+	store: &Store,
+	key_type: KeyTypeId,
+	scheme: CryptoTypeId,
+) -> Result<Vec<u8>, CryptoStoreError> {
+	match scheme {
+		sr25519::CRYPTO_ID => store.sr25519_generate_new(key_type, None).await.map(|k| k.0.to_vec()),
+		ed25519::CRYPTO_ID => store.ed25519_generate_new(key_type, None).await.map(|k| k.0.to_vec()),
+		ecdsa::CRYPTO_ID => store.ecdsa_generate_new(key_type, None).await.map(|k| k.0.to_vec()),
+		_ => Err(CryptoStoreError::KeyNotSupported(key_type)),
+	}
+}
+
+/// Generate a fresh key for each `(key type, scheme)`, all or nothing.
+///
+/// If any generation fails, the keys generated so far are retired into
+/// `archive` again. Without an archive there is no way back, so more than a
+/// single key is refused with `Unavailable`. Returns the SCALE encoded
+/// session keys, i.e. the concatenated public keys in the order given, as
+/// expected by `session.setKeys`.
+pub async fn generate_session_keys<Store: CryptoStore>(
+	store: &Store,
+	archive: Option<&dyn KeyArchive>,
+	keys: Vec<(KeyTypeId, CryptoTypeId)>,
+) -> Result<Vec<u8>, CryptoStoreError> {
+	if let Some((key_type, _)) = keys.iter().find(|(_, scheme)| ![
+		sr25519::CRYPTO_ID, ed25519::CRYPTO_ID, ecdsa::CRYPTO_ID,
+	].contains(scheme)) {
+		return Err(CryptoStoreError::KeyNotSupported(*key_type))
+	}
+	if archive.is_none() && keys.len() > 1 {
+		return Err(CryptoStoreError::Unavailable)
+	}
+
+	let mut generated: Vec<(KeyTypeId, Vec<u8>)> = vec![];
+	for (key_type, scheme) in keys {
+		match generate(store, key_type, scheme).await {
+			Ok(public) => generated.push((key_type, public)),
+			Err(e) => {
+				// nothing was generated yet if there is no archive
+				if let Some(archive) = archive {
+					for (key_type, public) in &generated {
+						if let Err(e) = archive.archive(*key_type, public).await {
+							log::error!{
+								target: "remote_signer",
+								"Rolling back new {:?} key failed: {:}", key_type, e
+							};
+						}
+					}
+				}
+				return Err(e)
+			},
+		}
+	}
+
+	Ok(generated.into_iter().flat_map(|(_, public)| public).collect())
+}
+
+/// Generate fresh keys for `key_types`, then archive all previous keys of them.
+///
+/// Returns the SCALE encoded session keys like [`generate_session_keys`].
+pub async fn rotate_keys<Store: CryptoStore>(
+	store: &Store,
+	archive: &dyn KeyArchive,
+	key_types: Vec<KeyTypeId>,
+) -> Result<Vec<u8>, CryptoStoreError> {
+	let keys = key_types.into_iter()
+		.map(|k| session_key_scheme(k).map(|s| (k, s)).ok_or(CryptoStoreError::KeyNotSupported(k)))
+		.collect::<Result<Vec<_>, _>>()?;
+
+	let mut retired = vec![];
+	for (key_type, scheme) in &keys {
+		for public in public_keys(store, *key_type, *scheme).await {
+			retired.push((*key_type, public));
+		}
+	}
+
+	let session_keys = generate_session_keys(store, Some(archive), keys).await?;
+
+	for (key_type, public) in retired {
+		if let Err(e) = archive.archive(key_type, &public).await {
+			log::warn!(target: "remote_signer", "Could not retire {:?} key: {:}", key_type, e);
+		}
+	}
+
+	Ok(session_keys)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::executor::block_on;
+	use sc_keystore::LocalKeystore;
+	use sp_core::crypto::CryptoTypePublicPair;
+	use sp_keystore::vrf::{VRFTranscriptData, VRFSignature};
+
+	const AURA: KeyTypeId = KeyTypeId(*b"aura");
+	const GRAN: KeyTypeId = KeyTypeId(*b"gran");
+
+	/// A `LocalKeystore` that fails generating ed25519 keys.
+	struct NoEd25519(LocalKeystore);
+
+	#[async_trait]
+	impl CryptoStore for NoEd25519 {
+		async fn sr25519_public_keys(&self, id: KeyTypeId) -> Vec<sr25519::Public> {
+			self.0.sr25519_public_keys(id).await
+		}
+		async fn sr25519_generate_new(&self, id: KeyTypeId, seed: Option<&str>)
+			-> Result<sr25519::Public, CryptoStoreError>
+		{
+			self.0.sr25519_generate_new(id, seed).await
+		}
+		async fn ed25519_public_keys(&self, id: KeyTypeId) -> Vec<ed25519::Public> {
+			self.0.ed25519_public_keys(id).await
+		}
+		async fn ed25519_generate_new(&self, _: KeyTypeId, _: Option<&str>)
+			-> Result<ed25519::Public, CryptoStoreError>
+		{
+			Err(CryptoStoreError::Other("Out of entropy".into()))
+		}
+		async fn ecdsa_public_keys(&self, id: KeyTypeId) -> Vec<ecdsa::Public> {
+			self.0.ecdsa_public_keys(id).await
+		}
+		async fn ecdsa_generate_new(&self, id: KeyTypeId, seed: Option<&str>)
+			-> Result<ecdsa::Public, CryptoStoreError>
+		{
+			self.0.ecdsa_generate_new(id, seed).await
+		}
+		async fn insert_unknown(&self, id: KeyTypeId, suri: &str, public: &[u8]) -> Result<(), ()> {
+			self.0.insert_unknown(id, suri, public).await
+		}
+		async fn supported_keys(&self, id: KeyTypeId, keys: Vec<CryptoTypePublicPair>)
+			-> Result<Vec<CryptoTypePublicPair>, CryptoStoreError>
+		{
+			self.0.supported_keys(id, keys).await
+		}
+		async fn keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, CryptoStoreError> {
+			CryptoStore::keys(&self.0, id).await
+		}
+		async fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+			self.0.has_keys(public_keys).await
+		}
+		async fn sign_with(&self, id: KeyTypeId, key: &CryptoTypePublicPair, msg: &[u8])
+			-> Result<Vec<u8>, CryptoStoreError>
+		{
+			self.0.sign_with(id, key, msg).await
+		}
+		async fn sr25519_vrf_sign(
+			&self,
+			id: KeyTypeId,
+			public: &sr25519::Public,
+			transcript_data: VRFTranscriptData,
+		) -> Result<VRFSignature, CryptoStoreError> {
+			self.0.sr25519_vrf_sign(id, public, transcript_data).await
+		}
+	}
+
+	fn temp_dir(name: &str) -> PathBuf {
+		let path = std::env::temp_dir()
+			.join(format!("tssrs-manage-{}-{}", name, std::process::id()));
+		let _ = std::fs::remove_dir_all(&path);
+		path
+	}
+
+	#[test]
+	fn rolls_back_when_a_later_generation_fails() {
+		let path = temp_dir("rollback");
+		let store = NoEd25519(LocalKeystore::open(&path, None).unwrap());
+		let archive = FsKeyArchive::new(vec![path.clone()]);
+
+		let result = block_on(generate_session_keys(
+			&store,
+			Some(&archive),
+			vec![(AURA, sr25519::CRYPTO_ID), (GRAN, ed25519::CRYPTO_ID)],
+		));
+		assert!(result.is_err());
+		assert!(block_on(store.sr25519_public_keys(AURA)).is_empty());
+		assert_eq!(path.join("archive").read_dir().unwrap().count(), 1);
+
+		let _ = std::fs::remove_dir_all(&path);
+	}
+
+	#[test]
+	fn refuses_several_keys_without_archive() {
+		let store = NoEd25519(LocalKeystore::in_memory());
+
+		let result = block_on(generate_session_keys(
+			&store,
+			None,
+			vec![(AURA, sr25519::CRYPTO_ID), (GRAN, ed25519::CRYPTO_ID)],
+		));
+		assert!(matches!(result, Err(CryptoStoreError::Unavailable)));
+		assert!(block_on(store.sr25519_public_keys(AURA)).is_empty());
+
+		let session_keys = block_on(generate_session_keys(&store, None, vec![(AURA, sr25519::CRYPTO_ID)]));
+		assert_eq!(session_keys.unwrap().len(), 32);
+	}
+}
@@ -0,0 +1,532 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+/// Keystore backed by a PKCS#11 token, e.g. an HSM.
+///
+/// Only ed25519 (`CKM_EDDSA`) and ecdsa over secp256k1 (`CKM_ECDSA`) keys are
+/// supported, the secrets never leave the token. Keys are generated on the
+/// token, labeled with their key type and identified by their public key.
+/// Tokens know neither sr25519 nor VRFs, nor can secret URIs be imported:
+/// these calls fail with an "unsupported" error.
+
+use std::{convert::TryFrom, path::Path, ptr};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use ::pkcs11::{
+	Ctx,
+	types::*,
+};
+use sp_core::{
+	blake2_256,
+	ecdsa::{Public as EcdsaPublic, Signature as EcdsaSignature},
+	crypto::{CryptoTypePublicPair, KeyTypeId},
+	hexdisplay::HexDisplay,
+	sr25519::Public as Sr25519Public,
+};
+use sp_keystore::{
+	CryptoStore, Error as CryptoStoreError, SyncCryptoStore,
+	vrf::{VRFTranscriptData, VRFSignature},
+};
+use sp_application_crypto::{ed25519, sr25519, ecdsa};
+
+// PKCS#11 v3.0 EdDSA constants, not yet known to the `pkcs11` crate.
+const CKK_EC_EDWARDS: CK_KEY_TYPE = 0x40;
+const CKM_EC_EDWARDS_KEY_PAIR_GEN: CK_MECHANISM_TYPE = 0x1055;
+const CKM_EDDSA: CK_MECHANISM_TYPE = 0x1057;
+
+/// DER encoded OID of edwards25519 (1.3.101.112)
+const ED25519_PARAMS: &[u8] = &[0x06, 0x03, 0x2b, 0x65, 0x70];
+/// DER encoded OID of secp256k1 (1.3.132.0.10)
+const SECP256K1_PARAMS: &[u8] = &[0x06, 0x05, 0x2b, 0x81, 0x04, 0x00, 0x0a];
+
+/// Order of the secp256k1 group
+const SECP256K1_N: [u8; 32] = [
+	0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+	0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+
+fn unsupported(what: &str) -> CryptoStoreError {
+	CryptoStoreError::Other(format!("{:} is unsupported by the PKCS#11 backend", what))
+}
+
+fn token_error(e: ::pkcs11::errors::Error) -> CryptoStoreError {
+	CryptoStoreError::Other(format!("PKCS#11 token: {:}", e))
+}
+
+/// Unwrap the DER `OCTET STRING` some tokens put around `CKA_EC_POINT`.
+fn ec_point(raw: &[u8], len: usize) -> Option<&[u8]> {
+	match raw {
+		[0x04, l, point @ ..] if *l as usize == point.len() && point.len() == len => Some(point),
+		point if point.len() == len => Some(point),
+		_ => None,
+	}
+}
+
+/// Compress an uncompressed `04 || x || y` secp256k1 point.
+fn compress(point: &[u8]) -> Option<[u8; 33]> {
+	if point.len() != 65 || point[0] != 0x04 {
+		return None
+	}
+	let mut compressed = [0u8; 33];
+	compressed[0] = 0x02 | (point[64] & 1);
+	compressed[1..].copy_from_slice(&point[1..33]);
+	Some(compressed)
+}
+
+/// Turn a high `s` into `n - s`, as required by Substrate's ecdsa verification.
+fn normalize_s(s: &mut [u8]) {
+	let mut half = [0u8; 32];
+	let mut carry = 0u8;
+	for (h, n) in half.iter_mut().zip(SECP256K1_N.iter()) {
+		*h = (carry << 7) | (n >> 1);
+		carry = n & 1;
+	}
+	if s[..] <= half[..] {
+		return
+	}
+	let mut borrow = 0i16;
+	for i in (0..32).rev() {
+		let diff = SECP256K1_N[i] as i16 - s[i] as i16 - borrow;
+		borrow = if diff < 0 { 1 } else { 0 };
+		s[i] = (diff + (borrow << 8)) as u8;
+	}
+}
+
+/// Connection to a logged in PKCS#11 session.
+struct Session {
+	ctx: Ctx,
+	handle: CK_SESSION_HANDLE,
+}
+
+/// A keystore whose keys live on a PKCS#11 token.
+pub struct Pkcs11Keystore {
+	session: Mutex<Session>,
+}
+
+// The raw function pointers of `Ctx` aren't `Send`, but every call into the
+// library goes through the mutex, so the token is never used concurrently.
+unsafe impl Send for Pkcs11Keystore {}
+unsafe impl Sync for Pkcs11Keystore {}
+
+impl Pkcs11Keystore {
+	/// Load the PKCS#11 `module` and log into the token in `slot` with `pin`.
+	pub fn open<P: AsRef<Path>>(module: P, slot: CK_SLOT_ID, pin: &str) -> Result<Self, String> {
+		let ctx = Ctx::new_and_initialize(module.as_ref())
+			.map_err(|e| format!("Loading {:} failed: {:}", module.as_ref().display(), e))?;
+		let handle = ctx.open_session(slot, CKF_SERIAL_SESSION | CKF_RW_SESSION, None, None)
+			.map_err(|e| format!("Opening a session on slot {:} failed: {:}", slot, e))?;
+		ctx.login(handle, CKU_USER, Some(pin))
+			.map_err(|e| format!("Login to slot {:} failed: {:}", slot, e))?;
+		Ok(Pkcs11Keystore { session: Mutex::new(Session { ctx, handle }) })
+	}
+
+	fn label(key_type: KeyTypeId) -> String {
+		format!("tssrs-{}", HexDisplay::from(&key_type.0))
+	}
+
+	/// Objects of `class` and `key_type` with the given `label` and optional `id`.
+	fn find(
+		session: &Session,
+		class: CK_OBJECT_CLASS,
+		key_type: CK_KEY_TYPE,
+		label: &str,
+		id: Option<&[u8]>,
+	) -> Result<Vec<CK_OBJECT_HANDLE>, CryptoStoreError> {
+		let mut template = vec![
+			CK_ATTRIBUTE::new(CKA_CLASS).with_ck_ulong(&class),
+			CK_ATTRIBUTE::new(CKA_KEY_TYPE).with_ck_ulong(&key_type),
+			CK_ATTRIBUTE::new(CKA_LABEL).with_bytes(label.as_bytes()),
+		];
+		if let Some(id) = id {
+			template.push(CK_ATTRIBUTE::new(CKA_ID).with_bytes(id));
+		}
+		session.ctx.find_objects_init(session.handle, &template).map_err(token_error)?;
+		let found = session.ctx.find_objects(session.handle, 1024);
+		session.ctx.find_objects_final(session.handle).map_err(token_error)?;
+		found.map_err(token_error)
+	}
+
+	fn attribute(
+		session: &Session,
+		object: CK_OBJECT_HANDLE,
+		attribute: CK_ATTRIBUTE_TYPE,
+	) -> Result<Vec<u8>, CryptoStoreError> {
+		let mut template = vec![CK_ATTRIBUTE::new(attribute)];
+		session.ctx.get_attribute_value(session.handle, object, &mut template).map_err(token_error)?;
+		let value = vec![0u8; template[0].ulValueLen as usize];
+		let mut template = vec![CK_ATTRIBUTE::new(attribute).with_bytes(&value)];
+		session.ctx.get_attribute_value(session.handle, object, &mut template).map_err(token_error)?;
+		Ok(value)
+	}
+
+	/// Raw public keys on the token of the given scheme and key type.
+	fn public_keys(
+		&self,
+		key_type: KeyTypeId,
+		pkcs11_key_type: CK_KEY_TYPE,
+	) -> Result<Vec<Vec<u8>>, CryptoStoreError> {
+		let session = self.session.lock();
+		Self::find(&session, CKO_PUBLIC_KEY, pkcs11_key_type, &Self::label(key_type), None)?
+			.into_iter()
+			.map(|object| Self::attribute(&session, object, CKA_ID))
+			.collect()
+	}
+
+	fn private_key(
+		&self,
+		session: &Session,
+		key_type: KeyTypeId,
+		pkcs11_key_type: CK_KEY_TYPE,
+		public: &[u8],
+	) -> Result<Option<CK_OBJECT_HANDLE>, CryptoStoreError> {
+		Self::find(session, CKO_PRIVATE_KEY, pkcs11_key_type, &Self::label(key_type), Some(public))
+			.map(|found| found.into_iter().next())
+	}
+
+	/// Generate a key pair on the token and tag both halves with the public key.
+	fn generate(
+		&self,
+		key_type: KeyTypeId,
+		pkcs11_key_type: CK_KEY_TYPE,
+		mechanism: CK_MECHANISM_TYPE,
+		params: &[u8],
+	) -> Result<Vec<u8>, CryptoStoreError> {
+		let session = self.session.lock();
+		let label = Self::label(key_type);
+		let mechanism = CK_MECHANISM { mechanism, pParameter: ptr::null_mut(), ulParameterLen: 0 };
+		let public_template = vec![
+			CK_ATTRIBUTE::new(CKA_TOKEN).with_bool(&CK_TRUE),
+			CK_ATTRIBUTE::new(CKA_VERIFY).with_bool(&CK_TRUE),
+			CK_ATTRIBUTE::new(CKA_EC_PARAMS).with_bytes(params),
+			CK_ATTRIBUTE::new(CKA_LABEL).with_bytes(label.as_bytes()),
+		];
+		let private_template = vec![
+			CK_ATTRIBUTE::new(CKA_TOKEN).with_bool(&CK_TRUE),
+			CK_ATTRIBUTE::new(CKA_PRIVATE).with_bool(&CK_TRUE),
+			CK_ATTRIBUTE::new(CKA_SENSITIVE).with_bool(&CK_TRUE),
+			CK_ATTRIBUTE::new(CKA_EXTRACTABLE).with_bool(&CK_FALSE),
+			CK_ATTRIBUTE::new(CKA_SIGN).with_bool(&CK_TRUE),
+			CK_ATTRIBUTE::new(CKA_LABEL).with_bytes(label.as_bytes()),
+		];
+		let (public_key, private_key) = session.ctx
+			.generate_key_pair(session.handle, &mechanism, &public_template, &private_template)
+			.map_err(token_error)?;
+
+		let point = Self::attribute(&session, public_key, CKA_EC_POINT)?;
+		let public = match pkcs11_key_type {
+			CKK_EC_EDWARDS => ec_point(&point, 32).map(|p| p.to_vec()),
+			_ => ec_point(&point, 65).and_then(compress).map(|p| p.to_vec()),
+		}.ok_or_else(|| CryptoStoreError::Other("Token returned an unexpected public key".into()))?;
+
+		let id = vec![CK_ATTRIBUTE::new(CKA_ID).with_bytes(&public)];
+		for object in &[public_key, private_key] {
+			session.ctx.set_attribute_value(session.handle, *object, &id).map_err(token_error)?;
+		}
+		Ok(public)
+	}
+
+	fn sign(
+		&self,
+		key_type: KeyTypeId,
+		pkcs11_key_type: CK_KEY_TYPE,
+		mechanism: CK_MECHANISM_TYPE,
+		public: &[u8],
+		data: &[u8],
+	) -> Result<Vec<u8>, CryptoStoreError> {
+		let session = self.session.lock();
+		let key = self.private_key(&session, key_type, pkcs11_key_type, public)?
+			.ok_or_else(|| CryptoStoreError::PairNotFound(format!("{:}", HexDisplay::from(&public))))?;
+		let mechanism = CK_MECHANISM { mechanism, pParameter: ptr::null_mut(), ulParameterLen: 0 };
+		session.ctx.sign_init(session.handle, &mechanism, key).map_err(token_error)?;
+		session.ctx.sign(session.handle, data).map_err(token_error)
+	}
+
+	/// Sign the blake2 hash of `msg` and add the recovery id Substrate expects.
+	fn sign_ecdsa(&self, key_type: KeyTypeId, public: &[u8], msg: &[u8]) -> Result<Vec<u8>, CryptoStoreError> {
+		let mut rs = self.sign(key_type, CKK_EC, CKM_ECDSA, public, &blake2_256(msg))?;
+		if rs.len() != 64 {
+			return Err(CryptoStoreError::Other("Token returned an unexpected signature".into()))
+		}
+		normalize_s(&mut rs[32..]);
+
+		let expected = EcdsaPublic::try_from(public).map_err(|_| CryptoStoreError::ValidationError("Invalid public key".into()))?;
+		let mut signature = [0u8; 65];
+		signature[..64].copy_from_slice(&rs);
+		for recovery_id in 0..4 {
+			signature[64] = recovery_id;
+			if EcdsaSignature::from_raw(signature).recover(msg).as_ref() == Some(&expected) {
+				return Ok(signature.to_vec())
+			}
+		}
+		Err(CryptoStoreError::Other("Token signature doesn't match the public key".into()))
+	}
+}
+
+#[async_trait]
+impl CryptoStore for Pkcs11Keystore {
+	async fn keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, CryptoStoreError> {
+		SyncCryptoStore::keys(self, id)
+	}
+
+	async fn sr25519_public_keys(&self, id: KeyTypeId) -> Vec<sr25519::Public> {
+		SyncCryptoStore::sr25519_public_keys(self, id)
+	}
+
+	async fn sr25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<sr25519::Public, CryptoStoreError> {
+		SyncCryptoStore::sr25519_generate_new(self, id, seed)
+	}
+
+	async fn ed25519_public_keys(&self, id: KeyTypeId) -> Vec<ed25519::Public> {
+		SyncCryptoStore::ed25519_public_keys(self, id)
+	}
+
+	async fn ed25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ed25519::Public, CryptoStoreError> {
+		SyncCryptoStore::ed25519_generate_new(self, id, seed)
+	}
+
+	async fn ecdsa_public_keys(&self, id: KeyTypeId) -> Vec<ecdsa::Public> {
+		SyncCryptoStore::ecdsa_public_keys(self, id)
+	}
+
+	async fn ecdsa_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ecdsa::Public, CryptoStoreError> {
+		SyncCryptoStore::ecdsa_generate_new(self, id, seed)
+	}
+
+	async fn insert_unknown(&self, id: KeyTypeId, suri: &str, public: &[u8]) -> Result<(), ()> {
+		SyncCryptoStore::insert_unknown(self, id, suri, public)
+	}
+
+	async fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		SyncCryptoStore::has_keys(self, public_keys)
+	}
+
+	async fn supported_keys(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+	) -> Result<Vec<CryptoTypePublicPair>, CryptoStoreError> {
+		SyncCryptoStore::supported_keys(self, id, keys)
+	}
+
+	async fn sign_with(
+		&self,
+		id: KeyTypeId,
+		key: &CryptoTypePublicPair,
+		msg: &[u8],
+	) -> Result<Vec<u8>, CryptoStoreError> {
+		SyncCryptoStore::sign_with(self, id, key, msg)
+	}
+
+	async fn sr25519_vrf_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &Sr25519Public,
+		transcript_data: VRFTranscriptData,
+	) -> Result<VRFSignature, CryptoStoreError> {
+		SyncCryptoStore::sr25519_vrf_sign(self, key_type, public, transcript_data)
+	}
+}
+
+impl SyncCryptoStore for Pkcs11Keystore {
+	fn keys(&self, id: KeyTypeId) -> Result<Vec<CryptoTypePublicPair>, CryptoStoreError> {
+		let ed = self.public_keys(id, CKK_EC_EDWARDS)?
+			.into_iter()
+			.map(|k| CryptoTypePublicPair(ed25519::CRYPTO_ID, k));
+		let ec = self.public_keys(id, CKK_EC)?
+			.into_iter()
+			.map(|k| CryptoTypePublicPair(ecdsa::CRYPTO_ID, k));
+		Ok(ed.chain(ec).collect())
+	}
+
+	fn supported_keys(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+	) -> Result<Vec<CryptoTypePublicPair>, CryptoStoreError> {
+		let all_keys = SyncCryptoStore::keys(self, id)?;
+		Ok(keys.into_iter().filter(|key| all_keys.contains(key)).collect())
+	}
+
+	fn sign_with(
+		&self,
+		id: KeyTypeId,
+		key: &CryptoTypePublicPair,
+		msg: &[u8],
+	) -> Result<Vec<u8>, CryptoStoreError> {
+		match key.0 {
+			ed25519::CRYPTO_ID => self.sign(id, CKK_EC_EDWARDS, CKM_EDDSA, &key.1, msg),
+			ecdsa::CRYPTO_ID => self.sign_ecdsa(id, &key.1, msg),
+			sr25519::CRYPTO_ID => Err(unsupported("sr25519")),
+			_ => Err(CryptoStoreError::KeyNotSupported(id)),
+		}
+	}
+
+	fn sr25519_public_keys(&self, _key_type: KeyTypeId) -> Vec<sr25519::Public> {
+		vec![]
+	}
+
+	fn sr25519_generate_new(
+		&self,
+		_id: KeyTypeId,
+		_seed: Option<&str>,
+	) -> Result<sr25519::Public, CryptoStoreError> {
+		Err(unsupported("sr25519"))
+	}
+
+	fn ed25519_public_keys(&self, key_type: KeyTypeId) -> Vec<ed25519::Public> {
+		self.public_keys(key_type, CKK_EC_EDWARDS)
+			.unwrap_or_default()
+			.into_iter()
+			.filter_map(|k| ed25519::Public::try_from(&k[..]).ok())
+			.collect()
+	}
+
+	fn ed25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ed25519::Public, CryptoStoreError> {
+		if seed.is_some() {
+			return Err(unsupported("Importing a seed"))
+		}
+		let public = self.generate(id, CKK_EC_EDWARDS, CKM_EC_EDWARDS_KEY_PAIR_GEN, ED25519_PARAMS)?;
+		ed25519::Public::try_from(&public[..]).map_err(|_| CryptoStoreError::ValidationError("Invalid public key".into()))
+	}
+
+	fn ecdsa_public_keys(&self, key_type: KeyTypeId) -> Vec<ecdsa::Public> {
+		self.public_keys(key_type, CKK_EC)
+			.unwrap_or_default()
+			.into_iter()
+			.filter_map(|k| ecdsa::Public::try_from(&k[..]).ok())
+			.collect()
+	}
+
+	fn ecdsa_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<ecdsa::Public, CryptoStoreError> {
+		if seed.is_some() {
+			return Err(unsupported("Importing a seed"))
+		}
+		let public = self.generate(id, CKK_EC, CKM_EC_KEY_PAIR_GEN, SECP256K1_PARAMS)?;
+		ecdsa::Public::try_from(&public[..]).map_err(|_| CryptoStoreError::ValidationError("Invalid public key".into()))
+	}
+
+	fn insert_unknown(&self, _key_type: KeyTypeId, _suri: &str, _public: &[u8]) -> Result<(), ()> {
+		log::warn!(target: "remote_signer", "Importing keys is unsupported by the PKCS#11 backend");
+		Err(())
+	}
+
+	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		let session = self.session.lock();
+		public_keys.iter().all(|(public, key_type)| {
+			[CKK_EC_EDWARDS, CKK_EC].iter().any(|pkcs11_key_type| {
+				matches!(self.private_key(&session, *key_type, *pkcs11_key_type, public), Ok(Some(_)))
+			})
+		})
+	}
+
+	fn sr25519_vrf_sign(
+		&self,
+		_key_type: KeyTypeId,
+		_public: &Sr25519Public,
+		_transcript_data: VRFTranscriptData,
+	) -> Result<VRFSignature, CryptoStoreError> {
+		Err(unsupported("VRF signing"))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::Pair;
+
+	const TEST_TK: KeyTypeId = KeyTypeId(*b"test");
+
+	#[test]
+	fn normalizes_high_s() {
+		let mut s = SECP256K1_N;
+		s[31] -= 1;
+		normalize_s(&mut s);
+		let mut one = [0u8; 32];
+		one[31] = 1;
+		assert_eq!(s, one);
+
+		let mut low = one;
+		normalize_s(&mut low);
+		assert_eq!(low, one);
+	}
+
+	/// Needs a SoftHSM token, see the README:
+	/// `PKCS11_MODULE=/usr/lib/softhsm/libsofthsm2.so PKCS11_SLOT=... PKCS11_PIN=1234`
+	fn softhsm() -> Pkcs11Keystore {
+		let module = std::env::var("PKCS11_MODULE").expect("PKCS11_MODULE is set");
+		let slot = std::env::var("PKCS11_SLOT").expect("PKCS11_SLOT is set")
+			.parse().expect("PKCS11_SLOT is a number");
+		let pin = std::env::var("PKCS11_PIN").expect("PKCS11_PIN is set");
+		Pkcs11Keystore::open(module, slot, &pin).expect("SoftHSM token is available")
+	}
+
+	#[test]
+	#[ignore]
+	fn softhsm_ed25519() {
+		let keystore = softhsm();
+		let public = SyncCryptoStore::ed25519_generate_new(&keystore, TEST_TK, None).unwrap();
+		assert!(SyncCryptoStore::ed25519_public_keys(&keystore, TEST_TK).contains(&public));
+		assert!(SyncCryptoStore::has_keys(&keystore, &[(public.0.to_vec(), TEST_TK)]));
+
+		let key = CryptoTypePublicPair(ed25519::CRYPTO_ID, public.0.to_vec());
+		let signature = SyncCryptoStore::sign_with(&keystore, TEST_TK, &key, b"msg").unwrap();
+		let signature = sp_core::ed25519::Signature::from_slice(&signature);
+		assert!(sp_core::ed25519::Pair::verify(&signature, b"msg", &public));
+	}
+
+	#[test]
+	#[ignore]
+	fn softhsm_ecdsa() {
+		let keystore = softhsm();
+		let public = SyncCryptoStore::ecdsa_generate_new(&keystore, TEST_TK, None).unwrap();
+		assert!(SyncCryptoStore::ecdsa_public_keys(&keystore, TEST_TK).contains(&public));
+
+		let key = CryptoTypePublicPair(ecdsa::CRYPTO_ID, public.0.to_vec());
+		let signature = SyncCryptoStore::sign_with(&keystore, TEST_TK, &key, b"msg").unwrap();
+		let signature = EcdsaSignature::from_slice(&signature);
+		assert!(sp_core::ecdsa::Pair::verify(&signature, b"msg", &public));
+	}
+
+	#[test]
+	#[ignore]
+	fn softhsm_rejects_sr25519() {
+		let keystore = softhsm();
+		assert!(SyncCryptoStore::sr25519_generate_new(&keystore, TEST_TK, None).is_err());
+		assert!(SyncCryptoStore::sr25519_public_keys(&keystore, TEST_TK).is_empty());
+	}
+}
@@ -0,0 +1,325 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+/// Byte types of the v2 JSON wire schema.
+///
+/// Bytes are written as `0x`-prefixed hex strings, like everywhere else in
+/// the Substrate RPC ecosystem. For the transition period from v1 they are
+/// also read from the old JSON array-of-numbers form.
+
+use std::{convert::TryInto, fmt, ops::Deref};
+
+use codec::{Decode, Encode};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use sp_core::{
+	bytes::{from_hex, to_hex},
+	crypto::{CryptoTypeId, CryptoTypePublicPair},
+};
+use sp_keystore::vrf::{VRFSignature, VRFTranscriptValue};
+
+/// Bytes serialized as `0x`-prefixed hex, deserialized from hex or a number array.
+///
+/// SCALE encodes just like `Vec<u8>`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Encode, Decode)]
+pub struct HexBytes(pub Vec<u8>);
+
+impl From<Vec<u8>> for HexBytes {
+	fn from(bytes: Vec<u8>) -> HexBytes {
+		HexBytes(bytes)
+	}
+}
+
+impl From<&[u8]> for HexBytes {
+	fn from(bytes: &[u8]) -> HexBytes {
+		HexBytes(bytes.to_vec())
+	}
+}
+
+impl From<HexBytes> for Vec<u8> {
+	fn from(bytes: HexBytes) -> Vec<u8> {
+		bytes.0
+	}
+}
+
+impl Deref for HexBytes {
+	type Target = [u8];
+
+	fn deref(&self) -> &[u8] {
+		&self.0
+	}
+}
+
+impl Serialize for HexBytes {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(&to_hex(&self.0, false))
+	}
+}
+
+impl<'de> Deserialize<'de> for HexBytes {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		struct Visitor;
+
+		impl<'de> de::Visitor<'de> for Visitor {
+			type Value = HexBytes;
+
+			fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+				write!(formatter, "a 0x-prefixed hex string or an array of bytes")
+			}
+
+			fn visit_str<E: de::Error>(self, v: &str) -> Result<HexBytes, E> {
+				from_hex(v)
+					.map(HexBytes)
+					.map_err(|e| E::custom(format!("Invalid hex value: {:}", e)))
+			}
+
+			fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<HexBytes, A::Error> {
+				let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+				while let Some(byte) = seq.next_element::<u8>()? {
+					bytes.push(byte);
+				}
+				Ok(HexBytes(bytes))
+			}
+		}
+
+		deserializer.deserialize_any(Visitor)
+	}
+}
+
+/// A [`CryptoTypePublicPair`] serialized as `["ed25", "0x…"]`.
+///
+/// Also deserializes from the v1 `[[101, 100, 50, 53], [...]]` form.
+/// SCALE encodes just like `CryptoTypePublicPair`.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct PublicPair(pub CryptoTypePublicPair);
+
+impl From<CryptoTypePublicPair> for PublicPair {
+	fn from(pair: CryptoTypePublicPair) -> PublicPair {
+		PublicPair(pair)
+	}
+}
+
+impl From<PublicPair> for CryptoTypePublicPair {
+	fn from(pair: PublicPair) -> CryptoTypePublicPair {
+		pair.0
+	}
+}
+
+impl Serialize for PublicPair {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		let CryptoTypePublicPair(CryptoTypeId(id), public) = &self.0;
+		let public = HexBytes(public.clone());
+		match std::str::from_utf8(id) {
+			Ok(name) => (name, public).serialize(serializer),
+			Err(_) => (id, public).serialize(serializer),
+		}
+	}
+}
+
+impl<'de> Deserialize<'de> for PublicPair {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		#[derive(Deserialize)]
+		#[serde(untagged)]
+		enum Repr {
+			Named(String, HexBytes),
+			Raw([u8; 4], HexBytes),
+			Legacy(CryptoTypePublicPair),
+		}
+
+		Ok(PublicPair(match Repr::deserialize(deserializer)? {
+			Repr::Named(name, public) => {
+				let id: [u8; 4] = name.as_bytes().try_into()
+					.map_err(|_| de::Error::custom(format!("Invalid crypto type id '{:}'", name)))?;
+				CryptoTypePublicPair(CryptoTypeId(id), public.0)
+			},
+			Repr::Raw(id, public) => CryptoTypePublicPair(CryptoTypeId(id), public.0),
+			Repr::Legacy(pair) => pair,
+		}))
+	}
+}
+
+/// (De)serialize VRF transcript items as `{"Bytes": "0x…"}` or `{"U64": 1}`.
+///
+/// Also deserializes from the v1 `{"Bytes": [1, 2]}` form. For use with
+/// `#[serde(with = "…")]` on a `Vec<VRFTranscriptValue>`.
+pub mod transcript_items {
+	use super::*;
+
+	#[derive(Serialize, Deserialize)]
+	enum Item {
+		Bytes(HexBytes),
+		U64(u64),
+	}
+
+	/// Serialize `items` with hex encoded bytes.
+	pub fn serialize<S: Serializer>(items: &[VRFTranscriptValue], serializer: S) -> Result<S::Ok, S::Error> {
+		items.iter()
+			.map(|item| match item {
+				VRFTranscriptValue::Bytes(b) => Item::Bytes(HexBytes(b.clone())),
+				VRFTranscriptValue::U64(u) => Item::U64(*u),
+			})
+			.collect::<Vec<_>>()
+			.serialize(serializer)
+	}
+
+	/// Deserialize items with hex encoded bytes or v1 number arrays.
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<VRFTranscriptValue>, D::Error> {
+		Ok(Vec::<Item>::deserialize(deserializer)?
+			.into_iter()
+			.map(|item| match item {
+				Item::Bytes(b) => VRFTranscriptValue::Bytes(b.0),
+				Item::U64(u) => VRFTranscriptValue::U64(u),
+			})
+			.collect())
+	}
+}
+
+/// A [`VRFSignature`] serialized as `{"output": "0x…", "proof": "0x…"}`.
+///
+/// Also deserializes from the v1 form with number arrays.
+pub struct VrfSignature(pub VRFSignature);
+
+impl Clone for VrfSignature {
+	fn clone(&self) -> Self {
+		VrfSignature(VRFSignature { output: self.0.output, proof: self.0.proof.clone() })
+	}
+}
+
+impl From<VRFSignature> for VrfSignature {
+	fn from(signature: VRFSignature) -> VrfSignature {
+		VrfSignature(signature)
+	}
+}
+
+impl From<VrfSignature> for VRFSignature {
+	fn from(signature: VrfSignature) -> VRFSignature {
+		signature.0
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+struct VrfSignatureRepr {
+	output: HexBytes,
+	proof: HexBytes,
+}
+
+impl Serialize for VrfSignature {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		VrfSignatureRepr {
+			output: HexBytes(self.0.output.to_bytes().to_vec()),
+			proof: HexBytes(self.0.proof.to_bytes().to_vec()),
+		}.serialize(serializer)
+	}
+}
+
+impl<'de> Deserialize<'de> for VrfSignature {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let repr = VrfSignatureRepr::deserialize(deserializer)?;
+		Ok(VrfSignature(VRFSignature {
+			output: schnorrkel::vrf::VRFOutput::from_bytes(&repr.output)
+				.map_err(|e| de::Error::custom(format!("Invalid VRF output: {:}", e)))?,
+			proof: schnorrkel::vrf::VRFProof::from_bytes(&repr.proof)
+				.map_err(|e| de::Error::custom(format!("Invalid VRF proof: {:}", e)))?,
+		}))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::ed25519;
+
+	#[test]
+	fn hex_bytes_round_trip() {
+		let bytes = HexBytes(vec![0xde, 0xad, 0xbe, 0xef]);
+		let json = serde_json::to_string(&bytes).unwrap();
+		assert_eq!(json, "\"0xdeadbeef\"");
+		assert_eq!(serde_json::from_str::<HexBytes>(&json).unwrap(), bytes);
+	}
+
+	#[test]
+	fn hex_bytes_accepts_number_arrays() {
+		let bytes: HexBytes = serde_json::from_str("[222,173,190,239]").unwrap();
+		assert_eq!(bytes, HexBytes(vec![0xde, 0xad, 0xbe, 0xef]));
+		assert!(serde_json::from_str::<HexBytes>("[256]").is_err());
+		assert!(serde_json::from_str::<HexBytes>("\"0xzz\"").is_err());
+	}
+
+	#[test]
+	fn public_pair_round_trip() {
+		let pair = PublicPair(CryptoTypePublicPair(ed25519::CRYPTO_ID, vec![1, 2, 3]));
+		let json = serde_json::to_string(&pair).unwrap();
+		assert_eq!(json, "[\"ed25\",\"0x010203\"]");
+		assert_eq!(serde_json::from_str::<PublicPair>(&json).unwrap(), pair);
+	}
+
+	#[test]
+	fn public_pair_accepts_v1() {
+		let pair: PublicPair = serde_json::from_str("[[101,100,50,53],[1,2,3]]").unwrap();
+		assert_eq!(pair, PublicPair(CryptoTypePublicPair(ed25519::CRYPTO_ID, vec![1, 2, 3])));
+		assert!(serde_json::from_str::<PublicPair>("[\"ed25519\",\"0x01\"]").is_err());
+	}
+
+	#[test]
+	fn transcript_items_round_trip() {
+		#[derive(Serialize, Deserialize)]
+		struct Items(#[serde(with = "transcript_items")] Vec<VRFTranscriptValue>);
+
+		let json = serde_json::to_string(&Items(vec![
+			VRFTranscriptValue::Bytes(vec![1, 2]),
+			VRFTranscriptValue::U64(3),
+		])).unwrap();
+		assert_eq!(json, "[{\"Bytes\":\"0x0102\"},{\"U64\":3}]");
+
+		for json in &[json.as_str(), "[{\"Bytes\":[1,2]},{\"U64\":3}]"] {
+			let Items(items) = serde_json::from_str(json).unwrap();
+			assert!(matches!(&items[..], [VRFTranscriptValue::Bytes(b), VRFTranscriptValue::U64(3)] if b == &[1, 2]));
+		}
+	}
+
+	#[test]
+	fn vrf_signature_round_trip() {
+		use sp_core::{Pair, sr25519};
+		use sp_keystore::vrf::{make_transcript, VRFTranscriptData};
+
+		let pair = sr25519::Pair::from_string("//Alice", None).unwrap();
+		let (inout, proof, _) = pair.as_ref().vrf_sign(make_transcript(VRFTranscriptData {
+			label: b"test",
+			items: vec![("slot", VRFTranscriptValue::U64(1))],
+		}));
+		let signature = VrfSignature(VRFSignature { output: inout.to_output(), proof });
+
+		let bytes = |s: VrfSignature| (s.0.output.to_bytes().to_vec(), s.0.proof.to_bytes().to_vec());
+
+		let json = serde_json::to_value(&signature).unwrap();
+		assert_eq!(json["output"], to_hex(&signature.0.output.to_bytes(), false));
+		let decoded = serde_json::from_value::<VrfSignature>(json).unwrap();
+		assert_eq!(bytes(decoded), bytes(signature.clone()));
+
+		let (output, proof) = bytes(signature.clone());
+		let v1 = serde_json::json!({ "output": output, "proof": proof });
+		let decoded = serde_json::from_value::<VrfSignature>(v1).unwrap();
+		assert_eq!(bytes(decoded), bytes(signature));
+	}
+
+	#[test]
+	fn scale_encoding_matches_native() {
+		let pair = CryptoTypePublicPair(ed25519::CRYPTO_ID, vec![1, 2, 3]);
+		assert_eq!(PublicPair(pair.clone()).encode(), pair.encode());
+		assert_eq!(HexBytes(vec![1, 2, 3]).encode(), vec![1u8, 2, 3].encode());
+	}
+}
@@ -78,6 +78,16 @@ pub fn development_config() -> Result<ChainSpec, String> {
 }
 
 pub fn local_testnet_config() -> Result<ChainSpec, String> {
+	local_testnet_config_with_authorities(vec![
+		authority_keys_from_seed("Alice"),
+		authority_keys_from_seed("Bob"),
+	])
+}
+
+/// The local testnet, with the given initial PoA authorities.
+pub fn local_testnet_config_with_authorities(
+	initial_authorities: Vec<(AuraId, GrandpaId)>,
+) -> Result<ChainSpec, String> {
 	let wasm_binary = WASM_BINARY.ok_or_else(|| "Development wasm not available".to_string())?;
 
 	Ok(ChainSpec::from_genesis(
@@ -89,10 +99,7 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 		move || testnet_genesis(
 			wasm_binary,
 			// Initial PoA authorities
-			vec![
-				authority_keys_from_seed("Alice"),
-				authority_keys_from_seed("Bob"),
-			],
+			initial_authorities.clone(),
 			// Sudo account
 			get_account_id_from_seed::<sr25519::Public>("Alice"),
 			// Pre-funded accounts
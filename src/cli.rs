@@ -1,5 +1,7 @@
+use std::{fs, path::{Path, PathBuf}, time::Duration};
 use structopt::StructOpt;
 use sc_cli::RunCmd;
+use tssrs::client::{RemoteSignerConfig, TlsConfig};
 
 #[derive(Debug, StructOpt)]
 pub struct Cli {
@@ -8,14 +10,154 @@ pub struct Cli {
 
 	#[structopt(flatten)]
 	pub run: RunCmd,
+
+	/// Refuse to start an authority whose keystore holds no key of the current
+	/// AURA or GRANDPA authority set, instead of only warning about it.
+	#[structopt(long = "require-authority-keys")]
+	pub require_authority_keys: bool,
+
+	#[structopt(flatten)]
+	pub remote_signer: RemoteSignerParams,
+}
+
+/// How to talk to the signer given by `--keystore-uri tssrs+...`.
+///
+/// Shared by the node and the `signer` and `build-spec` subcommands, so the
+/// flags go before the subcommand.
+#[derive(Debug, StructOpt, Clone)]
+pub struct RemoteSignerParams {
+	/// Connection attempts before a call fails, `0` retries forever.
+	#[structopt(long = "remote-signer-retries", value_name = "COUNT", default_value = "10")]
+	pub retries: u8,
+
+	/// Delay after the first failed connection attempt, in milliseconds.
+	/// Doubled with every further attempt.
+	#[structopt(long = "remote-signer-backoff", value_name = "MS", default_value = "500")]
+	pub backoff: u64,
+
+	/// Upper bound of the delay between connection attempts, in milliseconds.
+	#[structopt(long = "remote-signer-max-backoff", value_name = "MS", default_value = "30000")]
+	pub max_backoff: u64,
+
+	/// Time a single call to the signer, or connection attempt, may take, in
+	/// milliseconds. Retrying to connect isn't bounded by it.
+	#[structopt(long = "remote-signer-timeout", value_name = "MS", default_value = "10000")]
+	pub timeout: u64,
+
+	/// File holding a bearer token to authenticate at the signer.
+	#[structopt(long = "remote-signer-auth-token-file", value_name = "PATH", parse(from_os_str))]
+	pub auth_token_file: Option<PathBuf>,
+
+	/// PEM encoded CA certificate to trust for `https` and `wss` signers,
+	/// in addition to the system's.
+	#[structopt(long = "remote-signer-ca-cert", value_name = "PATH", parse(from_os_str))]
+	pub ca_cert: Option<PathBuf>,
+
+	/// PKCS#12 archive with the client certificate and key to present to the signer.
+	#[structopt(long = "remote-signer-client-identity", value_name = "PATH", parse(from_os_str))]
+	pub client_identity: Option<PathBuf>,
+
+	/// File holding the password of the `--remote-signer-client-identity`.
+	#[structopt(
+		long = "remote-signer-client-identity-password-file",
+		value_name = "PATH",
+		parse(from_os_str),
+		requires = "client-identity"
+	)]
+	pub client_identity_password_file: Option<PathBuf>,
+
+	/// Further `tssrs+` signers to fail over to, tried in the given order.
+	#[structopt(long = "remote-signer-failover", value_name = "URI")]
+	pub failover: Vec<String>,
+
+	/// How long to cache the signer's public key listings, in seconds.
+	/// `0` asks the signer every time.
+	#[structopt(long = "remote-signer-cache-ttl", value_name = "SECONDS", default_value = "0")]
+	pub cache_ttl: u64,
+
+	/// Verify every signature and VRF proof of the signer before using it,
+	/// rejecting invalid ones.
+	#[structopt(long = "remote-signer-verify-signatures")]
+	pub verify_signatures: bool,
+}
+
+/// Read `path`, naming it as `what` in errors.
+fn read(path: &Path, what: &str) -> Result<Vec<u8>, String> {
+	fs::read(path).map_err(|e| format!("Reading the {} {} failed: {}", what, path.display(), e))
+}
+
+impl RemoteSignerParams {
+	/// Read the referenced files and check the resulting settings.
+	pub fn config(&self) -> Result<RemoteSignerConfig, String> {
+		let auth_token = self.auth_token_file.as_ref()
+			.map(|path| read(path, "auth token file")
+				.map(|token| String::from_utf8_lossy(&token).trim().to_owned()))
+			.transpose()?;
+		let ca_certificate = self.ca_cert.as_ref()
+			.map(|path| read(path, "CA certificate"))
+			.transpose()?;
+		let client_identity = match &self.client_identity {
+			Some(path) => {
+				let password = match &self.client_identity_password_file {
+					Some(path) => String::from_utf8_lossy(&read(path, "password file")?)
+						.trim_end_matches(&['\r', '\n'][..])
+						.to_owned(),
+					None => String::new(),
+				};
+				Some((read(path, "client identity")?, password))
+			},
+			None => None,
+		};
+		let failover = self.failover.iter()
+			.map(|url| match url.strip_prefix("tssrs+") {
+				// Key types are routed by the `--keystore-uri` alone.
+				Some(stripped) if stripped.contains('#') => Err(format!(
+					"Failover signer {} can't select key types, the fragment of --keystore-uri applies to it", url
+				)),
+				Some(stripped) => Ok(stripped.to_owned()),
+				None => Err(format!("Failover signer {} must be a tssrs+ URI", url)),
+			})
+			.collect::<Result<Vec<_>, _>>()?;
+
+		let config = RemoteSignerConfig {
+			max_retry: self.retries,
+			backoff: Duration::from_millis(self.backoff),
+			max_backoff: Duration::from_millis(self.max_backoff),
+			timeout: Duration::from_millis(self.timeout),
+			auth_token,
+			tls: TlsConfig { ca_certificate, client_identity },
+			failover,
+			cache_ttl: Duration::from_secs(self.cache_ttl),
+			verify_signatures: self.verify_signatures,
+		};
+		config.validate()?;
+		Ok(config)
+	}
+}
+
+/// `build-spec`, optionally with the genesis authorities of remote signers.
+#[derive(Debug, StructOpt)]
+pub struct BuildSpecCmd {
+	#[structopt(flatten)]
+	pub base: sc_cli::BuildSpecCmd,
+
+	/// Build a local testnet whose initial authorities are the keys held by
+	/// these `tssrs+` signers, one authority per signer. Each must hold an
+	/// `aura` sr25519 and a `gran` ed25519 key, the lowest of each is used.
+	/// Only works with the `local` chain. May be repeated.
+	#[structopt(long = "authorities-from", value_name = "URI")]
+	pub authorities_from: Vec<String>,
 }
 
 #[derive(Debug, StructOpt)]
 pub enum Subcommand {
 	/// Key management cli utilities
 	Key(sc_cli::KeySubcommand),
+
+	/// Talk to a remote signer, e.g. to check it is reachable and holds the right keys.
+	Signer(crate::signer::SignerCmd),
 	/// Build a chain specification.
-	BuildSpec(sc_cli::BuildSpecCmd),
+	BuildSpec(BuildSpecCmd),
 
 	/// Validate blocks.
 	CheckBlock(sc_cli::CheckBlockCmd),
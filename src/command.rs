@@ -15,10 +15,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{chain_spec, service};
+use crate::{chain_spec, service, signer};
 use crate::cli::{Cli, Subcommand};
 use sc_cli::{SubstrateCli, RuntimeVersion, Role, ChainSpec};
 use sc_service::PartialComponents;
+use tssrs::client::RemoteSignerConfig;
 use node_template_runtime::Block;
 
 impl SubstrateCli for Cli {
@@ -64,18 +65,37 @@ impl SubstrateCli for Cli {
 /// Parse and run command line arguments
 pub fn run() -> sc_cli::Result<()> {
 	let cli = Cli::from_args();
+	// Only the node itself and the `signer` and `build-spec` subcommands talk
+	// to a remote signer, the others never open a keystore.
+	let no_signer = RemoteSignerConfig::default();
 
 	match &cli.subcommand {
 		Some(Subcommand::Key(cmd)) => cmd.run(&cli),
+		Some(Subcommand::Signer(cmd)) => cmd.run(&cli.remote_signer),
 		Some(Subcommand::BuildSpec(cmd)) => {
-			let runner = cli.create_runner(cmd)?;
-			runner.sync_run(|config| cmd.run(config.chain_spec, config.network))
+			let runner = cli.create_runner(&cmd.base)?;
+			runner.sync_run(|mut config| {
+				if !cmd.authorities_from.is_empty() {
+					if config.chain_spec.id() != "local_testnet" {
+						return Err(sc_cli::Error::Input(format!(
+							"--authorities-from builds a local testnet, not {}", config.chain_spec.id(),
+						)))
+					}
+					let signer_config = cli.remote_signer.config().map_err(sc_cli::Error::Input)?;
+					let authorities = signer::authorities_from(&cmd.authorities_from, &signer_config)?;
+					config.chain_spec = Box::new(
+						chain_spec::local_testnet_config_with_authorities(authorities)
+							.map_err(sc_cli::Error::Input)?
+					);
+				}
+				cmd.base.run(config.chain_spec, config.network)
+			})
 		},
 		Some(Subcommand::CheckBlock(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
 				let PartialComponents { client, task_manager, import_queue, ..}
-					= service::new_partial(&config)?;
+					= service::new_partial(&config, &no_signer)?;
 				Ok((cmd.run(client, import_queue), task_manager))
 			})
 		},
@@ -83,7 +103,7 @@ pub fn run() -> sc_cli::Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
 				let PartialComponents { client, task_manager, ..}
-					= service::new_partial(&config)?;
+					= service::new_partial(&config, &no_signer)?;
 				Ok((cmd.run(client, config.database), task_manager))
 			})
 		},
@@ -91,7 +111,7 @@ pub fn run() -> sc_cli::Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
 				let PartialComponents { client, task_manager, ..}
-					= service::new_partial(&config)?;
+					= service::new_partial(&config, &no_signer)?;
 				Ok((cmd.run(client, config.chain_spec), task_manager))
 			})
 		},
@@ -99,7 +119,7 @@ pub fn run() -> sc_cli::Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
 				let PartialComponents { client, task_manager, import_queue, ..}
-					= service::new_partial(&config)?;
+					= service::new_partial(&config, &no_signer)?;
 				Ok((cmd.run(client, import_queue), task_manager))
 			})
 		},
@@ -111,7 +131,7 @@ pub fn run() -> sc_cli::Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
 				let PartialComponents { client, task_manager, backend, ..}
-					= service::new_partial(&config)?;
+					= service::new_partial(&config, &no_signer)?;
 				Ok((cmd.run(client, backend), task_manager))
 			})
 		},
@@ -126,11 +146,12 @@ pub fn run() -> sc_cli::Result<()> {
 			}
 		},
 		None => {
+			let signer_config = cli.remote_signer.config().map_err(sc_cli::Error::Input)?;
 			let runner = cli.create_runner(&cli.run)?;
 			runner.run_node_until_exit(|config| async move {
 				match config.role {
-					Role::Light => service::new_light(config),
-					_ => service::new_full(config),
+					Role::Light => service::new_light(config, &signer_config),
+					_ => service::new_full(config, cli.require_authority_keys, &signer_config),
 				}.map_err(sc_cli::Error::Service)
 			})
 		}
@@ -13,6 +13,8 @@ use sp_blockchain::{Error as BlockChainError, HeaderMetadata, HeaderBackend};
 use sp_block_builder::BlockBuilder;
 pub use sc_rpc_api::DenyUnsafe;
 use sp_transaction_pool::TransactionPool;
+use jsonrpc_derive::rpc;
+use tssrs::client::{RemoteKeystore, RemoteSignerStatus};
 
 
 /// Full client dependencies.
@@ -23,6 +25,38 @@ pub struct FullDeps<C, P> {
 	pub pool: Arc<P>,
 	/// Whether to deny unsafe calls
 	pub deny_unsafe: DenyUnsafe,
+	/// The remote keystore given by `--keystore-uri`, if any
+	pub remote_keystore: Option<Arc<RemoteKeystore>>,
+}
+
+/// Remote signer RPC methods.
+#[rpc]
+pub trait RemoteSignerApi {
+	/// Endpoints, connection state, last error, latency percentiles and
+	/// cached key counts of the remote signer, `null` without one.
+	#[rpc(name = "remoteSigner_status")]
+	fn status(&self) -> jsonrpc_core::Result<Option<RemoteSignerStatus>>;
+}
+
+/// Implements [`RemoteSignerApi`], gated by [`DenyUnsafe`] as it reveals
+/// the node's setup.
+pub struct RemoteSigner {
+	keystore: Option<Arc<RemoteKeystore>>,
+	deny_unsafe: DenyUnsafe,
+}
+
+impl RemoteSigner {
+	/// Report on `keystore`.
+	pub fn new(keystore: Option<Arc<RemoteKeystore>>, deny_unsafe: DenyUnsafe) -> Self {
+		RemoteSigner { keystore, deny_unsafe }
+	}
+}
+
+impl RemoteSignerApi for RemoteSigner {
+	fn status(&self) -> jsonrpc_core::Result<Option<RemoteSignerStatus>> {
+		self.deny_unsafe.check_if_safe()?;
+		Ok(self.keystore.as_ref().map(|keystore| keystore.status()))
+	}
 }
 
 /// Instantiate all full RPC extensions.
@@ -45,6 +79,7 @@ pub fn create_full<C, P>(
 		client,
 		pool,
 		deny_unsafe,
+		remote_keystore,
 	} = deps;
 
 	io.extend_with(
@@ -55,6 +90,10 @@ pub fn create_full<C, P>(
 		TransactionPaymentApi::to_delegate(TransactionPayment::new(client.clone()))
 	);
 
+	io.extend_with(
+		RemoteSignerApi::to_delegate(RemoteSigner::new(remote_keystore, deny_unsafe))
+	);
+
 	// Extend this RPC with a custom API by using the following syntax.
 	// `YourRpcStruct` should have a reference to a client, which is needed
 	// to call into the runtime.
@@ -2,16 +2,26 @@
 
 use std::sync::Arc;
 use std::time::Duration;
+use std::convert::TryFrom;
 use sc_client_api::{ExecutorProvider, RemoteBackend};
 use node_template_runtime::{self, opaque::Block, RuntimeApi};
-use sc_service::{error::Error as ServiceError, Configuration, TaskManager};
+use sc_service::{error::Error as ServiceError, Configuration, KeystoreContainer, TaskManager};
 use sp_inherents::InherentDataProviders;
 use sc_executor::native_executor_instance;
 pub use sc_executor::NativeExecutor;
 use sp_consensus_aura::sr25519::{AuthorityPair as AuraPair};
 use sc_finality_grandpa::SharedVoterState;
 use sc_telemetry::TelemetrySpan;
-use tssrs::client::RemoteKeystore;
+use prometheus_endpoint::{MetricSource, Opts, Registry, SourcedCounter};
+use tssrs::client::{RemoteKeystore, RemoteSignerConfig};
+use tssrs::composite::CompositeKeystore;
+use sp_core::crypto::{KeyTypeId, Public as _, key_types};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_consensus_aura::AuraApi;
+use sp_finality_grandpa::GrandpaApi;
+use sp_keystore::{SyncCryptoStore, SyncCryptoStorePtr};
+use sp_runtime::generic::BlockId;
 
 // Our native executor instance.
 native_executor_instance!(
@@ -25,7 +35,10 @@ type FullClient = sc_service::TFullClient<Block, RuntimeApi, Executor>;
 type FullBackend = sc_service::TFullBackend<Block>;
 type FullSelectChain = sc_consensus::LongestChain<FullBackend, Block>;
 
-pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponents<
+pub fn new_partial(
+	config: &Configuration,
+	signer_config: &RemoteSignerConfig,
+) -> Result<sc_service::PartialComponents<
 	FullClient, FullBackend, FullSelectChain,
 	sp_consensus::DefaultImportQueue<Block, FullClient>,
 	sc_transaction_pool::FullPool<Block, FullClient>,
@@ -38,18 +51,17 @@ pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponen
 		>,
 		sc_finality_grandpa::LinkHalf<Block, FullClient, FullSelectChain>,
 		Option<TelemetrySpan>,
+		Option<Arc<RemoteKeystore>>,
 	)
 >, ServiceError> {
-	if config.keystore_remote.is_some() {
-		return Err(ServiceError::Other(
-			format!("Remote Keystores are not supported.")))
-	}
 	let inherent_data_providers = sp_inherents::InherentDataProviders::new();
 
-	let (client, backend, keystore_container, task_manager, telemetry_span) =
+	let (client, backend, mut keystore_container, task_manager, telemetry_span) =
 		sc_service::new_full_parts::<Block, RuntimeApi, Executor>(&config)?;
 	let client = Arc::new(client);
 
+	let remote_keystore = set_remote_keystore(config, signer_config, &mut keystore_container)?;
+
 	let select_chain = sc_consensus::LongestChain::new(backend.clone());
 
 	let transaction_pool = sc_transaction_pool::BasicPool::new_full(
@@ -87,42 +99,174 @@ pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponen
 		select_chain,
 		transaction_pool,
 		inherent_data_providers,
-		other: (aura_block_import, grandpa_link, telemetry_span),
+		other: (aura_block_import, grandpa_link, telemetry_span, remote_keystore),
 	})
 }
 
 // WE have implemented this here to integrate the new url-scheme
-fn remote_keystore(url: &String) -> Result<Arc<RemoteKeystore>, String> {
-	if url.starts_with("tssrs+") {
-		RemoteKeystore::open(url[6..].to_string(), None)
-			.map(Arc::new)
-	} else {
-		Err("Remote Keystore not supported.".to_owned())
+// `tssrs+exec:///path/to/signer?arg=...&arg=...` spawns the signer as a child
+// process of the node, talking to it over stdio.
+// A trailing `#aura,gran` only hands the listed key types to the remote
+// signer and keeps all others in the local keystore.
+fn remote_keystore(
+	url: &String,
+	signer_config: &RemoteSignerConfig,
+) -> Result<(Arc<RemoteKeystore>, Option<Vec<KeyTypeId>>), String> {
+	if !url.starts_with("tssrs+") {
+		return Err("Remote Keystore not supported.".to_owned())
+	}
+	let (url, key_types) = match url[6..].rsplitn(2, '#').collect::<Vec<_>>()[..] {
+		[fragment, url] => {
+			let key_types = fragment.split(',')
+				.map(|k| KeyTypeId::try_from(k).map_err(|_| format!("Invalid key type '{}'", k)))
+				.collect::<Result<Vec<_>, _>>()?;
+			(url, Some(key_types))
+		},
+		_ => (&url[6..], None),
+	};
+	RemoteKeystore::open_with_config(url.to_string(), signer_config.clone())
+		.map(|k| (Arc::new(k), key_types))
+}
+
+/// Hook up the remote keystore configured with `--keystore-uri`, if any.
+///
+/// Shared by full and light clients.
+///
+/// Opening it doesn't connect yet, subcommands that never sign don't need
+/// the signer to be reachable.
+///
+/// Returns the remote keystore, e.g. to report its status.
+fn set_remote_keystore(
+	config: &Configuration,
+	signer_config: &RemoteSignerConfig,
+	keystore_container: &mut KeystoreContainer,
+) -> Result<Option<Arc<RemoteKeystore>>, ServiceError> {
+	let url = match &config.keystore_remote {
+		Some(url) => url,
+		None => return Ok(None),
+	};
+	match remote_keystore(url, signer_config) {
+		Ok((k, None)) => {
+			keystore_container.set_remote_keystore(k.clone());
+			Ok(Some(k))
+		},
+		Ok((k, Some(key_types))) => {
+			let local = keystore_container.local_keystore().map(|l| l as SyncCryptoStorePtr);
+			let composite = CompositeKeystore::partition(key_types, k.clone(), local);
+			keystore_container.set_remote_keystore(Arc::new(composite));
+			Ok(Some(k))
+		},
+		Err(e) => {
+			Err(ServiceError::Other(
+				format!("Error hooking up remote keystore for {}: {}", url, e)))
+		}
+	}
+}
+
+/// Signatures and VRF proofs of the remote signer that did not verify.
+#[derive(Clone)]
+struct InvalidSignatures(Arc<RemoteKeystore>);
+
+impl MetricSource for InvalidSignatures {
+	type N = u64;
+
+	fn collect(&self, mut set: impl FnMut(&[&str], Self::N)) {
+		set(&[], self.0.status().invalid_signatures);
 	}
 }
 
+/// Expose the counters of the remote keystore on the prometheus endpoint, if enabled.
+///
+/// Only full nodes verifying the signer's answers have anything to count.
+fn register_metrics(
+	registry: Option<&Registry>,
+	remote_keystore: &Arc<RemoteKeystore>,
+) -> Result<(), ServiceError> {
+	if let Some(registry) = registry {
+		prometheus_endpoint::register(SourcedCounter::new(
+			&Opts::new(
+				"remote_signer_invalid_signatures_total",
+				"Signatures and VRF proofs of the remote signer that did not verify",
+			),
+			InvalidSignatures(remote_keystore.clone()),
+		)?, registry)?;
+	}
+	Ok(())
+}
+
+/// Check the keystore holds the AURA and GRANDPA keys of a current authority.
+///
+/// Without them an authority starts just fine but never authors nor votes, so
+/// this fails if `require` is set and logs a prominent warning otherwise.
+/// Both keys of an authority are looked up with a single `has_keys` call,
+/// authorities are paired up by their position in the two sets.
+fn check_authority_keys(
+	client: &FullClient,
+	keystore: &SyncCryptoStorePtr,
+	remote_keystore: Option<&RemoteKeystore>,
+	require: bool,
+) -> Result<(), ServiceError> {
+	let at = BlockId::Hash(client.info().best_hash);
+	let runtime_error = |e: sp_api::ApiError| ServiceError::Other(format!("Fetching the authority set failed: {}", e));
+	let aura = client.runtime_api().authorities(&at).map_err(runtime_error)?;
+	let grandpa = client.runtime_api().grandpa_authorities(&at).map_err(runtime_error)?;
+
+	let held = aura.iter().zip(grandpa.iter()).any(|(aura, (grandpa, _))| {
+		SyncCryptoStore::has_keys(&**keystore, &[
+			(aura.to_raw_vec(), key_types::AURA),
+			(grandpa.to_raw_vec(), key_types::GRANDPA),
+		])
+	});
+	if held {
+		return Ok(())
+	}
+
+	let message = match remote_keystore.map(RemoteKeystore::status) {
+		Some(status) if !status.connected => format!(
+			"The remote signer {} is unreachable: {}",
+			status.current, status.last_error.unwrap_or_else(|| "no connection".into()),
+		),
+		_ => "The keystore holds no aura and gran key pair of the current authority set".into(),
+	};
+	if require {
+		return Err(ServiceError::Other(message))
+	}
+	log::warn!("⚠️  {}. This node will not author blocks nor vote until it does.", message);
+	Ok(())
+}
+
 /// Builds a new service for a full client.
-pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError> {
+///
+/// With `require_authority_keys` an authority without a key of the current
+/// authority set refuses to start, see [`check_authority_keys`].
+pub fn new_full(
+	mut config: Configuration,
+	require_authority_keys: bool,
+	signer_config: &RemoteSignerConfig,
+) -> Result<TaskManager, ServiceError> {
 	let sc_service::PartialComponents {
 		client,
 		backend,
 		mut task_manager,
 		import_queue,
-		mut keystore_container,
+		keystore_container,
 		select_chain,
 		transaction_pool,
 		inherent_data_providers,
-		other: (block_import, grandpa_link, telemetry_span),
-	} = new_partial(&config)?;
-
-	if let Some(url) = &config.keystore_remote {
-		match remote_keystore(url) {
-			Ok(k) => keystore_container.set_remote_keystore(k),
-			Err(e) => {
-				return Err(ServiceError::Other(
-					format!("Error hooking up remote keystore for {}: {}", url, e)))
-			}
-		};
+		other: (block_import, grandpa_link, telemetry_span, remote_keystore),
+	} = new_partial(&config, signer_config)?;
+
+	if let Some(remote_keystore) = remote_keystore.as_ref().filter(|_| signer_config.verify_signatures) {
+		register_metrics(config.prometheus_registry(), remote_keystore)?;
+	}
+
+	if config.role.is_authority() {
+		check_authority_keys(
+			&client,
+			&keystore_container.sync_keystore(),
+			remote_keystore.as_deref(),
+			require_authority_keys,
+		)?;
 	}
 
 	config.network.extra_sets.push(sc_finality_grandpa::grandpa_peers_set_config());
@@ -160,6 +304,7 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 				client: client.clone(),
 				pool: pool.clone(),
 				deny_unsafe,
+				remote_keystore: remote_keystore.clone(),
 			};
 
 			crate::rpc::create_full(deps)
@@ -262,10 +407,15 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 }
 
 /// Builds a new service for a light client.
-pub fn new_light(mut config: Configuration) -> Result<TaskManager, ServiceError> {
-	let (client, backend, keystore_container, mut task_manager, on_demand, telemetry_span) =
+pub fn new_light(
+	mut config: Configuration,
+	signer_config: &RemoteSignerConfig,
+) -> Result<TaskManager, ServiceError> {
+	let (client, backend, mut keystore_container, mut task_manager, on_demand, telemetry_span) =
 		sc_service::new_light_parts::<Block, RuntimeApi, Executor>(&config)?;
 
+	set_remote_keystore(&config, signer_config, &mut keystore_container)?;
+
 	config.network.extra_sets.push(sc_finality_grandpa::grandpa_peers_set_config());
 
 	let select_chain = sc_consensus::LongestChain::new(backend.clone());
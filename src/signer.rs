@@ -0,0 +1,344 @@
+//! The `signer` subcommand, talking to a remote signer without starting a chain.
+
+use std::{convert::TryFrom, str::FromStr, time::{Duration, Instant}};
+use structopt::StructOpt;
+use sc_cli::{CryptoScheme, CryptoSchemeFlag};
+use sp_consensus_aura::sr25519::AuthorityId as AuraId;
+use sp_finality_grandpa::AuthorityId as GrandpaId;
+use sp_core::{
+	Pair,
+	crypto::{CryptoTypeId, CryptoTypePublicPair, KeyTypeId, Public, key_types},
+	ecdsa, ed25519, sr25519,
+	hexdisplay::HexDisplay,
+};
+use sp_keystore::{Error as CryptoStoreError, SyncCryptoStore};
+use tssrs::client::{RemoteKeystore, RemoteSignerConfig};
+
+use crate::cli::RemoteSignerParams;
+
+/// Hex encoded bytes, with or without `0x` prefix.
+#[derive(Debug, Clone)]
+pub struct Bytes(pub Vec<u8>);
+
+impl FromStr for Bytes {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		sp_core::bytes::from_hex(s)
+			.map(Bytes)
+			.map_err(|e| format!("Invalid hex '{}': {}", s, e))
+	}
+}
+
+fn parse_key_type(s: &str) -> Result<KeyTypeId, String> {
+	KeyTypeId::try_from(s).map_err(|_| format!("Invalid key type '{}'", s))
+}
+
+/// The key to sign with and the message.
+#[derive(Debug, StructOpt)]
+pub struct SignParams {
+	/// Key type, e.g. `aura`.
+	#[structopt(long = "key-type", value_name = "KEY_TYPE", parse(try_from_str = parse_key_type))]
+	pub key_type: KeyTypeId,
+
+	/// Public key to sign with, hex encoded.
+	#[structopt(long = "key", value_name = "PUBLIC")]
+	pub key: Bytes,
+
+	#[structopt(flatten)]
+	pub crypto_scheme: CryptoSchemeFlag,
+
+	/// Message to sign, hex encoded.
+	#[structopt(long = "msg", value_name = "HEX")]
+	pub msg: Bytes,
+}
+
+impl SignParams {
+	fn public_pair(&self) -> CryptoTypePublicPair {
+		CryptoTypePublicPair(crypto_id(self.crypto_scheme.scheme), self.key.0.clone())
+	}
+}
+
+/// What to ask the signer.
+#[derive(Debug, StructOpt)]
+pub enum SignerAction {
+	/// Connect and report the signer's capabilities and latency.
+	Ping,
+
+	/// List all public keys of a key type.
+	ListKeys {
+		/// Key type, e.g. `aura`.
+		#[structopt(long = "key-type", value_name = "KEY_TYPE", parse(try_from_str = parse_key_type))]
+		key_type: KeyTypeId,
+	},
+
+	/// Check the signer holds all the given public keys.
+	HasKeys {
+		/// Key type, e.g. `aura`.
+		#[structopt(long = "key-type", value_name = "KEY_TYPE", parse(try_from_str = parse_key_type))]
+		key_type: KeyTypeId,
+
+		/// Hex encoded public keys.
+		#[structopt(value_name = "PUBLIC", required = true)]
+		keys: Vec<Bytes>,
+	},
+
+	/// Sign a message and print the signature.
+	Sign(SignParams),
+
+	/// Check a signature of the signer locally, signing the message first
+	/// unless a `--signature` is given.
+	Verify {
+		#[structopt(flatten)]
+		params: SignParams,
+
+		/// Hex encoded signature to check instead of a fresh one.
+		#[structopt(long = "signature", value_name = "HEX")]
+		signature: Option<Bytes>,
+	},
+
+	/// Insert the key of a secret URI into the signer.
+	///
+	/// The public key is derived locally first and checked to be held by the
+	/// signer afterwards.
+	Insert {
+		/// Key type, e.g. `aura`.
+		#[structopt(long = "key-type", value_name = "KEY_TYPE", parse(try_from_str = parse_key_type))]
+		key_type: KeyTypeId,
+
+		#[structopt(flatten)]
+		crypto_scheme: CryptoSchemeFlag,
+
+		/// The secret URI, or a file holding it. Asked for if not given.
+		#[structopt(long = "suri", value_name = "SURI")]
+		suri: Option<String>,
+
+		/// Hex encoded public key the secret URI must derive.
+		#[structopt(long = "public", value_name = "PUBLIC")]
+		public: Option<Bytes>,
+	},
+
+	/// Have the signer generate a new key and print its public key.
+	Generate {
+		/// Key type, e.g. `aura`.
+		#[structopt(long = "key-type", value_name = "KEY_TYPE", parse(try_from_str = parse_key_type))]
+		key_type: KeyTypeId,
+
+		#[structopt(flatten)]
+		crypto_scheme: CryptoSchemeFlag,
+
+		/// Secret URI, or a file holding it, to derive the key from instead of
+		/// a random one. The key is inserted like with `insert`, so the signer
+		/// persists it.
+		#[structopt(long = "suri", value_name = "SURI")]
+		suri: Option<String>,
+	},
+}
+
+/// Talk to a remote signer directly, e.g. to check it is set up right.
+#[derive(Debug, StructOpt)]
+pub struct SignerCmd {
+	#[structopt(subcommand)]
+	pub action: SignerAction,
+
+	/// The signer, `tssrs+<url>` as given to `--keystore-uri`.
+	#[structopt(long = "keystore-uri", value_name = "URI")]
+	pub keystore_uri: String,
+}
+
+fn crypto_id(scheme: CryptoScheme) -> CryptoTypeId {
+	match scheme {
+		CryptoScheme::Ed25519 => ed25519::CRYPTO_ID,
+		CryptoScheme::Sr25519 => sr25519::CRYPTO_ID,
+		CryptoScheme::Ecdsa => ecdsa::CRYPTO_ID,
+	}
+}
+
+/// The public key `suri` derives for `scheme`.
+fn public_of(scheme: CryptoScheme, suri: &str) -> sc_cli::Result<Vec<u8>> {
+	fn derive<P: Pair>(suri: &str) -> sc_cli::Result<Vec<u8>> {
+		P::from_string(suri, None)
+			.map(|pair| pair.public().to_raw_vec())
+			.map_err(|e| sc_cli::Error::Input(format!("Invalid secret URI: {:?}", e)))
+	}
+	match scheme {
+		CryptoScheme::Ed25519 => derive::<ed25519::Pair>(suri),
+		CryptoScheme::Sr25519 => derive::<sr25519::Pair>(suri),
+		CryptoScheme::Ecdsa => derive::<ecdsa::Pair>(suri),
+	}
+}
+
+fn failed(e: CryptoStoreError) -> sc_cli::Error {
+	sc_cli::Error::Input(format!("The signer failed: {}", e))
+}
+
+fn hex(bytes: &[u8]) -> String {
+	format!("0x{}", HexDisplay::from(&bytes))
+}
+
+/// Run `call`, returning its result and how long it took.
+fn timed<T>(call: impl FnOnce() -> T) -> (T, Duration) {
+	let start = Instant::now();
+	let result = call();
+	(result, start.elapsed())
+}
+
+/// Open the signer at the `tssrs+` `uri`.
+fn open(uri: &str, config: RemoteSignerConfig) -> sc_cli::Result<RemoteKeystore> {
+	let url = uri.strip_prefix("tssrs+")
+		.ok_or_else(|| sc_cli::Error::Input(format!("{} is no tssrs+ URI", uri)))?;
+	// A `#key,types` fragment only routes keys within the node.
+	let url = url.split('#').next().unwrap_or(url);
+	RemoteKeystore::open_with_config(url.to_owned(), config).map_err(sc_cli::Error::Input)
+}
+
+/// The AURA and GRANDPA keys of each of the signers at `uris`.
+///
+/// Only public keys are asked for, so no secret ever leaves a signer. A
+/// signer holding several keys of a type is represented by the lowest one,
+/// so the same signers always make up the same authority set.
+pub fn authorities_from(
+	uris: &[String],
+	config: &RemoteSignerConfig,
+) -> sc_cli::Result<Vec<(AuraId, GrandpaId)>> {
+	uris.iter().map(|uri| {
+		let keystore = open(uri, config.clone())?;
+		let aura = SyncCryptoStore::sr25519_public_keys(&keystore, key_types::AURA);
+		let grandpa = SyncCryptoStore::ed25519_public_keys(&keystore, key_types::GRANDPA);
+		match (aura.iter().min(), grandpa.iter().min()) {
+			(Some(aura_key), Some(grandpa_key)) => {
+				if aura.len() > 1 || grandpa.len() > 1 {
+					log::warn!(
+						"{} holds {} aura and {} gran key(s), using the lowest of each",
+						uri, aura.len(), grandpa.len(),
+					);
+				}
+				log::info!("Authority of {}: aura {}, gran {}", uri, hex(aura_key.as_ref()), hex(grandpa_key.as_ref()));
+				Ok((AuraId::from(*aura_key), GrandpaId::from(*grandpa_key)))
+			},
+			_ => Err(sc_cli::Error::Input(format!(
+				"{} holds {} aura sr25519 and {} gran ed25519 key(s), expected at least one each",
+				uri, aura.len(), grandpa.len(),
+			))),
+		}
+	}).collect()
+}
+
+/// Insert the key `suri` derives for `scheme`, checking it against `public`
+/// if given and that the signer holds it afterwards.
+///
+/// Returns the public key and how long inserting took.
+fn insert(
+	keystore: &RemoteKeystore,
+	key_type: KeyTypeId,
+	scheme: CryptoScheme,
+	suri: &str,
+	public: Option<&Bytes>,
+) -> sc_cli::Result<(Vec<u8>, Duration)> {
+	let derived = public_of(scheme, suri)?;
+	if public.map_or(false, |public| public.0 != derived) {
+		return Err(sc_cli::Error::Input(format!(
+			"The secret URI derives {}, not the given public key", hex(&derived),
+		)))
+	}
+	let (inserted, took) = timed(|| SyncCryptoStore::insert_unknown(keystore, key_type, suri, &derived));
+	inserted.map_err(|_| sc_cli::Error::Input("The signer refused the key".into()))?;
+	if !SyncCryptoStore::has_keys(keystore, &[(derived.clone(), key_type)]) {
+		return Err(sc_cli::Error::Input(format!("The signer doesn't hold {} after inserting it", hex(&derived))))
+	}
+	Ok((derived, took))
+}
+
+impl SignerCmd {
+	/// Connect to the signer, configured by the node's `params`, and run the action.
+	pub fn run(&self, params: &RemoteSignerParams) -> sc_cli::Result<()> {
+		let config = params.config().map_err(sc_cli::Error::Input)?;
+		let keystore = open(&self.keystore_uri, config)?;
+
+		match &self.action {
+			SignerAction::Ping => {
+				let (capabilities, connect) = timed(|| futures::executor::block_on(keystore.capabilities()));
+				let capabilities = capabilities.map_err(failed)?;
+				let (_, round_trip) = timed(|| futures::executor::block_on(keystore.capabilities()));
+				println!("Connected to {} in {:?}, round trip {:?}", keystore.endpoint(), connect, round_trip);
+				println!("Implementation: {}", capabilities.implementation);
+				println!("Key management: {}", if capabilities.key_management { "yes" } else { "no" });
+				let missing = tssrs::METHODS.iter()
+					.filter(|m| !capabilities.methods.iter().any(|n| n == *m))
+					.collect::<Vec<_>>();
+				if missing.is_empty() {
+					println!("Methods: all {}", capabilities.methods.len());
+				} else {
+					println!("Methods: {} of {}, missing {:?}", capabilities.methods.len(), tssrs::METHODS.len(), missing);
+				}
+			},
+			SignerAction::ListKeys { key_type } => {
+				let (keys, took) = timed(|| SyncCryptoStore::keys(&keystore, *key_type));
+				let keys = keys.map_err(failed)?;
+				for CryptoTypePublicPair(scheme, public) in &keys {
+					println!("{} {}", String::from_utf8_lossy(&scheme.0), hex(public));
+				}
+				println!("{} key(s) in {:?}", keys.len(), took);
+			},
+			SignerAction::HasKeys { key_type, keys } => {
+				let keys = keys.iter().map(|k| (k.0.clone(), *key_type)).collect::<Vec<_>>();
+				let (has, took) = timed(|| SyncCryptoStore::has_keys(&keystore, &keys));
+				println!("{} in {:?}", has, took);
+			},
+			SignerAction::Sign(params) => {
+				let (signature, took) = timed(|| SyncCryptoStore::sign_with(
+					&keystore, params.key_type, &params.public_pair(), &params.msg.0,
+				));
+				println!("{} in {:?}", hex(&signature.map_err(failed)?), took);
+			},
+			SignerAction::Verify { params, signature } => {
+				let signature = match signature {
+					Some(signature) => signature.0.clone(),
+					None => SyncCryptoStore::sign_with(
+						&keystore, params.key_type, &params.public_pair(), &params.msg.0,
+					).map_err(failed)?,
+				};
+				let (public, msg) = (&params.key.0, &params.msg.0);
+				let valid = match params.crypto_scheme.scheme {
+					CryptoScheme::Ed25519 => ed25519::Pair::verify_weak(&signature, msg, public),
+					CryptoScheme::Sr25519 => sr25519::Pair::verify_weak(&signature, msg, public),
+					// `verify_weak` compares against the uncompressed public key.
+					CryptoScheme::Ecdsa => match (
+						ecdsa::Public::try_from(&public[..]), ecdsa::Signature::try_from(&signature[..]),
+					) {
+						(Ok(public), Ok(signature)) => ecdsa::Pair::verify(&signature, msg, &public),
+						_ => false,
+					},
+				};
+				if !valid {
+					return Err(sc_cli::Error::Input(format!("Signature {} does not verify", hex(&signature))))
+				}
+				println!("Signature {} is valid", hex(&signature));
+			},
+			SignerAction::Insert { key_type, crypto_scheme, suri, public } => {
+				let suri = sc_cli::utils::read_uri(suri.as_ref())?;
+				let (derived, took) = insert(&keystore, *key_type, crypto_scheme.scheme, &suri, public.as_ref())?;
+				println!("Inserted {} in {:?}", hex(&derived), took);
+			},
+			// A key generated from a seed only lives in the signer's memory,
+			// so it is inserted like a known one instead.
+			SignerAction::Generate { key_type, crypto_scheme, suri: Some(suri) } => {
+				let suri = sc_cli::utils::read_uri(Some(suri))?;
+				let (public, took) = insert(&keystore, *key_type, crypto_scheme.scheme, &suri, None)?;
+				println!("{} in {:?}", hex(&public), took);
+			},
+			SignerAction::Generate { key_type, crypto_scheme, suri: None } => {
+				let (public, took) = timed(|| match crypto_scheme.scheme {
+					CryptoScheme::Ed25519 => SyncCryptoStore::ed25519_generate_new(&keystore, *key_type, None)
+						.map(|public| public.to_raw_vec()),
+					CryptoScheme::Sr25519 => SyncCryptoStore::sr25519_generate_new(&keystore, *key_type, None)
+						.map(|public| public.to_raw_vec()),
+					CryptoScheme::Ecdsa => SyncCryptoStore::ecdsa_generate_new(&keystore, *key_type, None)
+						.map(|public| public.to_raw_vec()),
+				});
+				println!("{} in {:?}", hex(&public.map_err(failed)?), took);
+			},
+		}
+		Ok(())
+	}
+}
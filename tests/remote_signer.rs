@@ -0,0 +1,123 @@
+//! Runs the dev chain with its only authority key held by a remote signer.
+
+use std::{
+	io::{BufRead, BufReader},
+	process::{Child, Command, Stdio},
+	sync::mpsc::{self, Receiver},
+	thread,
+	time::{Duration, Instant},
+};
+use futures::StreamExt;
+use jsonrpc_ws_server::{Server, ServerBuilder, jsonrpc_core::IoHandler};
+use sc_keystore::LocalKeystore;
+use sp_core::crypto::key_types::{AURA, GRANDPA};
+use sp_keystore::SyncCryptoStore;
+use tssrs::{RemoteSignerApi, server::GenericRemoteSignerServer};
+
+/// Serve `//Alice`'s authority keys from memory over websocket.
+fn start_signer(runtime: &tokio::runtime::Runtime) -> Server {
+	let keystore = LocalKeystore::in_memory();
+	SyncCryptoStore::sr25519_generate_new(&keystore, AURA, Some("//Alice")).unwrap();
+	SyncCryptoStore::ed25519_generate_new(&keystore, GRANDPA, Some("//Alice")).unwrap();
+
+	let (server, mut receiver) = GenericRemoteSignerServer::proxy(keystore);
+	runtime.spawn(async move { while receiver.next().await.is_some() {} });
+
+	let mut io = IoHandler::new();
+	io.extend_with(RemoteSignerApi::to_delegate(server));
+	ServerBuilder::new(io).start(&"127.0.0.1:0".parse().unwrap()).unwrap()
+}
+
+/// Kills the node, even if the test fails.
+struct Node(Child);
+
+impl Drop for Node {
+	fn drop(&mut self) {
+		let _ = self.0.kill();
+		let _ = self.0.wait();
+	}
+}
+
+/// The number following `marker` in `line`.
+fn number_after(line: &str, marker: &str) -> Option<u64> {
+	let start = line.find(marker)? + marker.len();
+	line[start..].split(|c: char| !c.is_ascii_digit()).next()?.parse().ok()
+}
+
+/// Best and finalized block numbers, as the node's informant logs them.
+fn watch(node: &mut Node) -> Receiver<(u64, u64)> {
+	let stderr = node.0.stderr.take().expect("stderr is piped");
+	let (sender, receiver) = mpsc::channel();
+	thread::spawn(move || {
+		// Keep draining, or the node blocks on a full pipe.
+		for line in BufReader::new(stderr).lines().filter_map(Result::ok) {
+			if let (Some(best), Some(finalized)) =
+				(number_after(&line, "best: #"), number_after(&line, "finalized #"))
+			{
+				let _ = sender.send((best, finalized));
+			}
+		}
+	});
+	receiver
+}
+
+/// The first report within `timeout` that satisfies `done`.
+fn wait_for(
+	reports: &Receiver<(u64, u64)>,
+	timeout: Duration,
+	done: impl Fn(u64, u64) -> bool,
+) -> Option<(u64, u64)> {
+	let deadline = Instant::now() + timeout;
+	while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+		match reports.recv_timeout(left) {
+			Ok((best, finalized)) if done(best, finalized) => return Some((best, finalized)),
+			Ok(_) => {},
+			Err(_) => return None,
+		}
+	}
+	None
+}
+
+/// The last report within `period`.
+fn latest(reports: &Receiver<(u64, u64)>, period: Duration) -> Option<(u64, u64)> {
+	let deadline = Instant::now() + period;
+	let mut latest = None;
+	while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+		match reports.recv_timeout(left) {
+			Ok(report) => latest = Some(report),
+			Err(_) => break,
+		}
+	}
+	latest
+}
+
+#[test]
+fn authors_and_finalizes_with_a_remote_signer() {
+	let runtime = tokio::runtime::Runtime::new().unwrap();
+	let signer = start_signer(&runtime);
+	let base_path = tempfile::tempdir().unwrap();
+
+	// No `--dev`: it would put `//Alice` into the local keystore as well.
+	let mut node = Node(Command::new(env!("CARGO_BIN_EXE_node-tssrs-example"))
+		.args(&["--chain", "dev", "--validator", "--force-authoring"])
+		.args(&["--port", "0", "--rpc-port", "0", "--ws-port", "0", "--no-prometheus", "--no-telemetry"])
+		.arg("--base-path").arg(base_path.path())
+		.arg("--keystore-uri").arg(format!("tssrs+ws://{}", signer.addr()))
+		.stderr(Stdio::piped())
+		.spawn()
+		.unwrap());
+	let reports = watch(&mut node);
+
+	wait_for(&reports, Duration::from_secs(120), |best, finalized| best >= 3 && finalized >= 1)
+		.expect("The node neither authored nor finalized blocks with the remote signer");
+
+	signer.close();
+
+	// A block in flight may still make it, then authoring must stop.
+	let (settled, _) = latest(&reports, Duration::from_secs(15))
+		.expect("The node stopped reporting");
+	let (later, _) = latest(&reports, Duration::from_secs(30))
+		.expect("The node stopped reporting");
+	assert_eq!(later, settled, "The node kept authoring without its signer");
+	assert!(node.0.try_wait().unwrap().is_none(), "The node exited without its signer");
+}